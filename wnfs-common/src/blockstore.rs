@@ -107,21 +107,46 @@ pub trait BlockStore: CondSync {
         cid: &Cid,
     ) -> impl Future<Output = Result<bool, BlockStoreError>> + CondSend;
 
-    // This should be the same in all implementations of BlockStore
+    /// Computes the CID that `put_block` would store the given bytes under.
+    ///
+    /// By default this hashes the bytes with Blake3. Use a [`HashingBlockStore`] to choose
+    /// a different hash function for any block store.
     fn create_cid(&self, bytes: &[u8], codec: u64) -> Result<Cid, BlockStoreError> {
-        // If there are too many bytes, abandon this task
-        if bytes.len() > MAX_BLOCK_SIZE {
-            return Err(BlockStoreError::MaximumBlockSizeExceeded(bytes.len()));
-        }
+        create_cid_with(Code::Blake3_256, bytes, codec)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Computes a V1 CID for given bytes and codec using the given hash function.
+pub fn create_cid_with(code: Code, bytes: &[u8], codec: u64) -> Result<Cid, BlockStoreError> {
+    // If there are too many bytes, abandon this task
+    if bytes.len() > MAX_BLOCK_SIZE {
+        return Err(BlockStoreError::MaximumBlockSizeExceeded(bytes.len()));
+    }
 
-        // Compute the Blake3 hash of the bytes
-        let hash = Code::Blake3_256.digest(bytes);
+    // Compute the hash of the bytes
+    let hash = code.digest(bytes);
+
+    // Represent the hash as a V1 CID
+    let cid = Cid::new(Version::V1, codec, hash)?;
+
+    Ok(cid)
+}
 
-        // Represent the hash as a V1 CID
-        let cid = Cid::new(Version::V1, codec, hash)?;
+/// Checks that the given bytes hash to the digest in given CID, using the
+/// hash function the CID was created with.
+pub fn verify_cid(cid: &Cid, bytes: &[u8]) -> Result<(), BlockStoreError> {
+    let code = Code::try_from(cid.hash().code())
+        .map_err(|_| BlockStoreError::UnsupportedHashCode(cid.hash().code()))?;
 
-        Ok(cid)
+    if code.digest(bytes) != *cid.hash() {
+        return Err(BlockStoreError::BlockDoesNotMatchCID(*cid));
     }
+
+    Ok(())
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -188,6 +213,41 @@ impl<B: BlockStore> BlockStore for Box<B> {
     }
 }
 
+/// A block store wrapper that hashes new blocks with a configurable hash function.
+///
+/// Loading blocks is unaffected, so DAGs with blocks hashed by different hash
+/// functions can be read as usual.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs_common::{
+///     libipld::multihash::Code, BlockStore, HashingBlockStore, MemoryBlockStore, CODEC_RAW,
+/// };
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = HashingBlockStore::new(MemoryBlockStore::new(), Code::Sha2_256);
+///     let cid = store.put_block(b"hello world".to_vec(), CODEC_RAW).await.unwrap();
+///
+///     assert_eq!(cid.hash().code(), u64::from(Code::Sha2_256));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HashingBlockStore<B> {
+    inner: B,
+    code: Code,
+}
+
+/// A block store wrapper that checks that blocks match their CIDs when they are
+/// stored or loaded.
+///
+/// The digest is recomputed with whatever hash function the CID specifies.
+#[derive(Debug, Clone)]
+pub struct VerifyingBlockStore<B> {
+    inner: B,
+}
+
 /// An in-memory block store to simulate IPFS.
 ///
 /// IPFS is basically a glorified HashMap.
@@ -199,6 +259,91 @@ pub struct MemoryBlockStore(
     pub(crate) Arc<Mutex<HashMap<Cid, Bytes>>>,
 );
 
+impl<B: BlockStore> HashingBlockStore<B> {
+    /// Wraps given block store, hashing new blocks with given hash function.
+    pub fn new(inner: B, code: Code) -> Self {
+        Self { inner, code }
+    }
+
+    /// Returns the hash function used for new blocks.
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Returns the wrapped block store.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: BlockStore> BlockStore for HashingBlockStore<B> {
+    async fn get_block(&self, cid: &Cid) -> Result<Bytes, BlockStoreError> {
+        self.inner.get_block(cid).await
+    }
+
+    async fn put_block_keyed(
+        &self,
+        cid: Cid,
+        bytes: impl Into<Bytes> + CondSend,
+    ) -> Result<(), BlockStoreError> {
+        self.inner.put_block_keyed(cid, bytes).await
+    }
+
+    async fn has_block(&self, cid: &Cid) -> Result<bool, BlockStoreError> {
+        self.inner.has_block(cid).await
+    }
+
+    fn create_cid(&self, bytes: &[u8], codec: u64) -> Result<Cid, BlockStoreError> {
+        create_cid_with(self.code, bytes, codec)
+    }
+}
+
+impl<B: BlockStore> VerifyingBlockStore<B> {
+    /// Wraps given block store, verifying all blocks going in and out of it.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped block store.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: BlockStore> BlockStore for VerifyingBlockStore<B> {
+    async fn get_block(&self, cid: &Cid) -> Result<Bytes, BlockStoreError> {
+        let bytes = self.inner.get_block(cid).await?;
+        verify_cid(cid, &bytes)?;
+        Ok(bytes)
+    }
+
+    async fn put_block(
+        &self,
+        bytes: impl Into<Bytes> + CondSend,
+        codec: u64,
+    ) -> Result<Cid, BlockStoreError> {
+        self.inner.put_block(bytes, codec).await
+    }
+
+    async fn put_block_keyed(
+        &self,
+        cid: Cid,
+        bytes: impl Into<Bytes> + CondSend,
+    ) -> Result<(), BlockStoreError> {
+        let bytes = bytes.into();
+        verify_cid(&cid, &bytes)?;
+        self.inner.put_block_keyed(cid, bytes).await
+    }
+
+    async fn has_block(&self, cid: &Cid) -> Result<bool, BlockStoreError> {
+        self.inner.has_block(cid).await
+    }
+
+    fn create_cid(&self, bytes: &[u8], codec: u64) -> Result<Cid, BlockStoreError> {
+        self.inner.create_cid(bytes, codec)
+    }
+}
+
 impl MemoryBlockStore {
     /// Creates a new in-memory block store.
     pub fn new() -> Self {
@@ -322,4 +467,50 @@ mod tests {
         bs_serialization_test::<MemoryBlockStore>(store).await?;
         Ok(())
     }
+
+    #[async_std::test]
+    async fn hashing_blockstore() -> Result<()> {
+        let store = &HashingBlockStore::new(MemoryBlockStore::new(), Code::Sha2_256);
+        bs_retrieval_test::<HashingBlockStore<MemoryBlockStore>>(store).await?;
+        bs_duplication_test::<HashingBlockStore<MemoryBlockStore>>(store).await?;
+
+        let cid = store.put_block(b"hello world".to_vec(), CODEC_RAW).await?;
+        assert_eq!(
+            cid,
+            create_cid_with(Code::Sha2_256, b"hello world", CODEC_RAW)?
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn verifying_blockstore_accepts_mixed_hashes() -> Result<()> {
+        let inner = MemoryBlockStore::new();
+        let blake3_cid = inner.put_block(b"blake3".to_vec(), CODEC_RAW).await?;
+        let sha2_cid = HashingBlockStore::new(inner.clone(), Code::Sha2_256)
+            .put_block(b"sha2".to_vec(), CODEC_RAW)
+            .await?;
+
+        let store = VerifyingBlockStore::new(inner);
+        assert_eq!(store.get_block(&blake3_cid).await?, &b"blake3"[..]);
+        assert_eq!(store.get_block(&sha2_cid).await?, &b"sha2"[..]);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn verifying_blockstore_rejects_mismatched_blocks() -> Result<()> {
+        let inner = MemoryBlockStore::new();
+        let cid = create_cid_with(Code::Sha2_256, b"hello", CODEC_RAW)?;
+        inner.put_block_keyed(cid, b"goodbye".to_vec()).await?;
+
+        let store = VerifyingBlockStore::new(inner);
+        assert!(matches!(
+            store.get_block(&cid).await,
+            Err(BlockStoreError::BlockDoesNotMatchCID(_))
+        ));
+        assert!(matches!(
+            store.put_block_keyed(cid, b"goodbye".to_vec()).await,
+            Err(BlockStoreError::BlockDoesNotMatchCID(_))
+        ));
+        Ok(())
+    }
 }
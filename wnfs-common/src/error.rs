@@ -16,6 +16,12 @@ pub enum BlockStoreError {
     #[error("Cannot find specified CID in block store: {0}")]
     CIDNotFound(Cid),
 
+    #[error("Block doesn't match the digest in its CID: {0}")]
+    BlockDoesNotMatchCID(Cid),
+
+    #[error("Unsupported multihash code: {0:#x}")]
+    UnsupportedHashCode(u64),
+
     #[error("CID error during blockstore operation: {0}")]
     CIDError(#[from] cid::Error),
 
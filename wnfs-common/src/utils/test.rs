@@ -1,11 +1,14 @@
 use super::{Arc, CondSend, CondSync};
-use crate::{BlockStore, BlockStoreError, MemoryBlockStore, CODEC_DAG_CBOR, CODEC_RAW};
+use crate::{
+    BlockStore, BlockStoreError, MemoryBlockStore, CODEC_DAG_CBOR, CODEC_DAG_PB, CODEC_RAW,
+};
 use anyhow::Result;
 use base64_serde::base64_serde_type;
 use bytes::Bytes;
 use libipld::{
     cbor::DagCborCodec,
     json::DagJsonCodec,
+    pb::DagPbCodec,
     prelude::{Decode, Encode, References},
     Cid, Ipld, IpldCodec,
 };
@@ -65,6 +68,7 @@ impl SnapshotBlockStore {
     pub fn handle_block(&self, cid: &Cid, bytes: &Bytes) -> Result<BlockSnapshot> {
         let ipld = match cid.codec() {
             CODEC_DAG_CBOR => Ipld::decode(DagCborCodec, &mut Cursor::new(bytes))?,
+            CODEC_DAG_PB => Ipld::decode(DagPbCodec, &mut Cursor::new(bytes))?,
             CODEC_RAW => match self.block_handlers.lock().get(cid) {
                 Some(func) => func.convert(bytes)?,
                 None => Ipld::Bytes(bytes.to_vec()),
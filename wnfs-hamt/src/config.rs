@@ -0,0 +1,89 @@
+use crate::constants::{HAMT_BIT_WIDTH, HAMT_VALUES_BUCKET_SIZE};
+use std::fmt::Debug;
+use wnfs_common::utils::CondSync;
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The structural parameters of a HAMT.
+///
+/// Nodes with different configurations can't be mixed, so the configuration is part of the
+/// [`Node`](crate::Node) and [`Hamt`](crate::Hamt) types.
+///
+/// Loading a [`Hamt`](crate::Hamt) and diffing or merging nodes also checks at runtime that
+/// the pairs were placed with the configured bit width and hasher.
+///
+/// Only the structural parameters are configurable. Nodes are still encoded in WNFS's own
+/// layout, so this doesn't make them readable by other HAMT implementations such as
+/// go-hamt-ipld, even with matching parameters.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs_hamt::{Config, DefaultConfig, HamtConfig};
+///
+/// assert_eq!(DefaultConfig::BIT_WIDTH, 4);
+/// assert_eq!(DefaultConfig::BUCKET_SIZE, 3);
+/// assert_eq!(Config::<8, 1>::FANOUT, 256);
+/// ```
+pub trait HamtConfig: Debug + CondSync + 'static {
+    /// The number of hash bits used to index into a node at each level, between 1 and 8.
+    const BIT_WIDTH: u8;
+
+    /// The maximum number of pairs stored in a bucket before it gets split into a child node.
+    const BUCKET_SIZE: usize;
+
+    /// The number of pointers a node can hold, `2^BIT_WIDTH`.
+    const FANOUT: usize = 1 << Self::BIT_WIDTH;
+
+    /// The number of bytes the bitmask of a node takes up when serialized.
+    const BITMASK_BYTE_SIZE: usize = if Self::FANOUT < 8 {
+        1
+    } else {
+        Self::FANOUT / 8
+    };
+}
+
+/// A [`HamtConfig`] with the bit width and bucket size given as const generics.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use wnfs_hamt::{Config, Node};
+/// use wnfs_common::MemoryBlockStore;
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &MemoryBlockStore::new();
+///     let mut node = Arc::new(Node::<String, usize, blake3::Hasher, Config<8, 1>>::default());
+///
+///     node.set("key".into(), 42, store).await.unwrap();
+///     assert_eq!(node.get(&String::from("key"), store).await.unwrap(), Some(&42));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config<const BIT_WIDTH: u8, const BUCKET_SIZE: usize>;
+
+/// The configuration used by WNFS, with a bit width of 4 and a bucket size of 3.
+pub type DefaultConfig = Config<HAMT_BIT_WIDTH, HAMT_VALUES_BUCKET_SIZE>;
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl<const BIT_WIDTH: u8, const BUCKET_SIZE: usize> HamtConfig for Config<BIT_WIDTH, BUCKET_SIZE> {
+    const BIT_WIDTH: u8 = {
+        assert!(
+            BIT_WIDTH >= 1 && BIT_WIDTH <= 8,
+            "HAMT bit width must be between 1 and 8"
+        );
+        BIT_WIDTH
+    };
+
+    const BUCKET_SIZE: usize = {
+        assert!(BUCKET_SIZE >= 1, "HAMT bucket size must be at least 1");
+        BUCKET_SIZE
+    };
+}
@@ -1,6 +1,9 @@
 use semver::Version;
 
-pub const HAMT_BITMASK_BIT_SIZE: usize = 16;
+pub const HAMT_BIT_WIDTH: u8 = 4;
+pub const HAMT_BITMASK_BIT_SIZE: usize = 1 << HAMT_BIT_WIDTH;
 pub const HAMT_BITMASK_BYTE_SIZE: usize = HAMT_BITMASK_BIT_SIZE / 8;
 pub const HAMT_VALUES_BUCKET_SIZE: usize = 3;
+/// The size of the largest bitmask supported, for a bit width of 8.
+pub const HAMT_MAX_BITMASK_BYTE_SIZE: usize = 32;
 pub const HAMT_VERSION: Version = Version::new(0, 1, 0);
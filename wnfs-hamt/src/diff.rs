@@ -1,9 +1,9 @@
 use super::HashNibbles;
-use crate::{Hasher, Node, Pair, Pointer, HAMT_BITMASK_BIT_SIZE};
+use crate::{hash::cmp_bits, HamtConfig, HashPrefix, Hasher, Node, Pair, Pointer};
 use anyhow::{Ok, Result};
 use async_recursion::async_recursion;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{collections::HashMap, hash::Hash, mem};
+use std::{cmp::Ordering, collections::HashMap, hash::Hash, mem};
 use wnfs_common::{
     utils::{Arc, CondSync},
     BlockStore, Link, Storable,
@@ -98,9 +98,9 @@ impl<K, V> KeyValueChange<K, V> {
 ///    println!("Changes {:#?}", changes);
 /// }
 /// ```
-pub async fn diff<K, V, H>(
-    main_link: Link<Arc<Node<K, V, H>>>,
-    other_link: Link<Arc<Node<K, V, H>>>,
+pub async fn diff<K, V, H, C>(
+    main_link: Link<Arc<Node<K, V, H, C>>>,
+    other_link: Link<Arc<Node<K, V, H, C>>>,
     store: &impl BlockStore,
 ) -> Result<Vec<KeyValueChange<K, V>>>
 where
@@ -109,15 +109,16 @@ where
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     diff_helper(main_link, other_link, 1, store).await
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
 #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
-pub async fn diff_helper<K, V, H>(
-    main_link: Link<Arc<Node<K, V, H>>>,
-    other_link: Link<Arc<Node<K, V, H>>>,
+pub async fn diff_helper<K, V, H, C>(
+    main_link: Link<Arc<Node<K, V, H, C>>>,
+    other_link: Link<Arc<Node<K, V, H, C>>>,
     depth: usize,
     store: &impl BlockStore,
 ) -> Result<Vec<KeyValueChange<K, V>>>
@@ -127,6 +128,7 @@ where
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     // If Cids are available, check to see if they are equal so we can skip further comparisons.
     if let (Some(cid), Some(cid2)) = (main_link.get_cid(), other_link.get_cid()) {
@@ -140,8 +142,13 @@ where
 
     let mut other_node = other_link.resolve_owned_value(store).await?;
 
+    // The types only guarantee a matching config if the nodes were created with it,
+    // so loaded nodes are checked against it here.
+    main_node.check_placement(depth - 1)?;
+    other_node.check_placement(depth - 1)?;
+
     let mut changes = vec![];
-    for index in 0..HAMT_BITMASK_BIT_SIZE {
+    for index in 0..C::FANOUT {
         match (main_node.bitmask[index], other_node.bitmask[index]) {
             (true, false) => {
                 // Main has a value, other doesn't.
@@ -192,8 +199,186 @@ where
     Ok(changes)
 }
 
-async fn generate_add_or_remove_changes<K, V, H>(
-    node_pointer: &Pointer<K, V, H>,
+/// Compare two nodes and get the key-value changes made to the main node, restricted to
+/// the keys whose hashes start with `hashprefix`.
+///
+/// Only the nodes on the path to the prefix and the subtrees below it are loaded,
+/// so this is much cheaper than filtering the result of [`diff`] for large tries.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use wnfs_hamt::{diff_at, HashPrefix, Hasher, Node};
+/// use wnfs_common::{Link, MemoryBlockStore};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &MemoryBlockStore::new();
+///     let main_node = &mut Arc::new(Node::<[u8; 4], String>::default());
+///     for i in 0u32..100 {
+///         main_node
+///             .set(i.to_le_bytes(), i.to_string(), store)
+///             .await
+///             .unwrap();
+///     }
+///
+///     let other_node = &mut Arc::new(Node::<[u8; 4], String>::default());
+///
+///     let hashprefix = HashPrefix::with_length(blake3::Hasher::hash(&42_u32.to_le_bytes()), 1);
+///     let changes = diff_at(
+///         Link::from(Arc::clone(main_node)),
+///         Link::from(Arc::clone(other_node)),
+///         &hashprefix,
+///         store,
+///     )
+///     .await
+///     .unwrap();
+///
+///     assert!(changes.iter().any(|change| change.key == 42_u32.to_le_bytes()));
+///     assert!(changes
+///         .iter()
+///         .all(|change| hashprefix.is_prefix_of(&blake3::Hasher::hash(&change.key))));
+/// }
+/// ```
+pub async fn diff_at<K, V, H, C>(
+    main_link: Link<Arc<Node<K, V, H, C>>>,
+    other_link: Link<Arc<Node<K, V, H, C>>>,
+    hashprefix: &HashPrefix,
+    store: &impl BlockStore,
+) -> Result<Vec<KeyValueChange<K, V>>>
+where
+    K: Storable + Clone + Eq + Hash + AsRef<[u8]> + CondSync,
+    V: Storable + Clone + Eq + CondSync,
+    K::Serializable: Serialize + DeserializeOwned,
+    V::Serializable: Serialize + DeserializeOwned,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    let changes = diff_at_helper(
+        main_link,
+        other_link,
+        hashprefix,
+        HashPrefix::default(),
+        store,
+    )
+    .await?;
+
+    // Buckets above the end of the prefix may hold keys outside of it.
+    Ok(changes
+        .into_iter()
+        .filter(|change| hashprefix.is_prefix_of(&H::hash(&change.key)))
+        .collect())
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+#[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+async fn diff_at_helper<K, V, H, C>(
+    main_link: Link<Arc<Node<K, V, H, C>>>,
+    other_link: Link<Arc<Node<K, V, H, C>>>,
+    hashprefix: &HashPrefix,
+    path: HashPrefix,
+    store: &impl BlockStore,
+) -> Result<Vec<KeyValueChange<K, V>>>
+where
+    K: Storable + Clone + Eq + Hash + AsRef<[u8]> + CondSync,
+    V: Storable + Clone + Eq + CondSync,
+    K::Serializable: Serialize + DeserializeOwned,
+    V::Serializable: Serialize + DeserializeOwned,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    let depth = path.bit_len() / C::BIT_WIDTH as usize + 1;
+
+    // Everything below this node is within the prefix.
+    if path.bit_len() >= hashprefix.bit_len() {
+        return diff_helper(main_link, other_link, depth, store).await;
+    }
+
+    if let (Some(cid), Some(cid2)) = (main_link.get_cid(), other_link.get_cid()) {
+        if cid == cid2 {
+            return Ok(vec![]);
+        }
+    }
+
+    let mut main_node = main_link.resolve_owned_value(store).await?;
+    let mut other_node = other_link.resolve_owned_value(store).await?;
+
+    let mut changes = vec![];
+    for index in 0..C::FANOUT {
+        let mut child_path = path.clone();
+        child_path.push_bits(index as u8, C::BIT_WIDTH);
+
+        let common_length = child_path.bit_len().min(hashprefix.bit_len());
+        if cmp_bits(&child_path.digest, &hashprefix.digest, common_length) != Ordering::Equal {
+            continue;
+        }
+
+        let main_pointer = take_pointer(&mut main_node, index);
+        let other_pointer = take_pointer(&mut other_node, index);
+        let (main_pointer, other_pointer) = match (main_pointer, other_pointer) {
+            (None, None) => continue,
+            (Some(main @ Pointer::Values(_)), Some(other @ Pointer::Values(_))) => {
+                changes.extend(pointers_diff(main, other, depth, store).await?);
+                continue;
+            }
+            pointers => pointers,
+        };
+
+        // A missing side is diffed as an empty node, so the other side is only
+        // loaded along the prefix as well.
+        let main_link = pointer_to_link(main_pointer, depth, store).await?;
+        let other_link = pointer_to_link(other_pointer, depth, store).await?;
+        changes.extend(diff_at_helper(main_link, other_link, hashprefix, child_path, store).await?);
+    }
+
+    Ok(changes)
+}
+
+fn take_pointer<K, V, H, C>(
+    node: &mut Arc<Node<K, V, H, C>>,
+    index: usize,
+) -> Option<Pointer<K, V, H, C>>
+where
+    K: Clone + CondSync,
+    V: Clone + CondSync,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    if !node.bitmask[index] {
+        return None;
+    }
+
+    let value_index = node.get_value_index(index);
+    Some(mem::take(
+        Arc::make_mut(node).pointers.get_mut(value_index).unwrap(),
+    ))
+}
+
+async fn pointer_to_link<K, V, H, C>(
+    pointer: Option<Pointer<K, V, H, C>>,
+    depth: usize,
+    store: &impl BlockStore,
+) -> Result<Link<Arc<Node<K, V, H, C>>>>
+where
+    K: Storable + Clone + Eq + Hash + AsRef<[u8]> + CondSync,
+    V: Storable + Clone + Eq + CondSync,
+    K::Serializable: Serialize + DeserializeOwned,
+    V::Serializable: Serialize + DeserializeOwned,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    Ok(match pointer {
+        None => Link::from(Arc::new(Node::default())),
+        Some(Pointer::Values(values)) => {
+            Link::from(create_node_from_pairs(values, depth, store).await?)
+        }
+        Some(Pointer::Link(link)) => link,
+    })
+}
+
+async fn generate_add_or_remove_changes<K, V, H, C>(
+    node_pointer: &Pointer<K, V, H, C>,
     r#type: ChangeType,
     store: &impl BlockStore,
 ) -> Result<Vec<KeyValueChange<K, V>>>
@@ -203,6 +388,7 @@ where
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     match node_pointer {
         Pointer::Values(values) => Ok(values
@@ -233,9 +419,9 @@ where
     }
 }
 
-async fn pointers_diff<K, V, H>(
-    main_pointer: Pointer<K, V, H>,
-    other_pointer: Pointer<K, V, H>,
+async fn pointers_diff<K, V, H, C>(
+    main_pointer: Pointer<K, V, H, C>,
+    other_pointer: Pointer<K, V, H, C>,
     depth: usize,
     store: &impl BlockStore,
 ) -> Result<Vec<KeyValueChange<K, V>>>
@@ -245,6 +431,7 @@ where
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     match (main_pointer, other_pointer) {
         (Pointer::Link(main_link), Pointer::Link(other_link)) => {
@@ -306,22 +493,23 @@ where
     }
 }
 
-async fn create_node_from_pairs<K, V, H>(
+async fn create_node_from_pairs<K, V, H, C>(
     values: Vec<Pair<K, V>>,
     depth: usize,
     store: &impl BlockStore,
-) -> Result<Arc<Node<K, V, H>>>
+) -> Result<Arc<Node<K, V, H, C>>>
 where
     K: Storable + Clone + Eq + Hash + AsRef<[u8]> + CondSync,
     V: Storable + Clone + Eq + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
-    let mut node = Arc::new(Node::<_, _, H>::default());
+    let mut node = Arc::new(Node::<_, _, H, C>::default());
     for Pair { key, value } in values {
         let digest = &H::hash(&key);
-        let hashnibbles = &mut HashNibbles::with_cursor(digest, depth);
+        let hashnibbles = &mut HashNibbles::with_bit_width(digest, depth, C::BIT_WIDTH);
         node.set_value(hashnibbles, key, value, store).await?;
     }
     Ok(node)
@@ -334,6 +522,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::{ChangeType::*, *};
+    use crate::{error::HamtError, strategies::ReversedHasher, Config};
     use helper::*;
     use std::collections::BTreeSet;
     use wnfs_common::MemoryBlockStore;
@@ -596,19 +785,133 @@ mod tests {
             ]
         );
     }
+
+    #[async_std::test]
+    async fn diff_works_with_custom_config() {
+        let store = &MemoryBlockStore::default();
+
+        let mut main_node = Arc::new(Node::<String, u64, blake3::Hasher, Config<2, 1>>::default());
+        let mut other_node = Arc::clone(&main_node);
+        for i in 0..100 {
+            main_node.set(i.to_string(), i, store).await.unwrap();
+        }
+        for i in 50..150 {
+            let value = if i < 60 { i + 1 } else { i };
+            other_node.set(i.to_string(), value, store).await.unwrap();
+        }
+
+        let changes = diff(Link::from(main_node), Link::from(other_node), store)
+            .await
+            .unwrap();
+
+        let count = |r#type| changes.iter().filter(|c| c.r#type == r#type).count();
+        assert_eq!(count(Add), 50);
+        assert_eq!(count(Remove), 50);
+        assert_eq!(count(Modify), 10);
+    }
+
+    #[async_std::test]
+    async fn diff_at_only_loads_blocks_under_prefix() {
+        let store = &MemoryBlockStore::default();
+
+        let mut main_node = Arc::new(Node::<String, u64>::default());
+        for i in 0..1000 {
+            main_node.set(i.to_string(), i, store).await.unwrap();
+        }
+        let mut other_node = Arc::clone(&main_node);
+        for i in 0..100 {
+            other_node.remove(&i.to_string(), store).await.unwrap();
+            other_node
+                .set(format!("other {i}"), i, store)
+                .await
+                .unwrap();
+        }
+        let main_cid = main_node.store(store).await.unwrap();
+        let other_cid = other_node.store(store).await.unwrap();
+
+        let hashprefix = HashPrefix::with_length(blake3::Hasher::hash(&"42"), 1);
+        let index = hashprefix.get(0).unwrap() as usize;
+
+        // A store with only the root blocks and the subtrees at the prefix
+        let partial_store = &MemoryBlockStore::default();
+        for cid in [main_cid, other_cid] {
+            let root = Node::<String, u64>::load(&cid, store).await.unwrap();
+            let bytes = store.get_block(&cid).await.unwrap();
+            partial_store.put_block_keyed(cid, bytes).await.unwrap();
+
+            let mut stack = match &root.pointers[root.get_value_index(index)] {
+                Pointer::Link(link) => vec![*link.get_cid().unwrap()],
+                Pointer::Values(_) => vec![],
+            };
+            while let Some(cid) = stack.pop() {
+                let bytes = store.get_block(&cid).await.unwrap();
+                partial_store.put_block_keyed(cid, bytes).await.unwrap();
+                let node = Node::<String, u64>::load(&cid, store).await.unwrap();
+                for pointer in node.pointers.iter() {
+                    if let Pointer::Link(link) = pointer {
+                        stack.push(*link.get_cid().unwrap());
+                    }
+                }
+            }
+        }
+
+        let changes = diff_at(
+            Link::<Arc<Node<String, u64>>>::from_cid(main_cid),
+            Link::from_cid(other_cid),
+            &hashprefix,
+            partial_store,
+        )
+        .await
+        .unwrap();
+
+        let expected = diff(Link::from(main_node), Link::from(other_node), store)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|change| hashprefix.is_prefix_of(&blake3::Hasher::hash(&change.key)))
+            .collect::<BTreeSet<_>>();
+
+        assert!(!expected.is_empty());
+        assert_eq!(changes.into_iter().collect::<BTreeSet<_>>(), expected);
+    }
+
+    #[async_std::test]
+    async fn diff_fails_for_nodes_built_with_different_hasher() {
+        let store = &MemoryBlockStore::default();
+        let mut main_node = Arc::new(Node::<String, u64>::default());
+        for i in 0..3 {
+            main_node.set(i.to_string(), i, store).await.unwrap();
+        }
+        let main_cid = main_node.store(store).await.unwrap();
+
+        let result = diff(
+            Link::<Arc<Node<String, u64, ReversedHasher>>>::from_cid(main_cid),
+            Link::from(Arc::new(Node::default())),
+            store,
+        )
+        .await;
+
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<HamtError>(),
+            Some(HamtError::MisplacedPairs(_))
+        ));
+    }
 }
 
 #[cfg(test)]
 mod proptests {
     use crate::{
         strategies::{self, generate_kvs, generate_ops_and_changes, Change, Operations},
-        ChangeType,
+        ChangeType, HashPrefix, Hasher,
     };
     use async_std::task;
     use proptest::{prop_assert, prop_assert_eq};
-    use std::collections::HashSet;
+    use std::collections::{BTreeSet, HashSet};
     use test_strategy::proptest;
-    use wnfs_common::{utils::Arc, Link, MemoryBlockStore};
+    use wnfs_common::{
+        utils::{self, Arc},
+        Link, MemoryBlockStore,
+    };
 
     #[proptest(cases = 100, max_shrink_iters = 4000)]
     fn diff_correspondence(
@@ -652,6 +955,42 @@ mod proptests {
         })?;
     }
 
+    #[proptest(cases = 100)]
+    fn diff_at_is_diff_restricted_to_prefix(
+        #[strategy(generate_kvs("[a-z0-9]{1,3}", 0u64..1000, 0..100))] kvs1: Vec<(String, u64)>,
+        #[strategy(generate_kvs("[a-z0-9]{1,3}", 0u64..1000, 0..100))] kvs2: Vec<(String, u64)>,
+        prefix: [u8; 2],
+        #[strategy(0u16..=16)] bit_length: u16,
+    ) {
+        task::block_on(async {
+            let store = &MemoryBlockStore::default();
+            let hashprefix =
+                HashPrefix::with_bit_length(utils::to_hash_output(&prefix), bit_length);
+
+            let node1 = strategies::node_from_kvs(kvs1, store).await.unwrap();
+            let node2 = strategies::node_from_kvs(kvs2, store).await.unwrap();
+
+            let changes = super::diff_at(
+                Link::from(Arc::clone(&node1)),
+                Link::from(Arc::clone(&node2)),
+                &hashprefix,
+                store,
+            )
+            .await
+            .unwrap();
+
+            let expected = super::diff(Link::from(node1), Link::from(node2), store)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|change| hashprefix.is_prefix_of(&blake3::Hasher::hash(&change.key)))
+                .collect::<BTreeSet<_>>();
+
+            prop_assert_eq!(changes.into_iter().collect::<BTreeSet<_>>(), expected);
+            Ok(())
+        })?;
+    }
+
     #[proptest(cases = 1000, max_shrink_iters = 40000)]
     fn diff_unique_keys(
         #[strategy(generate_kvs("[a-z0-9]{1,3}", 0u64..1000, 0..100))] kvs1: Vec<(String, u64)>,
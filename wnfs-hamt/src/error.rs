@@ -23,4 +23,18 @@ pub enum HamtError {
 
     #[error("The hashprefix index is out of bounds: {0}")]
     HashPrefixIndexOutOfBounds(u8),
+
+    #[error("Invalid node bitmask: {0}")]
+    InvalidBitmask(String),
+
+    #[error("Node pairs don't match the HAMT's bit width and hasher: {0}")]
+    MisplacedPairs(String),
+
+    #[error("HAMT config mismatch: expected bit width {expected_bit_width} and bucket size {expected_bucket_size}, got bit width {bit_width} and bucket size {bucket_size}")]
+    ConfigMismatch {
+        expected_bit_width: u8,
+        expected_bucket_size: usize,
+        bit_width: u8,
+        bucket_size: usize,
+    },
 }
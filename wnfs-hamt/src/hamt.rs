@@ -1,12 +1,12 @@
-use super::{KeyValueChange, Node, HAMT_VERSION};
-use crate::{serializable::HamtSerializable, Hasher};
-use anyhow::Result;
+use super::{HashPrefix, KeyValueChange, Node, Pair, HAMT_VERSION};
+use crate::{error::HamtError, serializable::HamtSerializable, DefaultConfig, HamtConfig, Hasher};
+use anyhow::{bail, Result};
 use libipld::Cid;
 use semver::Version;
 use serde::{de::DeserializeOwned, Serialize};
 use std::hash::Hash;
 use wnfs_common::{
-    utils::{Arc, CondSync},
+    utils::{Arc, BoxStream, CondSync},
     BlockStore, Link, Storable,
 };
 
@@ -28,11 +28,12 @@ use wnfs_common::{
 /// println!("HAMT: {:?}", hamt);
 /// ```
 #[derive(Debug, Clone)]
-pub struct Hamt<K: CondSync, V: CondSync, H = blake3::Hasher>
+pub struct Hamt<K: CondSync, V: CondSync, H = blake3::Hasher, C = DefaultConfig>
 where
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
-    pub root: Arc<Node<K, V, H>>,
+    pub root: Arc<Node<K, V, H, C>>,
     pub version: Version,
 }
 
@@ -40,7 +41,7 @@ where
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
-impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Hamt<K, V, H> {
+impl<K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> Hamt<K, V, H, C> {
     /// Creates a new empty HAMT.
     ///
     /// # Examples
@@ -70,7 +71,7 @@ impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Hamt<K, V, H> {
     ///
     /// println!("HAMT: {:?}", hamt);
     /// ```
-    pub fn with_root(root: Arc<Node<K, V, H>>) -> Self {
+    pub fn with_root(root: Arc<Node<K, V, H, C>>) -> Self {
         Self {
             root,
             version: HAMT_VERSION,
@@ -126,15 +127,108 @@ impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Hamt<K, V, H> {
         )
         .await
     }
+
+    /// Gets the difference between two HAMTs at the key-value level, restricted to the keys
+    /// whose hashes start with `hashprefix`.
+    ///
+    /// Only blocks on the path to the prefix and below it are loaded, see [`diff_at`](crate::diff_at).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use wnfs_hamt::{Hamt, HashPrefix, Hasher, Node};
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::default();
+    ///
+    ///     let main_hamt = Hamt::<String, usize>::with_root({
+    ///         let mut node = Arc::new(Node::default());
+    ///         node.set("foo".into(), 400, store).await.unwrap();
+    ///         node.set("bar".into(), 500, store).await.unwrap();
+    ///         node
+    ///     });
+    ///
+    ///     let other_hamt = Hamt::<String, usize>::new();
+    ///
+    ///     let hashprefix = HashPrefix::with_length(blake3::Hasher::hash(&"foo"), 2);
+    ///     let diff = main_hamt.diff_at(&other_hamt, &hashprefix, store).await.unwrap();
+    ///
+    ///     assert_eq!(diff.len(), 1);
+    ///     assert_eq!(diff[0].key, "foo");
+    /// }
+    /// ```
+    pub async fn diff_at(
+        &self,
+        other: &Self,
+        hashprefix: &HashPrefix,
+        store: &impl BlockStore,
+    ) -> Result<Vec<KeyValueChange<K, V>>>
+    where
+        K: Storable + Clone + Eq + Hash + AsRef<[u8]>,
+        V: Storable + Clone + Eq,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        super::diff_at(
+            Link::from(Arc::clone(&self.root)),
+            Link::from(Arc::clone(&other.root)),
+            hashprefix,
+            store,
+        )
+        .await
+    }
+
+    /// Returns a stream of all key-value pairs in the HAMT, ordered by the hashes of their keys.
+    ///
+    /// Nodes are loaded lazily as the stream progresses.
+    /// See [`Node::stream_pairs_at`] for listing only part of the HAMT or resuming a listing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use futures::TryStreamExt;
+    /// use wnfs_hamt::{Hamt, Node};
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::default();
+    ///
+    ///     let hamt = Hamt::<String, usize>::with_root({
+    ///         let mut node = Arc::new(Node::default());
+    ///         node.set("foo".into(), 400, store).await.unwrap();
+    ///         node.set("bar".into(), 500, store).await.unwrap();
+    ///         node
+    ///     });
+    ///
+    ///     let pairs = hamt.stream(store).try_collect::<Vec<_>>().await.unwrap();
+    ///
+    ///     assert_eq!(pairs.len(), 2);
+    /// }
+    /// ```
+    pub fn stream<'a>(&'a self, store: &'a impl BlockStore) -> BoxStream<'a, Result<&'a Pair<K, V>>>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        self.root.stream_pairs(store)
+    }
 }
 
-impl<K, V, H> Storable for Hamt<K, V, H>
+impl<K, V, H, C> Storable for Hamt<K, V, H, C>
 where
-    K: Storable + CondSync,
+    K: Storable + AsRef<[u8]> + CondSync,
     V: Storable + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     type Serializable = HamtSerializable<K::Serializable, V::Serializable>;
 
@@ -143,6 +237,8 @@ where
             root: self.root.to_serializable(store).await?,
             version: self.version.clone(),
             structure: "hamt".to_string(),
+            bit_width: C::BIT_WIDTH,
+            bucket_size: C::BUCKET_SIZE,
         })
     }
 
@@ -150,26 +246,39 @@ where
         _cid: Option<&Cid>,
         serializable: Self::Serializable,
     ) -> Result<Self> {
+        if serializable.bit_width != C::BIT_WIDTH || serializable.bucket_size != C::BUCKET_SIZE {
+            bail!(HamtError::ConfigMismatch {
+                expected_bit_width: C::BIT_WIDTH,
+                expected_bucket_size: C::BUCKET_SIZE,
+                bit_width: serializable.bit_width,
+                bucket_size: serializable.bucket_size,
+            });
+        }
+
+        let root = Node::<K, V, H, C>::from_serializable(None, serializable.root).await?;
+        root.check_placement(0)?;
+
         Ok(Self {
-            root: Arc::new(Node::from_serializable(None, serializable.root).await?),
+            root: Arc::new(root),
             version: serializable.version,
         })
     }
 }
 
-impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Default for Hamt<K, V, H> {
+impl<K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> Default for Hamt<K, V, H, C> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<K: CondSync, V: CondSync, H> PartialEq for Hamt<K, V, H>
+impl<K: CondSync, V: CondSync, H, C> PartialEq for Hamt<K, V, H, C>
 where
     K: Storable + PartialEq + CondSync,
     V: Storable + PartialEq + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     fn eq(&self, other: &Self) -> bool {
         self.root == other.root && self.version == other.version
@@ -183,6 +292,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{strategies::ReversedHasher, Config};
     use wnfs_common::MemoryBlockStore;
 
     #[async_std::test]
@@ -196,6 +306,51 @@ mod tests {
 
         assert_eq!(hamt, decoded_hamt);
     }
+
+    #[async_std::test]
+    async fn hamt_with_custom_config_can_encode_decode_as_cbor() {
+        let store = &MemoryBlockStore::default();
+        let mut hamt = Hamt::<String, i32, blake3::Hasher, Config<8, 1>>::new();
+        for i in 0..50 {
+            hamt.root.set(i.to_string(), i, store).await.unwrap();
+        }
+
+        let hamt_cid = hamt.store(store).await.unwrap();
+        let decoded_hamt = Hamt::load(&hamt_cid, store).await.unwrap();
+
+        assert_eq!(hamt, decoded_hamt);
+    }
+
+    #[async_std::test]
+    async fn loading_hamt_with_different_config_fails() {
+        let store = &MemoryBlockStore::default();
+        let hamt = Hamt::<String, i32, blake3::Hasher, Config<4, 1>>::new();
+        let hamt_cid = hamt.store(store).await.unwrap();
+
+        let result = Hamt::<String, i32>::load(&hamt_cid, store).await;
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<HamtError>(),
+            Some(HamtError::ConfigMismatch { bucket_size: 1, .. })
+        ));
+    }
+
+    #[async_std::test]
+    async fn loading_hamt_with_different_hasher_fails() {
+        let store = &MemoryBlockStore::default();
+        let mut hamt = Hamt::<String, i32>::new();
+        for i in 0..3 {
+            hamt.root.set(i.to_string(), i, store).await.unwrap();
+        }
+        let hamt_cid = hamt.store(store).await.unwrap();
+
+        let result = Hamt::<String, i32, ReversedHasher>::load(&hamt_cid, store).await;
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<HamtError>(),
+            Some(HamtError::MisplacedPairs(_))
+        ));
+    }
 }
 
 #[cfg(test)]
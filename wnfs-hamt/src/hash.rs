@@ -1,6 +1,6 @@
 use crate::error::HamtError;
 use anyhow::{bail, Result};
-use std::fmt::Debug;
+use std::{cmp::Ordering, fmt::Debug};
 use wnfs_common::{utils, HashOutput, HASH_BYTE_SIZE};
 
 //--------------------------------------------------------------------------------------------------
@@ -37,11 +37,43 @@ pub trait Hasher {
     fn hash<D: AsRef<[u8]>>(data: &D) -> HashOutput;
 }
 
+/// A hasher with a unique identifier.
+///
+/// Structures whose layout depends on the hash function record this identifier
+/// to detect being loaded with a different hasher.
+///
+/// # Examples
+///
+/// ```
+/// use wnfs_hamt::{Hasher, NamedHasher};
+/// use wnfs_common::HashOutput;
+///
+/// struct MyHasher;
+///
+/// impl Hasher for MyHasher {
+///     fn hash<D: AsRef<[u8]>>(data: &D) -> HashOutput {
+///         blake3::hash(data.as_ref()).into()
+///     }
+/// }
+///
+/// impl NamedHasher for MyHasher {
+///     const NAME: &'static str = "my-blake3";
+/// }
+/// ```
+pub trait NamedHasher: Hasher {
+    /// A unique identifier of the hash function.
+    const NAME: &'static str;
+}
+
 /// HashNibbles is a wrapper around a byte slice that provides a cursor for traversing the nibbles.
+///
+/// HAMTs with a bit width other than 4 traverse the digest in chunks of that many bits instead,
+/// see [`HashNibbles::with_bit_width`].
 #[derive(Clone)]
 pub struct HashNibbles<'a> {
     pub digest: &'a HashOutput,
     cursor: usize,
+    bit_width: u8,
 }
 
 /// This represents the location of a intermediate or leaf node in the HAMT.
@@ -62,7 +94,8 @@ pub struct HashNibbles<'a> {
 #[derive(Clone, Default)]
 pub struct HashPrefix {
     pub digest: HashOutput,
-    length: u8,
+    /// The length of the prefix in bits.
+    length: u16,
 }
 
 /// An iterator over the nibbles of a HashPrefix.
@@ -96,7 +129,32 @@ impl<'a> HashNibbles<'a> {
 
     /// Constructs a `HashNibbles` with custom cursor index.
     pub fn with_cursor(digest: &'a HashOutput, cursor: usize) -> HashNibbles<'a> {
-        Self { digest, cursor }
+        Self::with_bit_width(digest, cursor, 4)
+    }
+
+    /// Constructs a `HashNibbles` that yields `bit_width` bits of the digest at a time,
+    /// starting at the given cursor index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs_hamt::HashNibbles;
+    /// use wnfs_common::utils;
+    ///
+    /// let digest = utils::to_hash_output(&[0b1011_0011, 0b1000_0000]);
+    /// let mut hashnibbles = HashNibbles::with_bit_width(&digest, 0, 3);
+    ///
+    /// assert_eq!(hashnibbles.next(), Some(0b101));
+    /// assert_eq!(hashnibbles.next(), Some(0b100));
+    /// assert_eq!(hashnibbles.next(), Some(0b111));
+    /// ```
+    pub fn with_bit_width(digest: &'a HashOutput, cursor: usize, bit_width: u8) -> HashNibbles<'a> {
+        debug_assert!((1..=8).contains(&bit_width));
+        Self {
+            digest,
+            cursor,
+            bit_width,
+        }
     }
 
     /// Gets the next nibble from the hash.
@@ -118,19 +176,14 @@ impl Iterator for HashNibbles<'_> {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cursor >= MAX_HASH_NIBBLE_LENGTH {
+        let bit_width = self.bit_width as usize;
+        if (self.cursor + 1) * bit_width > HASH_BYTE_SIZE * 8 {
             return None;
         }
 
-        let byte = self.digest[self.cursor / 2];
-        let byte = if self.cursor % 2 == 0 {
-            byte >> 4
-        } else {
-            byte & 0b0000_1111
-        };
-
+        let bits = read_bits(self.digest, self.cursor * bit_width, self.bit_width);
         self.cursor += 1;
-        Some(byte)
+        Some(bits)
     }
 }
 
@@ -154,6 +207,10 @@ impl Hasher for blake3::Hasher {
     }
 }
 
+impl NamedHasher for blake3::Hasher {
+    const NAME: &'static str = "blake3";
+}
+
 impl HashPrefix {
     /// Creates a new `HashPrefix` instance from a `[u8; 32]` hash.
     ///
@@ -168,7 +225,28 @@ impl HashPrefix {
     /// println!("{:?}", hashprefix);
     /// ```
     pub fn with_length(digest: HashOutput, length: u8) -> HashPrefix {
-        Self { digest, length }
+        Self::with_bit_length(digest, length as u16 * 4)
+    }
+
+    /// Creates a new `HashPrefix` of the first `bit_length` bits of a hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs_hamt::HashPrefix;
+    /// use wnfs_common::utils;
+    ///
+    /// let hashprefix = HashPrefix::with_bit_length(utils::to_hash_output(&[0b1010_0000]), 3);
+    ///
+    /// assert!(hashprefix.is_prefix_of(&[0b1011_1111]));
+    /// assert!(!hashprefix.is_prefix_of(&[0b1000_0000]));
+    /// ```
+    pub fn with_bit_length(digest: HashOutput, bit_length: u16) -> HashPrefix {
+        assert!(bit_length as usize <= HASH_BYTE_SIZE * 8);
+        Self {
+            digest,
+            length: bit_length,
+        }
     }
 
     /// Pushes a nibble to the end of the hash.
@@ -187,20 +265,40 @@ impl HashPrefix {
     /// assert_eq!(hashprefix.len(), 16);
     /// ```
     pub fn push(&mut self, nibble: u8) {
-        if self.length >= MAX_HASH_NIBBLE_LENGTH as u8 {
+        self.push_bits(nibble, 4)
+    }
+
+    /// Pushes the lowest `width` bits of `bits` to the end of the hash.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs_hamt::HashPrefix;
+    ///
+    /// let mut hashprefix = HashPrefix::default();
+    /// hashprefix.push_bits(0b101, 3);
+    /// hashprefix.push_bits(0b11111, 5);
+    ///
+    /// assert_eq!(hashprefix.bit_len(), 8);
+    /// assert!(hashprefix.is_prefix_of(&[0b1011_1111]));
+    /// ```
+    pub fn push_bits(&mut self, bits: u8, width: u8) {
+        if self.length as usize + width as usize > HASH_BYTE_SIZE * 8 {
             panic!("HashPrefix is full");
         }
 
-        let offset = self.length as usize / 2;
-        let byte = self.digest[offset];
-        let byte = if self.length as usize % 2 == 0 {
-            nibble << 4
-        } else {
-            byte | (nibble & 0x0F)
-        };
+        for i in 0..width {
+            let bit = (bits >> (width - 1 - i)) & 1;
+            let position = self.length as usize + i as usize;
+            let mask = 0x80 >> (position % 8);
+            if bit == 1 {
+                self.digest[position / 8] |= mask;
+            } else {
+                self.digest[position / 8] &= !mask;
+            }
+        }
 
-        self.digest[offset] = byte;
-        self.length += 1;
+        self.length += width as u16;
     }
 
     /// Gets the length of the hash.
@@ -220,6 +318,23 @@ impl HashPrefix {
     /// ```
     #[inline(always)]
     pub fn len(&self) -> usize {
+        self.length as usize / 4
+    }
+
+    /// Gets the length of the hash in bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs_hamt::HashPrefix;
+    ///
+    /// let mut hashprefix = HashPrefix::default();
+    /// hashprefix.push(0xf);
+    ///
+    /// assert_eq!(hashprefix.bit_len(), 4);
+    /// ```
+    #[inline(always)]
+    pub fn bit_len(&self) -> usize {
         self.length as usize
     }
 
@@ -254,16 +369,30 @@ impl HashPrefix {
     /// assert_eq!(hashprefix.get(15), Some(0x0f));
     /// ```
     pub fn get(&self, index: u8) -> Option<u8> {
-        if index >= self.length {
+        self.get_bits(index as usize, 4)
+    }
+
+    /// Gets the chunk of `width` bits at the specified offset, counted in chunks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs_hamt::HashPrefix;
+    /// use wnfs_common::utils;
+    ///
+    /// let hashprefix = HashPrefix::with_bit_length(utils::to_hash_output(&[0b1011_0011]), 6);
+    ///
+    /// assert_eq!(hashprefix.get_bits(0, 3), Some(0b101));
+    /// assert_eq!(hashprefix.get_bits(1, 3), Some(0b100));
+    /// assert_eq!(hashprefix.get_bits(2, 3), None);
+    /// ```
+    pub fn get_bits(&self, index: usize, width: u8) -> Option<u8> {
+        let offset = index * width as usize;
+        if offset + width as usize > self.length as usize {
             return None;
         }
 
-        let byte = self.digest.get(index as usize / 2)?;
-        Some(if index % 2 == 0 {
-            byte >> 4
-        } else {
-            byte & 0x0F
-        })
+        Some(read_bits(&self.digest, offset, width))
     }
 
     /// Creates an iterator over the nibbles of the hash.
@@ -299,7 +428,11 @@ impl HashPrefix {
     /// assert!(hashprefix.is_prefix_of(&[0xff, 0x22, 0x33]));
     /// ```
     pub fn is_prefix_of(&self, bytes: &[u8]) -> bool {
-        self == &HashPrefix::with_length(utils::to_hash_output(bytes), self.length)
+        cmp_bits(
+            &self.digest,
+            &utils::to_hash_output(bytes),
+            self.length as usize,
+        ) == Ordering::Equal
     }
 }
 
@@ -310,13 +443,21 @@ impl Debug for HashPrefix {
             write!(f, "{nibble:1X}")?;
         }
 
+        // Bits that don't make up a whole nibble
+        let rest = (self.length % 4) as u8;
+        if rest != 0 {
+            let bits = read_bits(&self.digest, self.length as usize - rest as usize, rest);
+            write!(f, "+0b{bits:0width$b}", width = rest as usize)?;
+        }
+
         Ok(())
     }
 }
 
 impl PartialEq for HashPrefix {
     fn eq(&self, other: &Self) -> bool {
-        self.iter().eq(other.iter())
+        self.length == other.length
+            && cmp_bits(&self.digest, &other.digest, self.length as usize) == Ordering::Equal
     }
 }
 
@@ -324,16 +465,38 @@ impl Iterator for HashPrefixIterator<'_> {
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.cursor >= self.hashprefix.length {
-            return None;
-        }
-
         let byte = self.hashprefix.get(self.cursor)?;
         self.cursor += 1;
         Some(byte)
     }
 }
 
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Reads `width` bits starting at bit `offset` of the digest, most significant bit first.
+fn read_bits(digest: &HashOutput, offset: usize, width: u8) -> u8 {
+    (offset..offset + width as usize).fold(0, |bits, position| {
+        let bit = (digest[position / 8] >> (7 - position % 8)) & 1;
+        (bits << 1) | bit
+    })
+}
+
+/// Compares the first `length` bits of two digests.
+pub(crate) fn cmp_bits(a: &HashOutput, b: &HashOutput, length: usize) -> Ordering {
+    let bytes = length / 8;
+    a[..bytes].cmp(&b[..bytes]).then_with(|| {
+        let rest = length % 8;
+        if rest == 0 {
+            return Ordering::Equal;
+        }
+
+        let mask = !(0xff >> rest);
+        (a[bytes] & mask).cmp(&(b[bytes] & mask))
+    })
+}
+
 //--------------------------------------------------------------------------------------------------
 // Tests
 //--------------------------------------------------------------------------------------------------
@@ -379,6 +542,7 @@ mod tests {
         }
 
         assert!(!hashprefix.is_empty());
+        assert_eq!(hashprefix.len(), HASH_BYTE_SIZE * 2);
 
         for i in 0..HASH_BYTE_SIZE {
             assert_eq!(hashprefix.get(i as u8 * 2).unwrap(), (i % 16) as u8);
@@ -388,4 +552,37 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn hash_nibbles_can_cursor_over_digest_with_bit_width() {
+        let key = utils::to_hash_output(&[0b1000_1100, 0b1010_1010]);
+
+        let indices = HashNibbles::with_bit_width(&key, 0, 5)
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![0b10001, 0b10010, 0b10101]);
+
+        let indices = HashNibbles::with_bit_width(&key, 0, 8).collect::<Vec<_>>();
+        assert_eq!(indices.len(), HASH_BYTE_SIZE);
+        assert_eq!(indices[..2], [0b1000_1100, 0b1010_1010]);
+
+        // 256 bits hold 51 chunks of 5 bits
+        assert_eq!(HashNibbles::with_bit_width(&key, 0, 5).count(), 51);
+    }
+
+    #[test]
+    fn hashprefix_can_push_bits_across_bytes() {
+        let mut hashprefix = HashPrefix::default();
+        hashprefix.push_bits(0b10001, 5);
+        hashprefix.push_bits(0b10010, 5);
+
+        assert_eq!(hashprefix.bit_len(), 10);
+        assert_eq!(hashprefix.get_bits(1, 5), Some(0b10010));
+        assert!(hashprefix.is_prefix_of(&[0b1000_1100, 0b1011_1111]));
+        assert!(!hashprefix.is_prefix_of(&[0b1000_1100, 0b1111_1111]));
+        assert_eq!(
+            hashprefix,
+            HashPrefix::with_bit_length(utils::to_hash_output(&[0b1000_1100, 0b1000_0000]), 10)
+        );
+    }
 }
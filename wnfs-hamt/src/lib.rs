@@ -11,6 +11,7 @@
 //!
 //! The implementation is based on [fvm_ipld_hamt](https://github.com/filecoin-project/ref-fvm/tree/master/ipld/hamt) with some modifications for async blockstore access and immutability-by-default.
 
+mod config;
 pub mod constants;
 mod diff;
 mod error;
@@ -19,8 +20,10 @@ mod hash;
 mod merge;
 mod node;
 mod pointer;
+mod proof;
 pub mod serializable;
 
+pub use config::*;
 pub(crate) use constants::*;
 pub use diff::*;
 pub use hamt::*;
@@ -28,6 +31,7 @@ pub use hash::*;
 pub use merge::*;
 pub use node::*;
 pub use pointer::*;
+pub use proof::*;
 
 #[cfg(any(test, feature = "test_utils"))]
 pub mod strategies;
@@ -1,5 +1,5 @@
 use super::{ChangeType, Node};
-use crate::{error::HamtError, Hasher};
+use crate::{error::HamtError, HamtConfig, Hasher};
 use anyhow::Result;
 use serde::{de::DeserializeOwned, Serialize};
 use std::hash::Hash;
@@ -13,18 +13,19 @@ use wnfs_common::{
 //--------------------------------------------------------------------------------------------------
 
 /// Merges a node with another with the help of a resolver function.
-pub async fn merge<K, V, H>(
-    main_link: Link<Arc<Node<K, V, H>>>,
-    other_link: Link<Arc<Node<K, V, H>>>,
+pub async fn merge<K, V, H, C>(
+    main_link: Link<Arc<Node<K, V, H, C>>>,
+    other_link: Link<Arc<Node<K, V, H, C>>>,
     f: impl Fn(&V, &V) -> Result<V>,
     store: &impl BlockStore,
-) -> Result<Arc<Node<K, V, H>>>
+) -> Result<Arc<Node<K, V, H, C>>>
 where
     K: Storable + Eq + Clone + CondSync + Hash + AsRef<[u8]>,
     V: Storable + Eq + Clone + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     let kv_changes = super::diff(main_link.clone(), other_link.clone(), store).await?;
 
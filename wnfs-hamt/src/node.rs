@@ -1,27 +1,29 @@
 use super::{
     error::HamtError,
-    hash::{HashNibbles, Hasher},
-    HashPrefix, Pair, Pointer, HAMT_BITMASK_BIT_SIZE, HAMT_BITMASK_BYTE_SIZE,
+    hash::{cmp_bits, HashNibbles, Hasher},
+    DefaultConfig, HamtConfig, HamtProof, HashPrefix, Pair, Pointer, HAMT_MAX_BITMASK_BYTE_SIZE,
 };
-use crate::{serializable::NodeSerializable, HAMT_VALUES_BUCKET_SIZE};
-use anyhow::{bail, Result};
+use crate::serializable::NodeSerializable;
+use anyhow::{anyhow, bail, Result};
 use async_once_cell::OnceCell;
 use async_recursion::async_recursion;
 use bitvec::array::BitArray;
 use either::{Either, Either::*};
+use futures::{stream, TryStreamExt};
 use libipld::Cid;
 #[cfg(feature = "log")]
 use log::debug;
 use serde::{de::DeserializeOwned, Serialize};
-use serde_byte_array::ByteArray;
+use serde_bytes::ByteBuf;
 use std::{
-    collections::HashMap,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::{self, Debug, Formatter},
     hash::Hash,
     marker::PhantomData,
 };
 use wnfs_common::{
-    utils::{boxed_fut, Arc, BoxFuture, CondSend, CondSync},
+    utils::{boxed_fut, Arc, BoxFuture, BoxStream, CondSend, CondSync},
     BlockStore, HashOutput, Link, Storable,
 };
 
@@ -29,8 +31,10 @@ use wnfs_common::{
 // Type Definitions
 //--------------------------------------------------------------------------------------------------
 
-/// The bitmask used by the HAMT which 16-bit, [u8; 2] type.
-pub type BitMaskType = [u8; HAMT_BITMASK_BYTE_SIZE];
+/// The bitmask used by the HAMT, large enough for the largest supported bit width.
+///
+/// Only the first [`HamtConfig::FANOUT`] bits are used.
+pub type BitMaskType = [u8; HAMT_MAX_BITMASK_BYTE_SIZE];
 
 /// Represents a node in the HAMT tree structure.
 ///
@@ -46,25 +50,27 @@ pub type BitMaskType = [u8; HAMT_BITMASK_BYTE_SIZE];
 ///
 /// assert!(node.is_empty());
 /// ```
-pub struct Node<K, V, H = blake3::Hasher>
+pub struct Node<K, V, H = blake3::Hasher, C = DefaultConfig>
 where
     H: Hasher + CondSync,
+    C: HamtConfig,
     K: CondSync,
     V: CondSync,
 {
     persisted_as: OnceCell<Cid>,
     pub(crate) bitmask: BitArray<BitMaskType>,
-    pub(crate) pointers: Vec<Pointer<K, V, H>>,
-    hasher: PhantomData<H>,
+    pub(crate) pointers: Vec<Pointer<K, V, H, C>>,
+    hasher: PhantomData<(H, C)>,
 }
 
 //--------------------------------------------------------------------------------------------------
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
-impl<K, V, H> Node<K, V, H>
+impl<K, V, H, C> Node<K, V, H, C>
 where
     H: Hasher + CondSync,
+    C: HamtConfig,
     K: CondSync,
     V: CondSync,
 {
@@ -98,7 +104,7 @@ where
         #[cfg(feature = "log")]
         debug!("set: hash = {:02x?}", hash);
 
-        self.set_value(&mut HashNibbles::new(hash), key, value, store)
+        self.set_value(&mut Self::hashnibbles(hash), key, value, store)
             .await
     }
 
@@ -133,7 +139,7 @@ where
         debug!("get: hash = {:02x?}", hash);
 
         Ok(self
-            .get_value(&mut HashNibbles::new(hash), store)
+            .get_value(&mut Self::hashnibbles(hash), store)
             .await?
             .map(|pair| &pair.value))
     }
@@ -179,7 +185,7 @@ where
         debug!("get_mut: hash = {:02x?}", hash);
 
         Ok(self
-            .get_value_mut(&mut HashNibbles::new(hash), store)
+            .get_value_mut(&mut Self::hashnibbles(hash), store)
             .await?
             .map(|pair| &mut pair.value))
     }
@@ -222,7 +228,7 @@ where
         #[cfg(feature = "log")]
         debug!("remove: hash = {:02x?}", hash);
 
-        self.remove_value(&mut HashNibbles::new(hash), store).await
+        self.remove_value(&mut Self::hashnibbles(hash), store).await
     }
 
     /// Gets the value at the key matching the provided hash.
@@ -260,7 +266,7 @@ where
         debug!("get_by_hash: hash = {:02x?}", hash);
 
         Ok(self
-            .get_value(&mut HashNibbles::new(hash), store)
+            .get_value(&mut Self::hashnibbles(hash), store)
             .await?
             .map(|pair| &pair.value))
     }
@@ -300,7 +306,113 @@ where
         K::Serializable: Serialize + DeserializeOwned,
         V::Serializable: Serialize + DeserializeOwned,
     {
-        self.remove_value(&mut HashNibbles::new(hash), store).await
+        self.remove_value(&mut Self::hashnibbles(hash), store).await
+    }
+
+    /// Sets many key-value pairs at once.
+    ///
+    /// Keys are grouped by their hash prefix, so every node on the way is
+    /// only copied and rebuilt once, no matter how many keys end up in it.
+    /// If a key appears more than once, the last value wins.
+    ///
+    /// The resulting tree is the same as if each pair was `set` one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use wnfs_hamt::Node;
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<String, usize>::default());
+    ///
+    ///     node.set_many((0..100).map(|i| (i.to_string(), i)), store).await.unwrap();
+    ///
+    ///     assert_eq!(node.get(&String::from("42"), store).await.unwrap(), Some(&42));
+    ///     assert_eq!(node.to_hashmap(store).await.unwrap().len(), 100);
+    /// }
+    /// ```
+    pub async fn set_many(
+        self: &mut Arc<Self>,
+        pairs: impl IntoIterator<Item = (K, V)>,
+        store: &impl BlockStore,
+    ) -> Result<()>
+    where
+        K: Storable + AsRef<[u8]> + Clone,
+        V: Storable + Clone,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        let entries = pairs
+            .into_iter()
+            .map(|(key, value)| (H::hash(&key), Pair::new(key, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        #[cfg(feature = "log")]
+        debug!("set_many: count = {}", entries.len());
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        self.set_values_many(0, entries.into_iter().collect(), store)
+            .await
+    }
+
+    /// Removes many keys at once, returning the pairs that were removed.
+    ///
+    /// Like `set_many`, this groups keys by their hash prefix and rebuilds
+    /// every touched node only once. Keys that are not present are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use wnfs_hamt::Node;
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<String, usize>::default());
+    ///     node.set_many((0..100).map(|i| (i.to_string(), i)), store).await.unwrap();
+    ///
+    ///     let keys = (0..50).map(|i| i.to_string()).collect::<Vec<_>>();
+    ///     let removed = node.remove_many(&keys, store).await.unwrap();
+    ///
+    ///     assert_eq!(removed.len(), 50);
+    ///     assert_eq!(node.get(&String::from("42"), store).await.unwrap(), None);
+    ///     assert_eq!(node.to_hashmap(store).await.unwrap().len(), 50);
+    /// }
+    /// ```
+    pub async fn remove_many<'k>(
+        self: &mut Arc<Self>,
+        keys: impl IntoIterator<Item = &'k K>,
+        store: &impl BlockStore,
+    ) -> Result<Vec<Pair<K, V>>>
+    where
+        K: Storable + AsRef<[u8]> + Clone + 'k,
+        V: Storable + Clone,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        let hashes = keys
+            .into_iter()
+            .map(|key| H::hash(key))
+            .collect::<BTreeSet<_>>();
+
+        #[cfg(feature = "log")]
+        debug!("remove_many: count = {}", hashes.len());
+
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.remove_values_many(0, hashes.into_iter().collect(), store)
+            .await
     }
 
     /// Checks if the node is empty.
@@ -327,18 +439,50 @@ where
         self.bitmask.count_ones() == 0
     }
 
+    /// Creates a cursor over the hash that yields `C::BIT_WIDTH` bits at a time.
+    fn hashnibbles(hash: &HashOutput) -> HashNibbles<'_> {
+        HashNibbles::with_bit_width(hash, 0, C::BIT_WIDTH)
+    }
+
     /// Calculates the value index from the bitmask index.
     pub(crate) fn get_value_index(&self, bit_index: usize) -> usize {
-        let shift_amount = HAMT_BITMASK_BIT_SIZE - bit_index;
-        let mask = if shift_amount < HAMT_BITMASK_BIT_SIZE {
-            let mut tmp = BitArray::<BitMaskType>::new([0xff, 0xff]);
-            tmp.shift_left(shift_amount);
-            tmp
-        } else {
-            BitArray::ZERO
-        };
-        debug_assert_eq!(mask.count_ones(), bit_index);
-        (mask & self.bitmask).count_ones()
+        self.bitmask[..bit_index].count_ones()
+    }
+
+    /// Checks that the values in this node's buckets hash to the slots they're stored in,
+    /// given that the node sits `cursor` levels below the root.
+    ///
+    /// Nodes built with a different bit width or hasher fail this check, since their
+    /// pairs were placed by different hash bits.
+    pub(crate) fn check_placement(&self, cursor: usize) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+    {
+        for (bit_index, pointer) in self.bitmask.iter_ones().zip(self.pointers.iter()) {
+            let Pointer::Values(values) = pointer else {
+                continue;
+            };
+
+            if values.len() > C::BUCKET_SIZE {
+                bail!(HamtError::MisplacedPairs(format!(
+                    "bucket holds {} pairs, but the bucket size is {}",
+                    values.len(),
+                    C::BUCKET_SIZE
+                )));
+            }
+
+            for pair in values {
+                let hash = &H::hash(&pair.key);
+                let index = HashNibbles::with_bit_width(hash, cursor, C::BIT_WIDTH).try_next()?;
+                if index != bit_index {
+                    bail!(HamtError::MisplacedPairs(format!(
+                        "pair hashes to slot {index} at level {cursor}, but is stored in slot {bit_index}"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     pub fn set_value<'a>(
@@ -387,7 +531,7 @@ where
                         values[i] = Pair::new(key, value);
                     } else {
                         // Otherwise, insert the new value if bucket is not full. Create new node if it is.
-                        if values.len() < HAMT_VALUES_BUCKET_SIZE {
+                        if values.len() < C::BUCKET_SIZE {
                             // Insert in order of key.
                             let index = values
                                 .iter()
@@ -396,7 +540,7 @@ where
                             values.insert(index, Pair::new(key, value));
                         } else {
                             // If values has reached threshold, we need to create a node link that splits it.
-                            let mut sub_node = Arc::new(Node::<K, V, H>::default());
+                            let mut sub_node = Arc::new(Node::<K, V, H, C>::default());
                             let cursor = hashnibbles.get_cursor();
                             // We can take because
                             // Pointer::Values() gets replaced with Pointer::Link at the end
@@ -405,7 +549,8 @@ where
                                 values.into_iter().chain(Some(Pair::new(key, value)))
                             {
                                 let hash = &H::hash(&key);
-                                let hashnibbles = &mut HashNibbles::with_cursor(hash, cursor);
+                                let hashnibbles =
+                                    &mut HashNibbles::with_bit_width(hash, cursor, C::BIT_WIDTH);
                                 sub_node.set_value(hashnibbles, key, value, store).await?;
                             }
                             node.pointers[value_index] = Pointer::Link(Link::from(sub_node));
@@ -413,7 +558,7 @@ where
                     }
                 }
                 Pointer::Link(link) => {
-                    let mut child: Arc<Node<K, V, H>> =
+                    let mut child: Arc<Node<K, V, H, C>> =
                         Arc::clone(link.resolve_value(store).await?);
                     child.set_value(hashnibbles, key, value, store).await?;
                     node.pointers[value_index] = Pointer::Link(Link::from(child));
@@ -573,6 +718,161 @@ where
         })
     }
 
+    /// Inserts pairs that are sorted by their hashes into this node.
+    ///
+    /// All the hashes are expected to share the nibbles before `cursor`.
+    fn set_values_many<'a>(
+        self: &'a mut Arc<Self>,
+        cursor: usize,
+        entries: Vec<(HashOutput, Pair<K, V>)>,
+        store: &'a impl BlockStore,
+    ) -> BoxFuture<'a, Result<()>>
+    where
+        K: Storable + Clone + AsRef<[u8]> + 'a,
+        V: Storable + Clone + 'a,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        Box::pin(async move {
+            let groups = group_by_nibble(entries, cursor, C::BIT_WIDTH, |(hash, _)| hash)?;
+
+            let node = Arc::make_mut(self);
+            node.persisted_as = OnceCell::new();
+
+            for (bit_index, group) in groups {
+                let value_index = node.get_value_index(bit_index);
+
+                // If the bit is not set yet, insert a new pointer.
+                if !node.bitmask[bit_index] {
+                    let pointer = Self::pointer_from_entries(cursor, group, store).await?;
+                    node.pointers.insert(value_index, pointer);
+                    node.bitmask.set(bit_index, true);
+                    continue;
+                }
+
+                match &mut node.pointers[value_index] {
+                    Pointer::Values(values) => {
+                        // Existing values get overwritten by new values with the same key.
+                        let mut merged = std::mem::take(values)
+                            .into_iter()
+                            .map(|pair| (H::hash(&pair.key), pair))
+                            .collect::<BTreeMap<_, _>>();
+                        merged.extend(group);
+
+                        node.pointers[value_index] =
+                            Self::pointer_from_entries(cursor, merged.into_iter().collect(), store)
+                                .await?;
+                    }
+                    Pointer::Link(link) => {
+                        let mut child = Arc::clone(link.resolve_value(store).await?);
+                        child.set_values_many(cursor + 1, group, store).await?;
+                        node.pointers[value_index] = Pointer::Link(Link::from(child));
+                    }
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Creates the pointer holding the given pairs sorted by their hashes.
+    ///
+    /// The pairs stay in a bucket if they fit, otherwise they get split into a new node.
+    async fn pointer_from_entries(
+        cursor: usize,
+        entries: Vec<(HashOutput, Pair<K, V>)>,
+        store: &impl BlockStore,
+    ) -> Result<Pointer<K, V, H, C>>
+    where
+        K: Storable + Clone + AsRef<[u8]>,
+        V: Storable + Clone,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        if entries.len() <= C::BUCKET_SIZE {
+            return Ok(Pointer::Values(
+                entries.into_iter().map(|(_, pair)| pair).collect(),
+            ));
+        }
+
+        let mut sub_node = Arc::new(Node::<K, V, H, C>::default());
+        sub_node.set_values_many(cursor + 1, entries, store).await?;
+        Ok(Pointer::Link(Link::from(sub_node)))
+    }
+
+    /// Removes the pairs matching the given sorted hashes from this node.
+    ///
+    /// All the hashes are expected to share the nibbles before `cursor`.
+    fn remove_values_many<'a>(
+        self: &'a mut Arc<Self>,
+        cursor: usize,
+        hashes: Vec<HashOutput>,
+        store: &'a impl BlockStore,
+    ) -> BoxFuture<'a, Result<Vec<Pair<K, V>>>>
+    where
+        K: Storable + AsRef<[u8]> + Clone + 'a,
+        V: Storable + Clone + 'a,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        Box::pin(async move {
+            let groups = group_by_nibble(hashes, cursor, C::BIT_WIDTH, |hash| hash)?
+                .into_iter()
+                .filter(|(bit_index, _)| self.bitmask[*bit_index])
+                .collect::<Vec<_>>();
+
+            let mut removed = Vec::new();
+            if groups.is_empty() {
+                return Ok(removed);
+            }
+
+            let node = Arc::make_mut(self);
+            node.persisted_as = OnceCell::new();
+
+            for (bit_index, group) in groups {
+                let value_index = node.get_value_index(bit_index);
+
+                match &mut node.pointers[value_index] {
+                    Pointer::Values(values) => {
+                        let (matching, rest): (Vec<_>, Vec<_>) = std::mem::take(values)
+                            .into_iter()
+                            .partition(|pair| group.binary_search(&H::hash(&pair.key)).is_ok());
+
+                        removed.extend(matching);
+
+                        if rest.is_empty() {
+                            node.bitmask.set(bit_index, false);
+                            node.pointers.remove(value_index);
+                        } else {
+                            node.pointers[value_index] = Pointer::Values(rest);
+                        }
+                    }
+                    Pointer::Link(link) => {
+                        let mut child = Arc::clone(link.resolve_value(store).await?);
+                        let child_removed =
+                            child.remove_values_many(cursor + 1, group, store).await?;
+
+                        if child_removed.is_empty() {
+                            node.pointers[value_index] = Pointer::Link(Link::from(child));
+                        } else if let Some(pointer) =
+                            Pointer::Link(Link::from(child)).canonicalize(store).await?
+                        {
+                            node.pointers[value_index] = pointer;
+                        } else {
+                            // The pointer now points to an empty node, so we remove it from the parent.
+                            node.bitmask.set(bit_index, false);
+                            node.pointers.remove(value_index);
+                        }
+
+                        removed.extend(child_removed);
+                    }
+                }
+            }
+
+            Ok(removed)
+        })
+    }
+
     /// Visits all the leaf nodes in the trie and calls the given function on each of them.
     ///
     /// # Examples
@@ -630,6 +930,193 @@ where
         Ok(items)
     }
 
+    /// Returns a stream of all key-value pairs in the trie, ordered by the hashes of their keys.
+    ///
+    /// Unlike `flat_map`, child nodes are only loaded once the stream reaches them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use futures::TryStreamExt;
+    /// use wnfs_hamt::Node;
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<[u8; 4], String>::default());
+    ///     for i in 0..99_u32 {
+    ///         node.set(i.to_le_bytes(), i.to_string(), store).await.unwrap();
+    ///     }
+    ///
+    ///     let pairs = node.stream_pairs(store).try_collect::<Vec<_>>().await.unwrap();
+    ///
+    ///     assert_eq!(pairs.len(), 99);
+    /// }
+    /// ```
+    pub fn stream_pairs<'a>(
+        &'a self,
+        store: &'a impl BlockStore,
+    ) -> BoxStream<'a, Result<&'a Pair<K, V>>>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        self.stream_pairs_at(HashPrefix::default(), None, store)
+    }
+
+    /// Returns a stream of the key-value pairs whose key hashes start with `hashprefix`,
+    /// ordered by the hashes of their keys.
+    ///
+    /// If `after` is given, only pairs with a key hash greater than it are returned.
+    /// Passing the hash of the last key received resumes a previous listing, which
+    /// allows paginating through the trie. Subtrees that can't contain any matching
+    /// pairs are never loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use futures::{StreamExt, TryStreamExt};
+    /// use wnfs_hamt::{Node, Hasher, HashPrefix};
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<[u8; 4], String>::default());
+    ///     for i in 0..99_u32 {
+    ///         node.set(i.to_le_bytes(), i.to_string(), store).await.unwrap();
+    ///     }
+    ///
+    ///     // Page through all pairs, 10 at a time.
+    ///     let mut cursor = None;
+    ///     let mut count = 0;
+    ///     loop {
+    ///         let page = node
+    ///             .stream_pairs_at(HashPrefix::default(), cursor, store)
+    ///             .take(10)
+    ///             .try_collect::<Vec<_>>()
+    ///             .await
+    ///             .unwrap();
+    ///
+    ///         let Some(last) = page.last() else { break };
+    ///         cursor = Some(blake3::Hasher::hash(&last.key));
+    ///         count += page.len();
+    ///     }
+    ///
+    ///     assert_eq!(count, 99);
+    /// }
+    /// ```
+    pub fn stream_pairs_at<'a>(
+        &'a self,
+        hashprefix: HashPrefix,
+        after: Option<HashOutput>,
+        store: &'a impl BlockStore,
+    ) -> BoxStream<'a, Result<&'a Pair<K, V>>>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        let stack = vec![StreamFrame::Node(self, HashPrefix::default())];
+
+        Box::pin(stream::try_unfold(
+            (stack, hashprefix, after),
+            move |(mut stack, hashprefix, after)| async move {
+                while let Some(frame) = stack.pop() {
+                    match frame {
+                        StreamFrame::Pair(pair) => {
+                            let hash = H::hash(&pair.key);
+                            if hashprefix.is_prefix_of(&hash) && after.map_or(true, |a| hash > a) {
+                                return Ok(Some((pair, (stack, hashprefix, after))));
+                            }
+                        }
+                        StreamFrame::Link(link, path) => {
+                            let child = link.resolve_value(store).await?;
+                            stack.push(StreamFrame::Node(child, path));
+                        }
+                        StreamFrame::Node(node, path) => {
+                            let pointers = node
+                                .bitmask
+                                .iter_ones()
+                                .zip(node.pointers.iter())
+                                .collect::<Vec<_>>();
+
+                            // Pushed in reverse, so they get visited in order.
+                            for (bit_index, pointer) in pointers.into_iter().rev() {
+                                let mut path = path.clone();
+                                path.push_bits(bit_index as u8, C::BIT_WIDTH);
+
+                                if !may_contain(&path, &hashprefix, after.as_ref()) {
+                                    continue;
+                                }
+
+                                match pointer {
+                                    Pointer::Values(values) => {
+                                        stack.extend(values.iter().rev().map(StreamFrame::Pair))
+                                    }
+                                    Pointer::Link(link) => {
+                                        stack.push(StreamFrame::Link(link, path))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Ok(None)
+            },
+        ))
+    }
+
+    /// Returns all key-value pairs whose key hashes start with `hashprefix`, ordered by
+    /// the hashes of their keys.
+    ///
+    /// Only the nodes on the path to the prefix and the subtrees below it are loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use wnfs_hamt::{Node, Hasher, HashPrefix};
+    /// use wnfs_common::MemoryBlockStore;
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<[u8; 4], String>::default());
+    ///     for i in 0..99_u32 {
+    ///         node.set(i.to_le_bytes(), i.to_string(), store).await.unwrap();
+    ///     }
+    ///
+    ///     let hashprefix = HashPrefix::with_length(blake3::Hasher::hash(&42_u32.to_le_bytes()), 1);
+    ///     let pairs = node.pairs_with_prefix(&hashprefix, store).await.unwrap();
+    ///
+    ///     assert!(pairs.iter().any(|pair| pair.value == "42"));
+    ///     assert!(pairs.len() < 99);
+    /// }
+    /// ```
+    pub async fn pairs_with_prefix<'a>(
+        &'a self,
+        hashprefix: &HashPrefix,
+        store: &'a impl BlockStore,
+    ) -> Result<Vec<&'a Pair<K, V>>>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        self.stream_pairs_at(hashprefix.clone(), None, store)
+            .try_collect()
+            .await
+    }
+
     /// Given a hashprefix representing the path to a node in the trie. This function will
     /// return the key-value pair or the intermediate node that the hashprefix points to.
     ///
@@ -689,7 +1176,7 @@ where
         V::Serializable: Serialize + DeserializeOwned,
     {
         let bit_index = hashprefix
-            .get(index)
+            .get_bits(index as usize, C::BIT_WIDTH)
             .ok_or(HamtError::HashPrefixIndexOutOfBounds(index))? as usize;
 
         if !self.bitmask[bit_index] {
@@ -706,7 +1193,7 @@ where
             }),
             Pointer::Link(link) => {
                 let child = link.resolve_value(store).await?;
-                if index == hashprefix.len() as u8 - 1 {
+                if (index as usize + 1) * C::BIT_WIDTH as usize >= hashprefix.bit_len() {
                     return Ok(Some(Right(child)));
                 }
 
@@ -715,6 +1202,105 @@ where
         }
     }
 
+    /// Creates a proof that the given key is, or isn't, present in the trie stored at `root_cid`.
+    ///
+    /// The proof contains the blocks of all nodes on the hash path of the key, so it can be
+    /// checked with [`verify_proof`](crate::verify_proof) given only the root CID.
+    /// Blocks are only read from the store, so the trie needs to be stored beforehand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use wnfs_hamt::Node;
+    /// use wnfs_common::{MemoryBlockStore, Storable};
+    ///
+    /// #[async_std::main]
+    /// async fn main() {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let mut node = Arc::new(Node::<String, usize>::default());
+    ///     for i in 0..100 {
+    ///         node.set(i.to_string(), i, store).await.unwrap();
+    ///     }
+    ///     let root_cid = node.store(store).await.unwrap();
+    ///
+    ///     let proof = Node::<String, usize>::prove(&root_cid, &"42".to_string(), store)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(!proof.is_empty());
+    /// }
+    /// ```
+    pub async fn prove(root_cid: &Cid, key: &K, store: &impl BlockStore) -> Result<HamtProof>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        Self::prove_by_hash(root_cid, &H::hash(key), store).await
+    }
+
+    /// Creates a proof that a key with the given hash is, or isn't, present in the trie
+    /// stored at `root_cid`.
+    ///
+    /// See [`Node::prove`].
+    pub async fn prove_by_hash(
+        root_cid: &Cid,
+        hash: &HashOutput,
+        store: &impl BlockStore,
+    ) -> Result<HamtProof>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        let mut proof = HamtProof::new();
+        proof.push_block(*root_cid, store.get_block(root_cid).await?.to_vec());
+        let root = Self::load(root_cid, store).await?;
+        root.prove_path_by_hash(hash, &mut proof, store).await?;
+        Ok(proof)
+    }
+
+    /// Adds the blocks of all nodes below this one on the hash path to the proof.
+    ///
+    /// This is useful when the block of this node is embedded in another block,
+    /// which then needs to be added to the proof instead.
+    /// Fails if any of the nodes on the path haven't been stored yet.
+    pub async fn prove_path_by_hash(
+        &self,
+        hash: &HashOutput,
+        proof: &mut HamtProof,
+        store: &impl BlockStore,
+    ) -> Result<()>
+    where
+        K: Storable + AsRef<[u8]>,
+        V: Storable,
+        K::Serializable: Serialize + DeserializeOwned,
+        V::Serializable: Serialize + DeserializeOwned,
+    {
+        let hashnibbles = &mut Self::hashnibbles(hash);
+        let mut node = self;
+        loop {
+            let bit_index = hashnibbles.try_next()?;
+            if !node.bitmask[bit_index] {
+                return Ok(());
+            }
+
+            match &node.pointers[node.get_value_index(bit_index)] {
+                Pointer::Values(_) => return Ok(()),
+                Pointer::Link(link) => {
+                    let cid = link
+                        .get_cid()
+                        .ok_or_else(|| anyhow!("can't prove a path through unstored nodes"))?;
+                    proof.push_block(*cid, store.get_block(cid).await?.to_vec());
+                    node = link.resolve_value(store).await?;
+                }
+            }
+        }
+    }
+
     /// Generates a hashmap from the node.
     ///
     /// # Examples
@@ -778,7 +1364,47 @@ where
     }
 }
 
-impl<K: Clone + CondSync, V: CondSync + Clone, H: Hasher + CondSync> Clone for Node<K, V, H> {
+/// A pending step of the depth-first traversal in `Node::stream_pairs_at`.
+enum StreamFrame<'a, K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> {
+    Node(&'a Node<K, V, H, C>, HashPrefix),
+    Link(&'a Link<Arc<Node<K, V, H, C>>>, HashPrefix),
+    Pair(&'a Pair<K, V>),
+}
+
+/// Checks whether the subtree at `path` may contain keys whose hashes start with
+/// `hashprefix` and are greater than `after`.
+fn may_contain(path: &HashPrefix, hashprefix: &HashPrefix, after: Option<&HashOutput>) -> bool {
+    let common_length = path.bit_len().min(hashprefix.bit_len());
+    let matches_prefix =
+        cmp_bits(&path.digest, &hashprefix.digest, common_length) == Ordering::Equal;
+
+    let reaches_after = match after {
+        Some(after) => cmp_bits(&path.digest, after, path.bit_len()) != Ordering::Less,
+        None => true,
+    };
+
+    matches_prefix && reaches_after
+}
+
+/// Groups items by the `bit_width` bits of their hash at `cursor`, in order of those bits.
+fn group_by_nibble<T>(
+    items: Vec<T>,
+    cursor: usize,
+    bit_width: u8,
+    hash: impl Fn(&T) -> &HashOutput,
+) -> Result<BTreeMap<usize, Vec<T>>> {
+    let mut groups = BTreeMap::<usize, Vec<T>>::new();
+    for item in items {
+        let bit_index = HashNibbles::with_bit_width(hash(&item), cursor, bit_width).try_next()?;
+        groups.entry(bit_index).or_default().push(item);
+    }
+
+    Ok(groups)
+}
+
+impl<K: Clone + CondSync, V: CondSync + Clone, H: Hasher + CondSync, C: HamtConfig> Clone
+    for Node<K, V, H, C>
+{
     fn clone(&self) -> Self {
         Self {
             persisted_as: self
@@ -794,39 +1420,44 @@ impl<K: Clone + CondSync, V: CondSync + Clone, H: Hasher + CondSync> Clone for N
     }
 }
 
-impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Default for Node<K, V, H> {
+impl<K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> Default for Node<K, V, H, C> {
     fn default() -> Self {
         Node {
             persisted_as: OnceCell::new(),
             bitmask: BitArray::ZERO,
-            pointers: Vec::with_capacity(HAMT_BITMASK_BIT_SIZE),
+            pointers: Vec::new(),
             hasher: PhantomData,
         }
     }
 }
 
-impl<K, V, H> PartialEq for Node<K, V, H>
+impl<K, V, H, C> PartialEq for Node<K, V, H, C>
 where
     K: Storable + PartialEq + CondSync,
     V: Storable + PartialEq + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     fn eq(&self, other: &Self) -> bool {
         self.bitmask == other.bitmask && self.pointers == other.pointers
     }
 }
 
-impl<K, V, H> Debug for Node<K, V, H>
+impl<K, V, H, C> Debug for Node<K, V, H, C>
 where
     K: Debug + CondSync,
     V: Debug + CondSync,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut bitmask_str = String::new();
-        for i in self.bitmask.as_raw_slice().iter().rev() {
+        for i in self.bitmask.as_raw_slice()[..C::BITMASK_BYTE_SIZE]
+            .iter()
+            .rev()
+        {
             bitmask_str.push_str(&format!("{i:08b}"));
         }
 
@@ -837,18 +1468,19 @@ where
     }
 }
 
-impl<K, V, H> Storable for Node<K, V, H>
+impl<K, V, H, C> Storable for Node<K, V, H, C>
 where
     K: Storable + CondSync,
     V: Storable + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     type Serializable = NodeSerializable<K::Serializable, V::Serializable>;
 
     async fn to_serializable(&self, store: &impl BlockStore) -> Result<Self::Serializable> {
-        let bitmask = ByteArray::from(self.bitmask.into_inner());
+        let bitmask = ByteBuf::from(&self.bitmask.as_raw_slice()[..C::BITMASK_BYTE_SIZE]);
 
         let mut pointers = Vec::with_capacity(self.pointers.len());
         for pointer in self.pointers.iter() {
@@ -865,7 +1497,25 @@ where
     ) -> Result<Self> {
         let NodeSerializable(bitmask, ser_pointers) = serializable;
 
-        let bitmask = BitArray::<BitMaskType>::new(bitmask.into());
+        if bitmask.len() != C::BITMASK_BYTE_SIZE {
+            bail!(HamtError::InvalidBitmask(format!(
+                "expected {} bytes for bit width {}, got {}",
+                C::BITMASK_BYTE_SIZE,
+                C::BIT_WIDTH,
+                bitmask.len()
+            )));
+        }
+
+        let mut bytes = BitMaskType::default();
+        bytes[..bitmask.len()].copy_from_slice(&bitmask);
+        let bitmask = BitArray::<BitMaskType>::new(bytes);
+        if bitmask[C::FANOUT..].any() {
+            bail!(HamtError::InvalidBitmask(format!(
+                "bits set beyond the fanout of {}",
+                C::FANOUT
+            )));
+        }
+
         let bitmask_bits_set = bitmask.count_ones();
 
         if ser_pointers.len() != bitmask_bits_set {
@@ -901,6 +1551,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Config;
     use helper::*;
     use wnfs_common::{utils, MemoryBlockStore};
 
@@ -1201,6 +1852,115 @@ mod tests {
             assert_eq!(map.get(&i.to_le_bytes()).unwrap(), &i.to_string());
         }
     }
+
+    async fn assert_config_roundtrips<C: HamtConfig>() {
+        let store = &MemoryBlockStore::default();
+
+        let node = &mut Arc::new(Node::<[u8; 4], String, blake3::Hasher, C>::default());
+        const NUM_VALUES: u32 = 500;
+        for i in 0..NUM_VALUES {
+            node.set(i.to_le_bytes(), i.to_string(), store)
+                .await
+                .unwrap();
+        }
+
+        let cid = node.store(store).await.unwrap();
+        let node = &mut Arc::new(
+            Node::<[u8; 4], String, blake3::Hasher, C>::load(&cid, store)
+                .await
+                .unwrap(),
+        );
+
+        for i in 0..NUM_VALUES {
+            let value = node.get(&i.to_le_bytes(), store).await.unwrap();
+            assert_eq!(value, Some(&i.to_string()));
+        }
+
+        for i in (0..NUM_VALUES).step_by(2) {
+            let removed = node.remove(&i.to_le_bytes(), store).await.unwrap();
+            assert_eq!(removed.map(|pair| pair.value), Some(i.to_string()));
+        }
+
+        let map = node.to_hashmap(store).await.unwrap();
+        assert_eq!(map.len(), NUM_VALUES as usize / 2);
+    }
+
+    #[async_std::test]
+    async fn nodes_with_custom_config_roundtrip() {
+        assert_config_roundtrips::<Config<1, 1>>().await;
+        assert_config_roundtrips::<Config<2, 1>>().await;
+        assert_config_roundtrips::<Config<5, 3>>().await;
+        assert_config_roundtrips::<Config<8, 3>>().await;
+    }
+
+    #[async_std::test]
+    async fn loading_node_with_different_bit_width_fails() {
+        let store = &MemoryBlockStore::default();
+
+        let node = &mut Arc::new(Node::<String, u64, blake3::Hasher, Config<8, 3>>::default());
+        node.set("key".into(), 42, store).await.unwrap();
+        let cid = node.store(store).await.unwrap();
+
+        let result = Node::<String, u64>::load(&cid, store).await;
+        assert!(result.is_err());
+    }
+
+    #[async_std::test]
+    async fn stream_pairs_at_respects_prefix_with_custom_bit_width() {
+        use futures::TryStreamExt;
+
+        let store = &MemoryBlockStore::default();
+        let node = &mut Arc::new(Node::<[u8; 4], String, blake3::Hasher, Config<3, 1>>::default());
+        for i in 0..200_u32 {
+            node.set(i.to_le_bytes(), i.to_string(), store)
+                .await
+                .unwrap();
+        }
+
+        let prefix = HashPrefix::with_bit_length(utils::to_hash_output(&[0b1010_0000]), 5);
+        let pairs = node
+            .stream_pairs_at(prefix.clone(), None, store)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let expected = (0..200_u32)
+            .filter(|i| prefix.is_prefix_of(&blake3::Hasher::hash(&i.to_le_bytes())))
+            .count();
+        assert_eq!(pairs.len(), expected);
+        assert!(pairs
+            .iter()
+            .all(|pair| prefix.is_prefix_of(&blake3::Hasher::hash(&pair.key))));
+    }
+
+    #[async_std::test]
+    async fn pairs_with_prefix_only_loads_nodes_under_prefix() {
+        let store = &MemoryBlockStore::default();
+        let node = &mut Arc::new(Node::<[u8; 4], String>::default());
+        for i in 0..1000_u32 {
+            node.set(i.to_le_bytes(), i.to_string(), store)
+                .await
+                .unwrap();
+        }
+        let cid = node.store(store).await.unwrap();
+        let node = Node::<[u8; 4], String>::load(&cid, store).await.unwrap();
+
+        let prefix = HashPrefix::with_length(blake3::Hasher::hash(&7_u32.to_le_bytes()), 2);
+        let pairs = node.pairs_with_prefix(&prefix, store).await.unwrap();
+
+        let expected = (0..1000_u32)
+            .filter(|i| prefix.is_prefix_of(&blake3::Hasher::hash(&i.to_le_bytes())))
+            .count();
+        assert_eq!(pairs.len(), expected);
+        assert!(pairs.iter().any(|pair| pair.key == 7_u32.to_le_bytes()));
+
+        let index = prefix.get(0).unwrap() as usize;
+        for (bit_index, pointer) in node.bitmask.iter_ones().zip(node.pointers.iter()) {
+            if let Pointer::Link(link) = pointer {
+                assert_eq!(link.has_value(), bit_index == index);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1209,6 +1969,7 @@ mod proptests {
     use crate::strategies::{
         node_from_operations, operations, operations_and_shuffled, Operations,
     };
+    use futures::{future, StreamExt, TryStreamExt};
     use proptest::prelude::*;
     use test_strategy::proptest;
     use wnfs_common::MemoryBlockStore;
@@ -1306,6 +2067,140 @@ mod proptests {
         })?;
     }
 
+    #[proptest(cases = 100)]
+    fn stream_pairs_is_ordered_and_complete(
+        #[strategy(operations(small_key(), 0u64..1000, 0..300))] operations: Operations<
+            String,
+            u64,
+        >,
+    ) {
+        async_std::task::block_on(async move {
+            let store = &MemoryBlockStore::default();
+            let node = node_from_operations(&operations, store).await.unwrap();
+
+            let pairs = node
+                .stream_pairs(store)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let hashes = pairs
+                .iter()
+                .map(|pair| blake3::Hasher::hash(&pair.key))
+                .collect::<Vec<_>>();
+            let map = pairs
+                .into_iter()
+                .map(|pair| (pair.key.clone(), pair.value))
+                .collect::<HashMap<_, _>>();
+
+            prop_assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+            prop_assert_eq!(map, HashMap::from(&operations));
+            Ok(())
+        })?;
+    }
+
+    #[proptest(cases = 100)]
+    fn stream_pairs_at_can_resume(
+        #[strategy(operations(small_key(), 0u64..1000, 0..300))] operations: Operations<
+            String,
+            u64,
+        >,
+        #[strategy(0usize..300)] split: usize,
+        #[strategy(0u8..3)] prefix_length: u8,
+        #[strategy(any::<[u8; 32]>())] prefix_digest: [u8; 32],
+    ) {
+        async_std::task::block_on(async move {
+            let store = &MemoryBlockStore::default();
+            let node = node_from_operations(&operations, store).await.unwrap();
+            let prefix = HashPrefix::with_length(prefix_digest, prefix_length);
+
+            let expected = node
+                .stream_pairs(store)
+                .try_filter(|pair| {
+                    future::ready(prefix.is_prefix_of(&blake3::Hasher::hash(&pair.key)))
+                })
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+
+            let mut pairs = node
+                .stream_pairs_at(prefix.clone(), None, store)
+                .take(split)
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            let cursor = pairs.last().map(|pair| blake3::Hasher::hash(&pair.key));
+            pairs.extend(
+                node.stream_pairs_at(prefix, cursor, store)
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap(),
+            );
+
+            prop_assert_eq!(pairs, expected);
+            Ok(())
+        })?;
+    }
+
+    #[proptest(cases = 100)]
+    fn set_many_is_like_set(
+        #[strategy(operations(small_key(), 0u64..1000, 0..100))] operations: Operations<
+            String,
+            u64,
+        >,
+        #[strategy(proptest::collection::vec((small_key(), 0..1000u64), 0..100))] pairs: Vec<(
+            String,
+            u64,
+        )>,
+    ) {
+        async_std::task::block_on(async move {
+            let store = &MemoryBlockStore::default();
+            let node1 = &mut node_from_operations(&operations, store).await.unwrap();
+            let node2 = &mut Arc::clone(node1);
+
+            for (key, value) in pairs.iter().cloned() {
+                node1.set(key, value, store).await.unwrap();
+            }
+            node2.set_many(pairs, store).await.unwrap();
+
+            let cid1 = node1.store(store).await.unwrap();
+            let cid2 = node2.store(store).await.unwrap();
+
+            prop_assert_eq!(cid1, cid2);
+            Ok(())
+        })?;
+    }
+
+    #[proptest(cases = 100)]
+    fn remove_many_is_like_remove(
+        #[strategy(operations(small_key(), 0u64..1000, 0..100))] operations: Operations<
+            String,
+            u64,
+        >,
+        #[strategy(proptest::collection::vec(small_key(), 0..100))] keys: Vec<String>,
+    ) {
+        async_std::task::block_on(async move {
+            let store = &MemoryBlockStore::default();
+            let node1 = &mut node_from_operations(&operations, store).await.unwrap();
+            let node2 = &mut Arc::clone(node1);
+
+            let mut removed1 = Vec::new();
+            for key in keys.iter() {
+                removed1.extend(node1.remove(key, store).await.unwrap());
+            }
+            let mut removed2 = node2.remove_many(&keys, store).await.unwrap();
+
+            removed1.sort_by(|a, b| a.key.cmp(&b.key));
+            removed2.sort_by(|a, b| a.key.cmp(&b.key));
+
+            let cid1 = node1.store(store).await.unwrap();
+            let cid2 = node2.store(store).await.unwrap();
+
+            prop_assert_eq!(removed1, removed2);
+            prop_assert_eq!(cid1, cid2);
+            Ok(())
+        })?;
+    }
+
     // This is sort of a "control group" for making sure that operations_and_shuffled is correct.
     #[proptest(cases = 200, max_shrink_iters = 10_000)]
     fn hash_map_is_history_independent(
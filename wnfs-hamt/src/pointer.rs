@@ -1,4 +1,4 @@
-use super::{error::HamtError, hash::Hasher, Node, HAMT_VALUES_BUCKET_SIZE};
+use super::{error::HamtError, hash::Hasher, DefaultConfig, HamtConfig, Node};
 use crate::serializable::PointerSerializable;
 use anyhow::Result;
 use libipld::Cid;
@@ -33,9 +33,14 @@ pub struct Pair<K, V> {
 
 /// Each bit in the bitmask of a node maps a `Pointer` in the HAMT structure.
 /// A `Pointer` can be either a link to a child node or a collection of key-value pairs.
-pub(crate) enum Pointer<K: CondSync, V: CondSync, H: Hasher + CondSync> {
+pub(crate) enum Pointer<
+    K: CondSync,
+    V: CondSync,
+    H: Hasher + CondSync,
+    C: HamtConfig = DefaultConfig,
+> {
     Values(Vec<Pair<K, V>>),
-    Link(Link<Arc<Node<K, V, H>>>),
+    Link(Link<Arc<Node<K, V, H, C>>>),
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -60,7 +65,7 @@ impl<K, V> Pair<K, V> {
     }
 }
 
-impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Pointer<K, V, H> {
+impl<K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> Pointer<K, V, H, C> {
     /// Converts a Link pointer to a canonical form to ensure consistent tree representation after deletes.
     pub async fn canonicalize(self, store: &impl BlockStore) -> Result<Option<Self>>
     where
@@ -78,7 +83,7 @@ impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Pointer<K, V, H> {
                     1 if matches!(node.pointers[0], Pointer::Values(_)) => {
                         Ok(Some(node.pointers[0].clone()))
                     }
-                    2..=HAMT_VALUES_BUCKET_SIZE if node.count_values().is_ok() => {
+                    n if n >= 2 && n <= C::BUCKET_SIZE && node.count_values().is_ok() => {
                         // Collect all the values of the node.
                         let mut values = node
                             .pointers
@@ -91,7 +96,7 @@ impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Pointer<K, V, H> {
                             .collect::<Vec<_>>();
 
                         // Bail if it's more values that we can fit into a bucket
-                        if values.len() > HAMT_VALUES_BUCKET_SIZE {
+                        if values.len() > C::BUCKET_SIZE {
                             return Ok(Some(Pointer::Link(Link::from(node))));
                         }
 
@@ -109,13 +114,14 @@ impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Pointer<K, V, H> {
     }
 }
 
-impl<K, V, H> Storable for Pointer<K, V, H>
+impl<K, V, H, C> Storable for Pointer<K, V, H, C>
 where
     K: Storable + CondSync,
     V: Storable + CondSync,
     K::Serializable: Serialize + DeserializeOwned,
     V::Serializable: Serialize + DeserializeOwned,
     H: Hasher + CondSync,
+    C: HamtConfig,
 {
     type Serializable = PointerSerializable<K::Serializable, V::Serializable>;
 
@@ -177,7 +183,9 @@ where
     }
 }
 
-impl<K: Clone + CondSync, V: Clone + CondSync, H: Hasher + CondSync> Clone for Pointer<K, V, H> {
+impl<K: Clone + CondSync, V: Clone + CondSync, H: Hasher + CondSync, C: HamtConfig> Clone
+    for Pointer<K, V, H, C>
+{
     fn clone(&self) -> Self {
         match self {
             Self::Values(arg0) => Self::Values(arg0.clone()),
@@ -186,8 +194,8 @@ impl<K: Clone + CondSync, V: Clone + CondSync, H: Hasher + CondSync> Clone for P
     }
 }
 
-impl<K: Debug + CondSync, V: Debug + CondSync, H: Hasher + CondSync> std::fmt::Debug
-    for Pointer<K, V, H>
+impl<K: Debug + CondSync, V: Debug + CondSync, H: Hasher + CondSync, C: HamtConfig> std::fmt::Debug
+    for Pointer<K, V, H, C>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -197,13 +205,15 @@ impl<K: Debug + CondSync, V: Debug + CondSync, H: Hasher + CondSync> std::fmt::D
     }
 }
 
-impl<K: CondSync, V: CondSync, H: Hasher + CondSync> Default for Pointer<K, V, H> {
+impl<K: CondSync, V: CondSync, H: Hasher + CondSync, C: HamtConfig> Default
+    for Pointer<K, V, H, C>
+{
     fn default() -> Self {
         Pointer::Values(Vec::new())
     }
 }
 
-impl<K, V, H: Hasher + CondSync> PartialEq for Pointer<K, V, H>
+impl<K, V, H: Hasher + CondSync, C: HamtConfig> PartialEq for Pointer<K, V, H, C>
 where
     K: Storable + PartialEq + CondSync,
     V: Storable + PartialEq + CondSync,
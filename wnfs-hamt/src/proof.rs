@@ -0,0 +1,302 @@
+use crate::{HamtConfig, Hasher, Node};
+use anyhow::Result;
+use libipld::Cid;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use wnfs_common::{
+    utils::CondSync, BlockStore, HashOutput, MemoryBlockStore, Storable, VerifyingBlockStore,
+};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A proof that a key is, or isn't, present in a HAMT.
+///
+/// It consists of the blocks of all nodes on the hash path of the key, starting at the root.
+/// This is enough for a verifier that only knows the root CID to look up the key,
+/// see [`verify_proof`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HamtProof {
+    blocks: Vec<(Cid, ByteBuf)>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl HamtProof {
+    /// Creates an empty proof.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a block to the proof.
+    pub fn push_block(&mut self, cid: Cid, bytes: impl Into<Vec<u8>>) {
+        self.blocks.push((cid, ByteBuf::from(bytes.into())));
+    }
+
+    /// Returns the CIDs and bytes of the blocks in this proof, in order from the root.
+    pub fn blocks(&self) -> impl Iterator<Item = (&Cid, &[u8])> {
+        self.blocks
+            .iter()
+            .map(|(cid, bytes)| (cid, bytes.as_slice()))
+    }
+
+    /// Returns the number of blocks in this proof.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true if the proof contains no blocks.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Puts the blocks of this proof into a block store, checking that each block
+    /// matches its CID.
+    ///
+    /// Looking up keys with this store fails when a required block is missing from the proof.
+    pub async fn into_block_store(self) -> Result<VerifyingBlockStore<MemoryBlockStore>> {
+        let store = VerifyingBlockStore::new(MemoryBlockStore::new());
+        for (cid, bytes) in self.blocks {
+            store.put_block_keyed(cid, bytes.into_vec()).await?;
+        }
+
+        Ok(store)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Verifies a proof created with [`Node::prove`] against the CID of the root node.
+///
+/// Returns the value for the key if the proof shows that it is present, or `None` if the
+/// proof shows that it is absent. Fails if the proof is incomplete or any of its blocks
+/// don't match their CIDs.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use wnfs_hamt::{verify_proof, DefaultConfig, Node};
+/// use wnfs_common::{MemoryBlockStore, Storable};
+///
+/// #[async_std::main]
+/// async fn main() {
+///     let store = &MemoryBlockStore::new();
+///     let mut node = Arc::new(Node::<String, usize>::default());
+///     for i in 0..100 {
+///         node.set(i.to_string(), i, store).await.unwrap();
+///     }
+///
+///     let root_cid = node.store(store).await.unwrap();
+///     let key = "42".to_string();
+///     let proof = Node::<String, usize>::prove(&root_cid, &key, store).await.unwrap();
+///
+///     let value = verify_proof::<_, usize, blake3::Hasher, DefaultConfig>(&root_cid, &key, &proof)
+///         .await
+///         .unwrap();
+///
+///     assert_eq!(value, Some(42));
+/// }
+/// ```
+pub async fn verify_proof<K, V, H, C>(
+    root_cid: &Cid,
+    key: &K,
+    proof: &HamtProof,
+) -> Result<Option<V>>
+where
+    K: Storable + AsRef<[u8]> + CondSync,
+    V: Storable + Clone + CondSync,
+    K::Serializable: Serialize + DeserializeOwned,
+    V::Serializable: Serialize + DeserializeOwned,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    verify_proof_by_hash::<K, V, H, C>(root_cid, &H::hash(key), proof).await
+}
+
+/// Verifies a proof created with [`Node::prove_by_hash`] against the CID of the root node.
+///
+/// See [`verify_proof`].
+pub async fn verify_proof_by_hash<K, V, H, C>(
+    root_cid: &Cid,
+    hash: &HashOutput,
+    proof: &HamtProof,
+) -> Result<Option<V>>
+where
+    K: Storable + AsRef<[u8]> + CondSync,
+    V: Storable + Clone + CondSync,
+    K::Serializable: Serialize + DeserializeOwned,
+    V::Serializable: Serialize + DeserializeOwned,
+    H: Hasher + CondSync,
+    C: HamtConfig,
+{
+    let store = &proof.clone().into_block_store().await?;
+    let root = Node::<K, V, H, C>::load(root_cid, store).await?;
+    Ok(root.get_by_hash(hash, store).await?.cloned())
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, DefaultConfig};
+    use wnfs_common::utils::Arc;
+
+    async fn node_with_values(store: &impl BlockStore) -> Result<(Arc<Node<String, u64>>, Cid)> {
+        let mut node = Arc::new(Node::<String, u64>::default());
+        for i in 0..500 {
+            node.set(i.to_string(), i, store).await?;
+        }
+
+        let cid = node.store(store).await?;
+        Ok((node, cid))
+    }
+
+    #[async_std::test]
+    async fn proof_of_inclusion_verifies_with_value() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let (_, root_cid) = node_with_values(store).await?;
+
+        for i in [0, 42, 499] {
+            let key = i.to_string();
+            let proof = Node::<String, u64>::prove(&root_cid, &key, store).await?;
+            let value =
+                verify_proof::<_, u64, blake3::Hasher, DefaultConfig>(&root_cid, &key, &proof)
+                    .await?;
+
+            assert_eq!(value, Some(i));
+        }
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn proof_of_exclusion_verifies_as_absent() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let (_, root_cid) = node_with_values(store).await?;
+
+        let key = "not in the hamt".to_string();
+        let proof = Node::<String, u64>::prove(&root_cid, &key, store).await?;
+        let value =
+            verify_proof::<_, u64, blake3::Hasher, DefaultConfig>(&root_cid, &key, &proof).await?;
+
+        assert_eq!(value, None);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn proof_only_contains_hash_path() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let (_, root_cid) = node_with_values(store).await?;
+
+        let proof = Node::<String, u64>::prove(&root_cid, &"42".to_string(), store).await?;
+
+        // The trie holds these values in three levels, the proof has one block per level
+        assert!(!proof.is_empty());
+        assert!(proof.len() <= 3);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn proof_for_other_key_or_root_fails() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let (node, root_cid) = node_with_values(store).await?;
+        let mut other = Arc::clone(&node);
+        other.set("extra".into(), 0, store).await?;
+        let other_root_cid = other.store(store).await?;
+
+        let proof = Node::<String, u64>::prove(&root_cid, &"42".to_string(), store).await?;
+
+        // The root block of the proof doesn't match the other root
+        let result = verify_proof::<_, u64, blake3::Hasher, DefaultConfig>(
+            &other_root_cid,
+            &"42".to_string(),
+            &proof,
+        )
+        .await;
+        assert!(result.is_err());
+
+        // A proof that doesn't cover the hash path of a key can't show anything about it
+        let key = (0..500)
+            .map(|i| i.to_string())
+            .find(|key| blake3::Hasher::hash(key)[0] >> 4 != blake3::Hasher::hash(&"42")[0] >> 4)
+            .unwrap();
+        let result =
+            verify_proof::<_, u64, blake3::Hasher, DefaultConfig>(&root_cid, &key, &proof).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn tampered_proof_fails() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let (_, root_cid) = node_with_values(store).await?;
+
+        let key = "42".to_string();
+        let proof = Node::<String, u64>::prove(&root_cid, &key, store).await?;
+
+        let mut tampered = HamtProof::new();
+        for (i, (cid, bytes)) in proof.blocks().enumerate() {
+            let mut bytes = bytes.to_vec();
+            if i == 0 {
+                *bytes.last_mut().unwrap() ^= 1;
+            }
+            tampered.push_block(*cid, bytes);
+        }
+
+        let result =
+            verify_proof::<_, u64, blake3::Hasher, DefaultConfig>(&root_cid, &key, &tampered).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn proving_path_through_unstored_nodes_fails() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let mut node = Arc::new(Node::<String, u64>::default());
+        for i in 0..500 {
+            node.set(i.to_string(), i, store).await?;
+        }
+
+        let mut proof = HamtProof::new();
+        let result = node
+            .prove_path_by_hash(&blake3::Hasher::hash(&"42"), &mut proof, store)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn proofs_work_with_custom_config() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let mut node = Arc::new(Node::<String, u64, blake3::Hasher, Config<2, 1>>::default());
+        for i in 0..100 {
+            node.set(i.to_string(), i, store).await?;
+        }
+        let root_cid = node.store(store).await?;
+
+        let key = "7".to_string();
+        let proof =
+            Node::<String, u64, blake3::Hasher, Config<2, 1>>::prove(&root_cid, &key, store)
+                .await?;
+        let value =
+            verify_proof::<_, u64, blake3::Hasher, Config<2, 1>>(&root_cid, &key, &proof).await?;
+
+        assert_eq!(value, Some(7));
+
+        Ok(())
+    }
+}
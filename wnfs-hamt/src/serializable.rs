@@ -1,4 +1,4 @@
-use crate::constants::HAMT_BITMASK_BYTE_SIZE;
+use crate::constants::{HAMT_BIT_WIDTH, HAMT_VALUES_BUCKET_SIZE};
 use libipld::{
     cid::serde::{BytesToCidVisitor, CID_SERDE_PRIVATE_IDENTIFIER},
     Cid,
@@ -8,7 +8,6 @@ use serde::{
     de::{SeqAccess, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
 };
-use serde_byte_array::ByteArray;
 use serde_bytes::ByteBuf;
 use std::marker::PhantomData;
 
@@ -21,11 +20,23 @@ pub struct HamtSerializable<K, V> {
     pub(crate) root: NodeSerializable<K, V>,
     pub(crate) version: Version,
     pub(crate) structure: String,
+    #[serde(
+        rename = "bitWidth",
+        default = "default_bit_width",
+        skip_serializing_if = "is_default_bit_width"
+    )]
+    pub(crate) bit_width: u8,
+    #[serde(
+        rename = "bucketSize",
+        default = "default_bucket_size",
+        skip_serializing_if = "is_default_bucket_size"
+    )]
+    pub(crate) bucket_size: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NodeSerializable<K, V>(
-    pub(crate) ByteArray<HAMT_BITMASK_BYTE_SIZE>,
+    pub(crate) ByteBuf,
     pub(crate) Vec<PointerSerializable<K, V>>,
 );
 
@@ -39,6 +50,25 @@ pub(crate) enum PointerSerializable<K, V> {
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
+// HAMTs with the default config omit it, so that their encoding stays the same as before
+// the config was recorded.
+
+fn default_bit_width() -> u8 {
+    HAMT_BIT_WIDTH
+}
+
+fn is_default_bit_width(bit_width: &u8) -> bool {
+    *bit_width == HAMT_BIT_WIDTH
+}
+
+fn default_bucket_size() -> usize {
+    HAMT_VALUES_BUCKET_SIZE
+}
+
+fn is_default_bucket_size(bucket_size: &usize) -> bool {
+    *bucket_size == HAMT_VALUES_BUCKET_SIZE
+}
+
 impl<K: Serialize, V: Serialize> Serialize for PointerSerializable<K, V> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         match *self {
@@ -0,0 +1,23 @@
+#![cfg(test)]
+use crate::Hasher;
+use wnfs_common::HashOutput;
+
+//--------------------------------------------------------------------------------------------------
+// Types
+//--------------------------------------------------------------------------------------------------
+
+/// A hasher that puts keys into different places than the default hasher.
+#[derive(Debug)]
+pub(crate) struct ReversedHasher;
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl Hasher for ReversedHasher {
+    fn hash<D: AsRef<[u8]>>(data: &D) -> HashOutput {
+        let mut hash = blake3::Hasher::hash(data);
+        hash.reverse();
+        hash
+    }
+}
@@ -1,8 +1,11 @@
 mod changes;
+mod hasher;
 mod kv;
 mod operations;
 
 #[cfg(test)]
 pub(crate) use changes::*;
+#[cfg(test)]
+pub(crate) use hasher::*;
 pub use kv::*;
 pub use operations::*;
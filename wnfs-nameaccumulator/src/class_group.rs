@@ -0,0 +1,489 @@
+//! Arithmetic in class groups of imaginary quadratic fields.
+//!
+//! Elements are reduced, positive definite binary quadratic forms `(a, b, c)` with
+//! discriminant `b^2 - 4ac = -d` for a prime `d = 7 mod 8` with less than 2046 bits.
+//!
+//! Forms are packed into a single unsigned number `a * 2^1024 + (b + a)`, so they
+//! fit the same 256 bytes as an element of a 2048-bit RSA group.
+//! `c` is determined by `a`, `b` and the discriminant.
+//! The identity form `(1, 1, (1 + d) / 4)` is packed as `1` instead,
+//! so that `1` is the identity in both the RSA and the class group.
+
+#[cfg(feature = "num-bigint-dig")]
+use num_bigint_dig::{BigInt, ExtendedGcd, Sign};
+#[cfg(feature = "rug")]
+use rug::Integer;
+use std::cmp::Ordering;
+
+/// The number of bits the `b + a` part of a packed form is shifted by.
+const PACKING_SHIFT: usize = 1024;
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Signed big integer math functions required to compute in class groups.
+pub(crate) trait ClassGroupInt: Clone + Ord {
+    fn from_u32(n: u32) -> Self;
+
+    fn add(&self, other: &Self) -> Self;
+
+    fn sub(&self, other: &Self) -> Self;
+
+    fn mul(&self, other: &Self) -> Self;
+
+    fn neg(&self) -> Self;
+
+    /// Computes `2 ^ exponent`.
+    fn pow2(exponent: usize) -> Self;
+
+    /// Returns `(quotient, remainder)` with `0 <= remainder < divisor`,
+    /// given a positive divisor.
+    fn div_rem_floor(&self, divisor: &Self) -> (Self, Self);
+
+    /// Returns `(gcd, x, y)` with `x * self + y * other = gcd`,
+    /// given non-negative `self` and `other`.
+    fn extended_gcd(&self, other: &Self) -> (Self, Self, Self);
+
+    /// The number of bits needed to represent this non-negative number.
+    fn bits(&self) -> usize;
+
+    /// Returns whether the bit at given index is set in this non-negative number.
+    fn bit(&self, index: usize) -> bool;
+}
+
+/// A binary quadratic form `a x^2 + b xy + c y^2`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Form<I> {
+    a: I,
+    b: I,
+    c: I,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Composes two packed forms of the class group with discriminant `-discriminant`.
+///
+/// Returns `None` if either of the inputs isn't a packed reduced form.
+pub(crate) fn compose<I: ClassGroupInt>(x: &I, y: &I, discriminant: &I) -> Option<I> {
+    let x = Form::unpack(x, discriminant)?;
+    let y = Form::unpack(y, discriminant)?;
+    Some(x.compose(&y, discriminant).pack())
+}
+
+/// Computes the packed form `base` to the power of the product of `exponents`.
+///
+/// Returns `None` if `base` isn't a packed reduced form.
+pub(crate) fn pow_product<'a, I: ClassGroupInt + 'a>(
+    base: &I,
+    exponents: impl Iterator<Item = &'a I>,
+    discriminant: &I,
+) -> Option<I> {
+    let mut result = Form::unpack(base, discriminant)?;
+    for exponent in exponents {
+        result = result.pow(exponent, discriminant);
+    }
+    Some(result.pack())
+}
+
+/// Computes the inverse of a packed form.
+///
+/// Returns `None` if `x` isn't a packed reduced form.
+pub(crate) fn inverse<I: ClassGroupInt>(x: &I, discriminant: &I) -> Option<I> {
+    let x = Form::unpack(x, discriminant)?;
+    let inverse = Form {
+        a: x.a,
+        b: x.b.neg(),
+        c: x.c,
+    };
+    Some(inverse.reduce().pack())
+}
+
+/// Returns whether given number is the canonical packing of a
+/// reduced form in the class group with discriminant `-discriminant`.
+pub(crate) fn is_element<I: ClassGroupInt>(x: &I, discriminant: &I) -> bool {
+    let one = I::from_u32(1);
+    if x == &one {
+        return true;
+    }
+
+    let (a, b_plus_a) = x.div_rem_floor(&I::pow2(PACKING_SHIFT));
+    if a <= one {
+        // a = 1 is only valid for the identity, which is packed as 1
+        return false;
+    }
+
+    let b = b_plus_a.sub(&a);
+    let (c, remainder) = b
+        .mul(&b)
+        .add(discriminant)
+        .div_rem_floor(&a.mul(&I::from_u32(4)));
+    if remainder != I::from_u32(0) {
+        return false;
+    }
+
+    let form = Form { a, b, c };
+    form.is_reduced() && &form.pack() == x
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl<I: ClassGroupInt> Form<I> {
+    fn identity(discriminant: &I) -> Self {
+        let (c, _) = discriminant
+            .add(&I::from_u32(1))
+            .div_rem_floor(&I::from_u32(4));
+        Self {
+            a: I::from_u32(1),
+            b: I::from_u32(1),
+            c,
+        }
+    }
+
+    /// Unpacks a form, if it's the canonical packing of a reduced form.
+    fn unpack(packed: &I, discriminant: &I) -> Option<Self> {
+        if packed == &I::from_u32(1) {
+            return Some(Self::identity(discriminant));
+        }
+
+        if !is_element(packed, discriminant) {
+            return None;
+        }
+
+        let (a, b_plus_a) = packed.div_rem_floor(&I::pow2(PACKING_SHIFT));
+        let b = b_plus_a.sub(&a);
+        let (c, _) = b
+            .mul(&b)
+            .add(discriminant)
+            .div_rem_floor(&a.mul(&I::from_u32(4)));
+        Some(Self { a, b, c })
+    }
+
+    /// Packs a reduced form.
+    fn pack(&self) -> I {
+        if self.a == I::from_u32(1) {
+            return I::from_u32(1);
+        }
+
+        self.a
+            .mul(&I::pow2(PACKING_SHIFT))
+            .add(&self.b.add(&self.a))
+    }
+
+    fn is_reduced(&self) -> bool {
+        let zero = I::from_u32(0);
+        let minus_a = self.a.neg();
+        if self.b <= minus_a || self.b > self.a || self.a > self.c {
+            return false;
+        }
+
+        !(self.a == self.c && self.b < zero)
+    }
+
+    /// Reduces a positive definite form.
+    ///
+    /// This is Algorithm 5.4.2 from Henri Cohen's
+    /// "A Course in Computational Algebraic Number Theory".
+    fn reduce(mut self) -> Self {
+        let zero = I::from_u32(0);
+        let two = I::from_u32(2);
+        loop {
+            let minus_a = self.a.neg();
+            if self.b <= minus_a || self.b > self.a {
+                let two_a = self.a.mul(&two);
+                let (mut q, mut r) = self.b.div_rem_floor(&two_a);
+                if r > self.a {
+                    r = r.sub(&two_a);
+                    q = q.add(&I::from_u32(1));
+                }
+                // b + r = 2aq + 2r is even
+                let (half_b_plus_r, _) = self.b.add(&r).div_rem_floor(&two);
+                self.c = self.c.sub(&half_b_plus_r.mul(&q));
+                self.b = r;
+            }
+
+            match self.a.cmp(&self.c) {
+                Ordering::Greater => {
+                    std::mem::swap(&mut self.a, &mut self.c);
+                    self.b = self.b.neg();
+                }
+                Ordering::Equal if self.b < zero => {
+                    self.b = self.b.neg();
+                    return self;
+                }
+                _ => return self,
+            }
+        }
+    }
+
+    /// Composes two forms of the same discriminant and reduces the result.
+    ///
+    /// This is Algorithm 5.4.7 from Henri Cohen's
+    /// "A Course in Computational Algebraic Number Theory".
+    fn compose(&self, other: &Self, discriminant: &I) -> Self {
+        let zero = I::from_u32(0);
+        let two = I::from_u32(2);
+        let (f1, f2) = if self.a > other.a {
+            (other, self)
+        } else {
+            (self, other)
+        };
+
+        let (s, _) = f1.b.add(&f2.b).div_rem_floor(&two);
+        let n = f2.b.sub(&s);
+
+        let (y1, d) = if f2.a.div_rem_floor(&f1.a).1 == zero {
+            (zero.clone(), f1.a.clone())
+        } else {
+            let (d, u, _) = f2.a.extended_gcd(&f1.a);
+            (u, d)
+        };
+
+        let (x2, y2, d1) = if s.div_rem_floor(&d).1 == zero {
+            (zero.clone(), I::from_u32(1).neg(), d)
+        } else {
+            let (d1, x2, y2) = if s < zero {
+                let (d1, x2, y2) = s.neg().extended_gcd(&d);
+                (d1, x2.neg(), y2)
+            } else {
+                s.extended_gcd(&d)
+            };
+            (x2, y2.neg(), d1)
+        };
+
+        let (v1, _) = f1.a.div_rem_floor(&d1);
+        let (v2, _) = f2.a.div_rem_floor(&d1);
+        let (_, r) = y1.mul(&y2).mul(&n).sub(&x2.mul(&f2.c)).div_rem_floor(&v1);
+
+        let b = f2.b.add(&v2.mul(&r).mul(&two));
+        let a = v1.mul(&v2);
+        let (c, _) = b
+            .mul(&b)
+            .add(discriminant)
+            .div_rem_floor(&a.mul(&I::from_u32(4)));
+
+        Self { a, b, c }.reduce()
+    }
+
+    /// Exponentiation by left-to-right square-and-multiply.
+    fn pow(&self, exponent: &I, discriminant: &I) -> Self {
+        let mut result = Self::identity(discriminant);
+        for index in (0..exponent.bits()).rev() {
+            result = result.compose(&result, discriminant);
+            if exponent.bit(index) {
+                result = result.compose(self, discriminant);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(feature = "num-bigint-dig")]
+impl ClassGroupInt for BigInt {
+    fn from_u32(n: u32) -> Self {
+        BigInt::from(n)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    fn pow2(exponent: usize) -> Self {
+        BigInt::from(1u8) << exponent
+    }
+
+    fn div_rem_floor(&self, divisor: &Self) -> (Self, Self) {
+        num_integer::Integer::div_mod_floor(self, divisor)
+    }
+
+    fn extended_gcd(&self, other: &Self) -> (Self, Self, Self) {
+        ExtendedGcd::extended_gcd(self, other)
+    }
+
+    fn bits(&self) -> usize {
+        BigInt::bits(self)
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        match self.to_bytes_le() {
+            (Sign::NoSign, _) => false,
+            (_, bytes) => bytes
+                .get(index / 8)
+                .is_some_and(|byte| byte & (1 << (index % 8)) != 0),
+        }
+    }
+}
+
+#[cfg(feature = "rug")]
+impl ClassGroupInt for Integer {
+    fn from_u32(n: u32) -> Self {
+        Integer::from(n)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Integer::from(self + other)
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Integer::from(self - other)
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Integer::from(self * other)
+    }
+
+    fn neg(&self) -> Self {
+        Integer::from(-self)
+    }
+
+    fn pow2(exponent: usize) -> Self {
+        Integer::from(1) << exponent as u32
+    }
+
+    fn div_rem_floor(&self, divisor: &Self) -> (Self, Self) {
+        self.clone().div_rem_floor(divisor.clone())
+    }
+
+    fn extended_gcd(&self, other: &Self) -> (Self, Self, Self) {
+        Integer::extended_gcd(self.clone(), other.clone(), Integer::new())
+    }
+
+    fn bits(&self) -> usize {
+        self.significant_bits() as usize
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.get_bit(index as u32)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(feature = "num-bigint-dig")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prop_assert_eq;
+    use test_strategy::proptest;
+
+    /// The class group with discriminant -71 has class number 7.
+    const SMALL_DISCRIMINANT: u32 = 71;
+
+    /// The packed form `(2, 1, (1 + d) / 8)`.
+    ///
+    /// It's a valid form, since the discriminant is `-d = 1 mod 8`.
+    fn generator<I: ClassGroupInt>(discriminant: &I) -> I {
+        let (c, _) = discriminant
+            .add(&I::from_u32(1))
+            .div_rem_floor(&I::from_u32(8));
+        Form {
+            a: I::from_u32(2),
+            b: I::from_u32(1),
+            c,
+        }
+        .reduce()
+        .pack()
+    }
+
+    fn form(a: i32, b: i32, c: i32) -> Form<BigInt> {
+        Form {
+            a: BigInt::from(a),
+            b: BigInt::from(b),
+            c: BigInt::from(c),
+        }
+    }
+
+    #[test]
+    fn class_number_of_small_discriminant() {
+        let d = &BigInt::from(SMALL_DISCRIMINANT);
+        let g = &generator(d);
+        let mut x = g.clone();
+        for _ in 1..7 {
+            assert_ne!(x, BigInt::from(1));
+            x = compose(&x, g, d).unwrap();
+        }
+        assert_eq!(x, BigInt::from(1));
+    }
+
+    #[test]
+    fn reduces_to_known_forms() {
+        let d = &BigInt::from(SMALL_DISCRIMINANT);
+        // All seven reduced forms of discriminant -71
+        let reduced = [
+            form(1, 1, 18),
+            form(2, 1, 9),
+            form(2, -1, 9),
+            form(3, 1, 6),
+            form(3, -1, 6),
+            form(4, 3, 5),
+            form(4, -3, 5),
+        ];
+        let g = Form::unpack(&generator(d), d).unwrap();
+        let mut x = Form::identity(d);
+        for _ in 0..7 {
+            assert!(x.is_reduced());
+            assert!(reduced.contains(&x));
+            x = x.compose(&g, d);
+        }
+        assert_eq!(form(9, 1, 2).reduce(), form(2, -1, 9));
+    }
+
+    #[proptest(cases = 64)]
+    fn group_laws_hold(x: u32, y: u32, z: u32) {
+        let d = &BigInt::from(1_000_000_007u32); // 7 mod 8 and prime
+        let g = &generator(d);
+        let pow = |e: u64| pow_product(g, [BigInt::from(e)].iter(), d).unwrap();
+        let (gx, gy, gz) = (&pow(x as u64), &pow(y as u64), &pow(z as u64));
+
+        prop_assert_eq!(
+            compose(gx, &compose(gy, gz, d).unwrap(), d),
+            compose(&compose(gx, gy, d).unwrap(), gz, d)
+        );
+        prop_assert_eq!(compose(gx, gy, d), Some(pow(x as u64 + y as u64)));
+        prop_assert_eq!(
+            compose(gx, &inverse(gx, d).unwrap(), d),
+            Some(BigInt::from(1))
+        );
+        prop_assert_eq!(compose(gx, &BigInt::from(1), d), Some(gx.clone()));
+        prop_assert_eq!(is_element(gx, d), true);
+    }
+
+    #[test]
+    fn rejects_non_canonical_packings() {
+        let d = &BigInt::from(SMALL_DISCRIMINANT);
+        let shift = BigInt::pow2(PACKING_SHIFT);
+        let pack = |a: i32, b: i32| BigInt::from(a) * &shift + BigInt::from(b + a);
+
+        assert!(is_element(&pack(2, 1), d));
+        assert!(!is_element(&pack(1, 1), d)); // identity must be packed as 1
+        assert!(!is_element(&pack(9, 1), d)); // not reduced
+        assert!(!is_element(&pack(2, 3), d)); // not reduced
+        assert!(!is_element(&pack(5, 1), d)); // wrong discriminant
+        assert!(!is_element(&BigInt::from(0), d));
+
+        let valid = &pack(2, 1);
+        let invalid = &pack(9, 1);
+        assert_eq!(compose(valid, invalid, d), None);
+        assert_eq!(compose(invalid, valid, d), None);
+        assert_eq!(pow_product(invalid, [BigInt::from(3)].iter(), d), None);
+        assert_eq!(inverse(invalid, d), None);
+    }
+}
@@ -19,4 +19,16 @@ pub enum VerificationError {
 
     #[error("Couldn't invert base accumulator state")]
     NoInverse,
+
+    #[error("Accumulator state or proof isn't a valid group element")]
+    InvalidGroupElement,
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum SetupError {
+    #[error("Class group discriminant needs to be a prime d = 7 mod 8 with 1024 to 2045 bits")]
+    InvalidDiscriminant,
+
+    #[error("Setup generator isn't a valid non-identity group element")]
+    InvalidGenerator,
 }
@@ -1,4 +1,4 @@
-use crate::Big;
+use crate::{error::VerificationError, AccumulatorGroup, Big};
 use blake3::traits::digest::{ExtendableOutput, ExtendableOutputReset};
 use num_traits::One;
 
@@ -13,19 +13,36 @@ const TEST_DSI: &str = "rs-wnfs tests";
 /// `base_i ^ (product of exponent_j with j != i)`.
 pub(crate) fn multi_exp<B: Big>(
     bases_and_exponents: &[(B::Num, B::Num)],
+    group: AccumulatorGroup,
     modulus: &B::Num,
-) -> B::Num {
+) -> Result<B::Num, VerificationError> {
     match bases_and_exponents {
-        &[] => B::Num::one(),
-        [(base, _)] => base.clone() % modulus,
+        &[] => Ok(B::Num::one()),
+        [(base, _)] => match group {
+            AccumulatorGroup::Rsa => Ok(base.clone() % modulus),
+            AccumulatorGroup::ClassGroup if group.is_element::<B>(base, modulus) => {
+                Ok(base.clone())
+            }
+            AccumulatorGroup::ClassGroup => Err(VerificationError::InvalidGroupElement),
+        },
         other => {
             let mid = other.len() / 2;
             let (left, right) = other.split_at(mid);
             let x_star_left = nlogn_product::<_, B>(left, |(_, x_i)| x_i);
             let x_star_right = nlogn_product::<_, B>(right, |(_, x_i)| x_i);
-            (B::modpow(&multi_exp::<B>(left, modulus), &x_star_right, modulus)
-                * B::modpow(&multi_exp::<B>(right, modulus), &x_star_left, modulus))
-                % modulus
+            group.op::<B>(
+                &group.pow::<B>(
+                    &multi_exp::<B>(left, group, modulus)?,
+                    &x_star_right,
+                    modulus,
+                )?,
+                &group.pow::<B>(
+                    &multi_exp::<B>(right, group, modulus)?,
+                    &x_star_left,
+                    modulus,
+                )?,
+                modulus,
+            )
         }
     }
 }
@@ -99,6 +116,59 @@ pub(crate) fn blake3_prime_digest_fast<B: Big>(
     }
 }
 
+/// Finalizes a hashing function to the absolute value `d` of a class group discriminant `-d`.
+///
+/// `d` is a prime with `d = 7 mod 8` and exactly `hash_len * 8` bits.
+///
+/// The output includes both `d` and a 32-bit counter
+/// that helps verifying the discriminant digest.
+pub(crate) fn blake3_discriminant_digest<B: Big>(
+    domain_separation_info: &str,
+    bytes: impl AsRef<[u8]>,
+    hash_len: usize,
+) -> (B::Num, u32) {
+    let mut counter: u32 = 0;
+    loop {
+        if let Some(discriminant) = blake3_discriminant_digest_fast::<B>(
+            domain_separation_info,
+            bytes.as_ref(),
+            hash_len,
+            counter,
+        ) {
+            return (discriminant, counter);
+        }
+
+        counter += 1;
+    }
+}
+
+/// Finalizes a discriminant digest fast, given the counter from a previous
+/// invocation of `blake3_discriminant_digest`.
+/// This will make sure that the returned discriminant is prime.
+pub(crate) fn blake3_discriminant_digest_fast<B: Big>(
+    domain_separation_info: &str,
+    bytes: impl AsRef<[u8]>,
+    hash_len: usize,
+    counter: u32,
+) -> Option<B::Num> {
+    let mut hash = vec![0u8; hash_len];
+    let mut hasher = blake3::Hasher::new_derive_key(domain_separation_info);
+    hasher.update(bytes.as_ref());
+    hasher.update(&counter.to_le_bytes());
+    hasher.finalize_xof_into(&mut hash);
+
+    hash[0] |= 0x80;
+    hash[hash_len - 1] |= 0x07;
+
+    let candidate = B::from_bytes_be(&hash);
+
+    if !B::is_probably_prime(&candidate) {
+        None
+    } else {
+        Some(candidate)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{blake3_prime_digest, TEST_DSI};
@@ -120,7 +190,7 @@ mod tests {
 mod proptests {
     use crate::{
         fns::{blake3_prime_digest, blake3_prime_digest_fast, multi_exp, nlogn_product, TEST_DSI},
-        BigNumDig,
+        AccumulatorGroup, BigNumDig,
     };
     use num_bigint_dig::{prime::probably_prime, BigUint, RandPrime};
     use num_traits::One;
@@ -151,7 +221,8 @@ mod proptests {
             .map(|(b, e)| (BigUint::from(*b), BigUint::from(*e)))
             .collect();
 
-        let actual = multi_exp::<BigNumDig>(&bases_and_exponents, &modulus);
+        let actual =
+            multi_exp::<BigNumDig>(&bases_and_exponents, AccumulatorGroup::Rsa, &modulus).unwrap();
         let expected = multi_exp_naive(&bases_and_exponents, &modulus);
         prop_assert_eq!(actual, expected);
     }
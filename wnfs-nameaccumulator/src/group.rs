@@ -0,0 +1,121 @@
+use crate::{error::VerificationError, Big};
+use serde::{Deserialize, Serialize};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The group of unknown order that name accumulators are computed in.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum AccumulatorGroup {
+    /// The multiplicative group of integers modulo a 2048-bit RSA modulus.
+    ///
+    /// Its setup requires trusting that nobody knows the modulus' prime factors.
+    #[default]
+    #[serde(rename = "rsa")]
+    Rsa,
+    /// The class group of an imaginary quadratic field, given by its
+    /// negative prime discriminant `-d` with `d = 7 mod 8`.
+    ///
+    /// Its setup doesn't need to be trusted, since computing the group's order
+    /// is believed to be hard even for whoever picked the discriminant.
+    ///
+    /// Elements are reduced binary quadratic forms `(a, b, c)`, represented
+    /// as the number `a * 2^1024 + (b + a)`, except for the identity, which
+    /// is represented as `1`. This fits 256 bytes for discriminants with
+    /// less than 2046 bits.
+    #[serde(rename = "class-group")]
+    ClassGroup,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl AccumulatorGroup {
+    /// Returns whether this is the RSA group.
+    pub fn is_rsa(&self) -> bool {
+        matches!(self, Self::Rsa)
+    }
+
+    /// Computes the group operation of `x` and `y`.
+    ///
+    /// `modulus` is the RSA modulus or the absolute value of the class group discriminant.
+    /// Fails if an argument isn't the canonical representation of a class group element.
+    pub(crate) fn op<B: Big>(
+        self,
+        x: &B::Num,
+        y: &B::Num,
+        modulus: &B::Num,
+    ) -> Result<B::Num, VerificationError> {
+        match self {
+            Self::Rsa => {
+                let mut product = x.clone();
+                product *= y;
+                Ok(product % modulus)
+            }
+            Self::ClassGroup => {
+                B::class_group_compose(x, y, modulus).ok_or(VerificationError::InvalidGroupElement)
+            }
+        }
+    }
+
+    /// Computes `base` to the power of `exponent`.
+    pub(crate) fn pow<B: Big>(
+        self,
+        base: &B::Num,
+        exponent: &B::Num,
+        modulus: &B::Num,
+    ) -> Result<B::Num, VerificationError> {
+        match self {
+            Self::Rsa => Ok(B::modpow(base, exponent, modulus)),
+            Self::ClassGroup => {
+                B::class_group_pow_product(base, Some(exponent).into_iter(), modulus)
+                    .ok_or(VerificationError::InvalidGroupElement)
+            }
+        }
+    }
+
+    /// Computes `base` to the power of the product of `exponents`.
+    pub(crate) fn pow_product<'a, B: Big>(
+        self,
+        base: &B::Num,
+        exponents: impl Iterator<Item = &'a B::Num>,
+        modulus: &B::Num,
+    ) -> Result<B::Num, VerificationError>
+    where
+        B::Num: 'a,
+    {
+        match self {
+            Self::Rsa => Ok(B::modpow_product(base, exponents, modulus)),
+            Self::ClassGroup => B::class_group_pow_product(base, exponents, modulus)
+                .ok_or(VerificationError::InvalidGroupElement),
+        }
+    }
+
+    /// Computes the inverse of `x`.
+    pub(crate) fn inverse<B: Big>(
+        self,
+        x: &B::Num,
+        modulus: &B::Num,
+    ) -> Result<B::Num, VerificationError> {
+        match self {
+            Self::Rsa => B::mod_inv(x, modulus).ok_or(VerificationError::NoInverse),
+            Self::ClassGroup => {
+                B::class_group_inverse(x, modulus).ok_or(VerificationError::InvalidGroupElement)
+            }
+        }
+    }
+
+    /// Returns whether `x` is the canonical representation of a group element.
+    ///
+    /// In the RSA group, any number represents the element it's congruent to.
+    pub(crate) fn is_element<B: Big>(self, x: &B::Num, modulus: &B::Num) -> bool {
+        match self {
+            Self::Rsa => true,
+            Self::ClassGroup => B::class_group_is_element(x, modulus),
+        }
+    }
+}
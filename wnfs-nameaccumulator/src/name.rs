@@ -4,22 +4,33 @@ use crate::BigNumDig;
 #[cfg(feature = "rug")]
 use crate::BigNumRug;
 use crate::{
-    error::VerificationError,
-    fns::{blake3_prime_digest, blake3_prime_digest_fast, multi_exp},
+    error::{SetupError, VerificationError},
+    fns::{blake3_discriminant_digest, blake3_prime_digest, blake3_prime_digest_fast, multi_exp},
     traits::Big,
+    AccumulatorGroup,
 };
 use anyhow::Result;
 use libipld::Cid;
 use num_traits::{One, Zero};
 use once_cell::sync::OnceCell;
 use rand_core::CryptoRngCore;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{hash::Hash, str::FromStr};
 use wnfs_common::{BlockStore, Storable};
 
 /// The domain separation string for deriving the l hash in the PoKE* protocol.
 const L_HASH_DSI: &str = "wnfs/1.0/PoKE*/l 128-bit hash derivation";
 
+/// The domain separation string for deriving class group discriminants from a seed.
+const DISCRIMINANT_DSI: &str = "wnfs/1.0/class group discriminant derivation";
+
+/// The byte length of class group discriminants derived from a seed.
+const DISCRIMINANT_BYTE_LEN: usize = 255;
+
+/// The absolute value of the class group discriminant derived from the seed `"rs-wnfs"`.
+/// The discriminant digest's counter is 2723.
+const CLASS_GROUP_2040_DISCRIMINANT: &str = "110491335081517044301343459204694074542375458398393852253504361105224962376582272008822182320362363627109257426597724879252899928769082716109650184810273659820917088381451352149387274353323476966138822538838037822017663559336729500609773745709982075952951863228688329472405664532793511579858255853993290370533950282875798774691497061296671059416377220358959579921672070746377099283854731511329774384566324443755421069458042258131491231997710970346073245061806230077612096214164515811000305784110712791303305771762213040854433985627632456580820811645210684656376834366486627441690236256131112226226322102526459375239";
+
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
 //--------------------------------------------------------------------------------------------------
@@ -34,7 +45,7 @@ pub type DefaultBig = BigNumRug;
 /// A WNFS name.
 /// Each file or directory has a name.
 /// Names consist of a set of name segments and are commited to name accumulators.
-/// However, these names are based on RSA accumulators to make it possible
+/// However, these names are based on RSA or class group accumulators to make it possible
 /// to prove a relationship between two names, e.g a file being contained in
 /// a sub-directory of a directory while leaking as little information as possible.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -43,9 +54,16 @@ pub struct Name<B: Big = DefaultBig> {
     segments: Vec<NameSegment<B>>,
 }
 
-/// Represents a setup needed for RSA accumulator operation.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+/// Represents a setup needed for RSA or class group accumulator operation.
+///
+/// Class group setups are validated when deserialized.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct AccumulatorSetup<B: Big = DefaultBig> {
+    /// The group the accumulator is computed in.
+    /// It's omitted in the serialized form of RSA setups.
+    #[serde(default, skip_serializing_if = "AccumulatorGroup::is_rsa")]
+    group: AccumulatorGroup,
+    /// The RSA modulus, or the absolute value of the class group discriminant
     #[serde(bound = "B: Big")]
     #[serde(deserialize_with = "crate::uint256_serde_be::deserialize::<B, _>")]
     #[serde(serialize_with = "crate::uint256_serde_be::serialize::<B, _>")]
@@ -56,10 +74,23 @@ pub struct AccumulatorSetup<B: Big = DefaultBig> {
     pub generator: B::Num,
 }
 
-/// A WNFS name represented as the RSA accumulator of all of its name segments.
+/// The serialized form of an [`AccumulatorSetup`] before validation.
+#[derive(Deserialize)]
+struct UncheckedAccumulatorSetup<B: Big> {
+    #[serde(default)]
+    group: AccumulatorGroup,
+    #[serde(bound = "B: Big")]
+    #[serde(deserialize_with = "crate::uint256_serde_be::deserialize::<B, _>")]
+    modulus: B::Num,
+    #[serde(bound = "B: Big")]
+    #[serde(deserialize_with = "crate::uint256_serde_be::deserialize::<B, _>")]
+    generator: B::Num,
+}
+
+/// A WNFS name represented as the RSA or class group accumulator of all of its name segments.
 #[derive(Clone, Eq)]
 pub struct NameAccumulator<B: Big = DefaultBig> {
-    /// A 2048-bit number
+    /// A 2048-bit number, representing an element of the setup's group
     state: B::Num,
     /// A cache for its serialized form
     serialized_cache: OnceCell<[u8; 256]>,
@@ -216,8 +247,8 @@ impl<B: Big> NameAccumulator<B> {
 
     /// Create an accumulator from the number it's represented as.
     ///
-    /// This needs to be a 2048-bit number in the RSA group from
-    /// the accumulator setup used.
+    /// This needs to be a 2048-bit number representing an element
+    /// of the group from the accumulator setup used.
     pub fn from_state(state: B::Num) -> Self {
         Self {
             state,
@@ -227,6 +258,11 @@ impl<B: Big> NameAccumulator<B> {
 
     /// Add a set of elements to the accumulator and return a batch
     /// elements proof that verifies the change of state of the accumulator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the accumulator isn't an element of the setup's group.
+    /// Accumulators from untrusted sources should be checked with [`NameAccumulator::validate`].
     pub fn add<'a>(
         &mut self,
         segments: impl IntoIterator<Item = &'a NameSegment<B>>,
@@ -244,7 +280,9 @@ impl<B: Big> NameAccumulator<B> {
         self.serialized_cache = OnceCell::new();
         let witness = self.state.clone();
 
-        self.state = B::modpow_product(&self.state, segments.iter(), &setup.modulus);
+        self.state = setup
+            .pow_product(&self.state, segments.iter())
+            .expect("Name accumulator isn't an element of the setup's group");
 
         let data = poke_fiat_shamir_l_hash_data::<B>(&setup.modulus, &witness, &self.state);
         let (l, l_hash_inc) = blake3_prime_digest::<B>(L_HASH_DSI, data, 16);
@@ -254,7 +292,10 @@ impl<B: Big> NameAccumulator<B> {
         let big_q = if B::Num::is_zero(&q) {
             B::Num::one()
         } else {
-            B::modpow(&witness, &q, &setup.modulus)
+            setup
+                .group
+                .pow::<B>(&witness, &q, &setup.modulus)
+                .expect("Name accumulator isn't an element of the setup's group")
         };
 
         ElementsProof {
@@ -264,6 +305,18 @@ impl<B: Big> NameAccumulator<B> {
         }
     }
 
+    /// Checks that this accumulator represents an element of the setup's group.
+    ///
+    /// This always succeeds for RSA setups. In class groups only reduced forms
+    /// in their canonical representation are elements.
+    pub fn validate(&self, setup: &AccumulatorSetup<B>) -> Result<()> {
+        if !setup.group.is_element::<B>(&self.state, &setup.modulus) {
+            Err(VerificationError::InvalidGroupElement)?;
+        }
+
+        Ok(())
+    }
+
     /// Deserialize a name accumulator from bytes.
     ///
     /// The byte array needs to be 256 bytes (2048 bits).
@@ -319,7 +372,11 @@ impl<B: Big> AccumulatorSetup<B> {
         let modulus = B::from_bytes_be(modulus_big_endian);
         // The generator is just some random quadratic residue.
         let generator = B::squaremod(&B::rand_below(&modulus, rng), &modulus);
-        Self { modulus, generator }
+        Self {
+            group: AccumulatorGroup::Rsa,
+            modulus,
+            generator,
+        }
     }
 
     /// Does a trusted setup in-memory and throws away the prime factors.
@@ -333,7 +390,11 @@ impl<B: Big> AccumulatorSetup<B> {
         let modulus = B::rand_rsa_modulus(rng);
         // The generator is just some random quadratic residue.
         let generator = B::squaremod(&B::rand_below(&modulus, rng), &modulus);
-        Self { modulus, generator }
+        Self {
+            group: AccumulatorGroup::Rsa,
+            modulus,
+            generator,
+        }
     }
 
     /// Faster than `trusted`, but depends on the 2048-bit [rsa factoring challenge]
@@ -347,7 +408,120 @@ impl<B: Big> AccumulatorSetup<B> {
             "25195908475657893494027183240048398571429282126204032027777137836043662020707595556264018525880784406918290641249515082189298559149176184502808489120072844992687392807287776735971418347270261896375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172654632282216869987549182422433637259085141865462043576798423387184774447920739934236584823824281198163815010674810451660377306056201619676256133844143603833904414952634432190114657544454178424020924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951378636564391212010397122822120720357",
         ).ok().unwrap();
         let generator = B::squaremod(&B::rand_below(&modulus, rng), &modulus);
-        Self { modulus, generator }
+        Self {
+            group: AccumulatorGroup::Rsa,
+            modulus,
+            generator,
+        }
+    }
+
+    /// Finishes a class group setup given the absolute value `d` of a negative
+    /// prime discriminant `-d`, encoded in big-endian.
+    ///
+    /// `d` needs to be a prime with `d = 7 mod 8` and between 1024 and 2045 bits,
+    /// otherwise this returns an error.
+    ///
+    /// Class groups don't require a trusted setup, the discriminant can be public.
+    pub fn with_discriminant(
+        discriminant_big_endian: &[u8; 256],
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self> {
+        let discriminant = B::from_bytes_be(discriminant_big_endian);
+        Self::validate_discriminant(&discriminant)?;
+        Ok(Self::class_group(discriminant, rng))
+    }
+
+    /// Does a class group setup with a 2040-bit discriminant derived from given public seed.
+    /// This requires searching for a 2040-bit prime, so it's fairly slow.
+    ///
+    /// Unlike with `trusted`, no toxic waste is generated during this operation.
+    /// Anyone can check that the discriminant was derived from the seed.
+    pub fn from_class_group_seed(seed: impl AsRef<[u8]>, rng: &mut impl CryptoRngCore) -> Self {
+        let (discriminant, _) =
+            blake3_discriminant_digest::<B>(DISCRIMINANT_DSI, seed, DISCRIMINANT_BYTE_LEN);
+        Self::class_group(discriminant, rng)
+    }
+
+    /// Faster than `from_class_group_seed`, as it uses a precomputed 2040-bit
+    /// discriminant derived from the seed `"rs-wnfs"`.
+    ///
+    /// This is great for tests, as it doesn't require lots of primality tests.
+    pub fn from_class_group_2040(rng: &mut impl CryptoRngCore) -> Self {
+        let discriminant = B::Num::from_str(CLASS_GROUP_2040_DISCRIMINANT)
+            .ok()
+            .unwrap();
+        Self::class_group(discriminant, rng)
+    }
+
+    fn class_group(discriminant: B::Num, rng: &mut impl CryptoRngCore) -> Self {
+        // The packed form (2, 1, (1 + d) / 8), a valid form since -d = 1 mod 8
+        let mut form_bytes = [0u8; 129];
+        form_bytes[0] = 2;
+        form_bytes[128] = 3;
+        let form = B::from_bytes_be(&form_bytes);
+        // The generator is just some random power of that form.
+        let exponent = B::rand_prime_256bit(rng);
+        let generator =
+            B::class_group_pow_product(&form, Some(&exponent).into_iter(), &discriminant)
+                .expect("Form (2, 1) is valid for discriminants -d with d = 7 mod 8");
+        Self {
+            group: AccumulatorGroup::ClassGroup,
+            modulus: discriminant,
+            generator,
+        }
+    }
+
+    /// Returns the group this setup's accumulators are computed in.
+    pub fn get_group(&self) -> AccumulatorGroup {
+        self.group
+    }
+
+    /// Checks that a class group setup has a valid discriminant and generator.
+    fn validate(&self) -> Result<(), SetupError> {
+        if self.group.is_rsa() {
+            return Ok(());
+        }
+
+        Self::validate_discriminant(&self.modulus)?;
+        if self.generator.is_one() || !self.group.is_element::<B>(&self.generator, &self.modulus) {
+            return Err(SetupError::InvalidGenerator);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `d` is a prime with `d = 7 mod 8` and between 1024 and 2045 bits.
+    ///
+    /// The primality test is skipped for the precomputed discriminant.
+    fn validate_discriminant(discriminant: &B::Num) -> Result<(), SetupError> {
+        let mut lower_bound = [0u8; 128];
+        lower_bound[0] = 0x80;
+        let mut upper_bound = [0u8; 256];
+        upper_bound[0] = 0x40;
+
+        let in_range = *discriminant >= B::from_bytes_be(&lower_bound)
+            && *discriminant < B::from_bytes_be(&upper_bound);
+        if !in_range || discriminant.clone() % &B::from_bytes_be(&[8]) != B::from_bytes_be(&[7]) {
+            return Err(SetupError::InvalidDiscriminant);
+        }
+
+        let precomputed = B::Num::from_str(CLASS_GROUP_2040_DISCRIMINANT).ok();
+        if precomputed.as_ref() != Some(discriminant) && !B::is_probably_prime(discriminant) {
+            return Err(SetupError::InvalidDiscriminant);
+        }
+
+        Ok(())
+    }
+
+    fn pow_product<'a>(
+        &self,
+        base: &B::Num,
+        exponents: impl Iterator<Item = &'a B::Num>,
+    ) -> Result<B::Num, VerificationError>
+    where
+        B: 'a,
+    {
+        self.group.pow_product::<B>(base, exponents, &self.modulus)
     }
 }
 
@@ -373,9 +547,16 @@ impl<B: Big> BatchedProofPart<B> {
 
     /// Add the batchable portion of a proof of elements
     /// for a certain name accumulator to this batch proof.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proof isn't made of elements of the setup's group,
+    /// which can't happen for proofs returned by [`NameAccumulator::add`].
     pub fn add(&mut self, proof: &ElementsProof<B>, setup: &AccumulatorSetup<B>) {
-        self.big_q_product *= &proof.big_q;
-        self.big_q_product %= &setup.modulus;
+        self.big_q_product = setup
+            .group
+            .op::<B>(&self.big_q_product, &proof.big_q, &setup.modulus)
+            .expect("Proof isn't made of elements of the setup's group");
     }
 }
 
@@ -402,8 +583,14 @@ impl<'a, B: Big> BatchedProofVerification<'a, B> {
         commitment: &NameAccumulator<B>,
         proof_part: &UnbatchableProofPart<B>,
     ) -> Result<()> {
-        let hasher =
-            poke_fiat_shamir_l_hash_data::<B>(&self.setup.modulus, &base.state, &commitment.state);
+        let AccumulatorSetup { group, modulus, .. } = self.setup;
+        if !group.is_element::<B>(&base.state, modulus)
+            || !group.is_element::<B>(&commitment.state, modulus)
+        {
+            Err(VerificationError::InvalidGroupElement)?;
+        }
+
+        let hasher = poke_fiat_shamir_l_hash_data::<B>(modulus, &base.state, &commitment.state);
         let l = blake3_prime_digest_fast::<B>(L_HASH_DSI, hasher, 16, proof_part.l_hash_inc)
             .ok_or(VerificationError::LHashNonPrime)?;
 
@@ -411,14 +598,15 @@ impl<'a, B: Big> BatchedProofVerification<'a, B> {
             Err(VerificationError::ResidueOutsideRange)?;
         }
 
-        let proof_kcr_base = (commitment.state.clone()
-            * B::modpow(
-                &B::mod_inv(&base.state, &self.setup.modulus)
-                    .ok_or(VerificationError::NoInverse)?,
+        let proof_kcr_base = group.op::<B>(
+            &commitment.state,
+            &group.pow::<B>(
+                &group.inverse::<B>(&base.state, modulus)?,
                 &proof_part.r,
-                &self.setup.modulus,
-            ))
-            % &self.setup.modulus;
+                modulus,
+            )?,
+            modulus,
+        )?;
 
         self.bases_and_exponents.push((proof_kcr_base, l));
 
@@ -430,10 +618,17 @@ impl<'a, B: Big> BatchedProofVerification<'a, B> {
     ///
     /// Will return an error if verification fails.
     pub fn verify(&self, batched_proof: &BatchedProofPart<B>) -> Result<()> {
+        let AccumulatorSetup { group, modulus, .. } = self.setup;
+        if !group.is_element::<B>(&batched_proof.big_q_product, modulus) {
+            return Err(VerificationError::InvalidGroupElement.into());
+        }
+
         let exponents = self.bases_and_exponents.iter().map(|(_, l)| l);
-        let tmp = B::modpow_product(&batched_proof.big_q_product, exponents, &self.setup.modulus);
+        let tmp = self
+            .setup
+            .pow_product(&batched_proof.big_q_product, exponents)?;
 
-        if tmp != multi_exp::<B>(&self.bases_and_exponents, &self.setup.modulus) {
+        if tmp != multi_exp::<B>(&self.bases_and_exponents, *group, modulus)? {
             return Err(VerificationError::ValidationFailed.into());
         }
 
@@ -488,6 +683,26 @@ impl<'de, B: Big> Deserialize<'de> for NameSegment<B> {
     }
 }
 
+impl<'de, B: Big> Deserialize<'de> for AccumulatorSetup<B> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let UncheckedAccumulatorSetup {
+            group,
+            modulus,
+            generator,
+        } = UncheckedAccumulatorSetup::<B>::deserialize(deserializer)?;
+        let setup = Self {
+            group,
+            modulus,
+            generator,
+        };
+        setup.validate().map_err(D::Error::custom)?;
+        Ok(setup)
+    }
+}
+
 impl<'de, B: Big> Deserialize<'de> for NameAccumulator<B> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -584,6 +799,7 @@ impl<B: Big> std::fmt::Debug for NameSegment<B> {
 impl<B: Big> std::fmt::Debug for AccumulatorSetup<B> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AccumulatorSetup")
+            .field("group", &self.group)
             .field("modulus", &self.modulus.to_string())
             .field("generator", &self.generator.to_string())
             .finish()
@@ -623,10 +839,14 @@ impl<B: Big> std::fmt::Debug for BatchedProofPart<B> {
 
 #[cfg(test)]
 mod tests {
-    use super::DefaultBig;
+    use super::{
+        DefaultBig, CLASS_GROUP_2040_DISCRIMINANT, DISCRIMINANT_BYTE_LEN, DISCRIMINANT_DSI,
+    };
     use crate::{
-        AccumulatorSetup, BatchedProofPart, BatchedProofVerification, Big, BigNumDig, Name,
-        NameAccumulator, NameSegment,
+        error::{SetupError, VerificationError},
+        fns::blake3_discriminant_digest_fast,
+        AccumulatorGroup, AccumulatorSetup, BatchedProofPart, BatchedProofVerification, Big,
+        BigNumDig, Name, NameAccumulator, NameSegment,
     };
     use anyhow::Result;
     use libipld::{
@@ -638,7 +858,7 @@ mod tests {
     use proptest::{prop_assert, prop_assert_eq};
     use rand::{thread_rng, SeedableRng};
     use rand_chacha::ChaCha12Rng;
-    use std::io::Cursor;
+    use std::{io::Cursor, str::FromStr};
     use test_strategy::proptest;
     use wnfs_common::{decode, encode};
 
@@ -806,6 +1026,157 @@ mod tests {
         }
     }
 
+    #[test]
+    fn class_group_batched_proof_example() -> Result<()> {
+        let rng = &mut thread_rng();
+        let setup = &AccumulatorSetup::<DefaultBig>::from_class_group_2040(rng);
+        let name_base = NameAccumulator::with_segments(&[NameSegment::new(rng)], setup);
+
+        let mut acc_one = name_base.clone();
+        let mut acc_two = name_base.clone();
+        let proof_one = acc_one.add(&[NameSegment::new(rng), NameSegment::new(rng)], setup);
+        let proof_two = acc_two.add(&[NameSegment::new(rng)], setup);
+
+        let mut batched_proof = BatchedProofPart::new();
+        batched_proof.add(&proof_one, setup);
+        batched_proof.add(&proof_two, setup);
+
+        let mut verification = BatchedProofVerification::new(setup);
+        verification.add(&name_base, &acc_one, &proof_one.part)?;
+        verification.add(&name_base, &acc_two, &proof_two.part)?;
+        verification.verify(&batched_proof)?;
+
+        let mut incomplete_proof = BatchedProofPart::new();
+        incomplete_proof.add(&proof_one, setup);
+        assert!(verification.verify(&incomplete_proof).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn class_group_rejects_invalid_elements() {
+        let rng = &mut thread_rng();
+        let setup = &AccumulatorSetup::<DefaultBig>::from_class_group_2040(rng);
+        let name_base = NameAccumulator::empty(setup);
+        let mut acc = name_base.clone();
+        let proof = acc.add(&[NameSegment::new(rng)], setup);
+
+        // An unreduced form (2^1024, -2^1024, _)
+        let mut invalid_bytes = [0u8; 256];
+        invalid_bytes[127] = 1;
+        let invalid = NameAccumulator::parse_slice(invalid_bytes);
+
+        assert!(acc.validate(setup).is_ok());
+        assert!(invalid.validate(setup).is_err());
+
+        let mut verification = BatchedProofVerification::new(setup);
+        let result = verification.add(&name_base, &invalid, &proof.part);
+        assert_eq!(
+            result.unwrap_err().downcast::<VerificationError>().unwrap(),
+            VerificationError::InvalidGroupElement
+        );
+    }
+
+    #[test]
+    fn class_group_setup_rejects_invalid_discriminants() {
+        let rng = &mut thread_rng();
+        let discriminant =
+            <DefaultBig as Big>::Num::from_str(CLASS_GROUP_2040_DISCRIMINANT).unwrap();
+        let valid = DefaultBig::to_bytes_be::<256>(&discriminant);
+        assert!(AccumulatorSetup::<DefaultBig>::with_discriminant(&valid, rng).is_ok());
+
+        // d = 5 mod 8
+        let mut wrong_residue = valid;
+        wrong_residue[255] ^= 0b010;
+        // Still d = 7 mod 8, but composite
+        let mut composite = valid;
+        composite[255] ^= 0b1000;
+        // 2^2046 + 7, too large for elements to fit 256 bytes
+        let mut too_large = [0u8; 256];
+        too_large[0] = 0x40;
+        too_large[255] = 7;
+        // 1_000_000_007, too small to be secure
+        let mut too_small = [0u8; 256];
+        too_small[252..].copy_from_slice(&1_000_000_007u32.to_be_bytes());
+
+        for invalid in [wrong_residue, composite, too_large, too_small] {
+            let error = AccumulatorSetup::<DefaultBig>::with_discriminant(&invalid, rng)
+                .unwrap_err()
+                .downcast::<SetupError>()
+                .unwrap();
+            assert_eq!(error, SetupError::InvalidDiscriminant);
+        }
+    }
+
+    #[test]
+    fn class_group_setup_is_validated_when_deserialized() {
+        let rng = &mut thread_rng();
+        let setup = AccumulatorSetup::<DefaultBig>::from_class_group_2040(rng);
+        let Ipld::Map(map) = libipld::serde::to_ipld(&setup).unwrap() else {
+            panic!("Setup isn't serialized as a map");
+        };
+
+        let Some(Ipld::Bytes(modulus)) = map.get("modulus") else {
+            panic!("Setup modulus isn't serialized as bytes");
+        };
+        let mut wrong_residue = modulus.clone();
+        wrong_residue[255] ^= 0b010;
+        let mut invalid_modulus = map.clone();
+        invalid_modulus.insert("modulus".into(), Ipld::Bytes(wrong_residue));
+        let result = libipld::serde::from_ipld::<AccumulatorSetup>(Ipld::Map(invalid_modulus));
+        assert!(result.is_err());
+
+        // An unreduced form (2^1024, -2^1024, _)
+        let mut unreduced = vec![0u8; 256];
+        unreduced[127] = 1;
+        let mut invalid_generator = map.clone();
+        invalid_generator.insert("generator".into(), Ipld::Bytes(unreduced));
+        let result = libipld::serde::from_ipld::<AccumulatorSetup>(Ipld::Map(invalid_generator));
+        assert!(result.is_err());
+
+        let result = libipld::serde::from_ipld::<AccumulatorSetup>(Ipld::Map(map));
+        assert_eq!(result.unwrap(), setup);
+    }
+
+    #[test]
+    fn setup_serialization_records_group() {
+        let rng = &mut thread_rng();
+        let rsa_setup = AccumulatorSetup::<DefaultBig>::from_rsa_2048(rng);
+        let class_group_setup = AccumulatorSetup::<DefaultBig>::from_class_group_2040(rng);
+
+        let rsa_ipld = libipld::serde::to_ipld(&rsa_setup).unwrap();
+        let class_group_ipld = libipld::serde::to_ipld(&class_group_setup).unwrap();
+
+        assert!(rsa_ipld.get("group").is_err());
+        assert_eq!(
+            class_group_ipld.get("group").unwrap(),
+            &Ipld::String("class-group".into())
+        );
+
+        let rsa_back: AccumulatorSetup = libipld::serde::from_ipld(rsa_ipld).unwrap();
+        let class_group_back: AccumulatorSetup =
+            libipld::serde::from_ipld(class_group_ipld).unwrap();
+
+        assert_eq!(rsa_back, rsa_setup);
+        assert_eq!(rsa_back.get_group(), AccumulatorGroup::Rsa);
+        assert_eq!(class_group_back, class_group_setup);
+        assert_eq!(class_group_back.get_group(), AccumulatorGroup::ClassGroup);
+    }
+
+    #[test]
+    fn class_group_2040_discriminant_is_derived_from_seed() {
+        let discriminant = blake3_discriminant_digest_fast::<BigNumDig>(
+            DISCRIMINANT_DSI,
+            "rs-wnfs",
+            DISCRIMINANT_BYTE_LEN,
+            2723,
+        );
+        assert_eq!(
+            discriminant,
+            Some(BigUint::from_str(CLASS_GROUP_2040_DISCRIMINANT).unwrap())
+        );
+    }
+
     #[proptest]
     fn padded_biguint_encoding_roundtrips(num: u64) {
         let num = BigUint::from(num);
@@ -1,5 +1,9 @@
+#[cfg(any(feature = "rug", feature = "num-bigint-dig"))]
+use crate::class_group;
 #[cfg(feature = "num-bigint-dig")]
-use num_bigint_dig::{prime::probably_prime, BigUint, ModInverse, RandBigInt, RandPrime};
+use num_bigint_dig::{
+    prime::probably_prime, BigInt, BigUint, ModInverse, RandBigInt, RandPrime, Sign,
+};
 use num_traits::{One, Zero};
 use rand_core::CryptoRngCore;
 #[cfg(feature = "rug")]
@@ -99,6 +103,39 @@ pub trait Big: Eq + Clone + Hash {
 
     /// Generate a random 256-bit prime number.
     fn rand_prime_256bit(rng: &mut impl CryptoRngCore) -> Self::Num;
+
+    /// Composes two elements of the class group with discriminant `-discriminant`.
+    ///
+    /// See [`AccumulatorGroup::ClassGroup`](crate::AccumulatorGroup::ClassGroup)
+    /// for how elements are represented.
+    ///
+    /// Returns `None` if either input doesn't represent a group element.
+    fn class_group_compose(
+        x: &Self::Num,
+        y: &Self::Num,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num>;
+
+    /// Computes the power of base to the product of some numbers in the
+    /// class group with discriminant `-discriminant`.
+    ///
+    /// Returns `None` if the base doesn't represent a group element.
+    fn class_group_pow_product<'a>(
+        base: &Self::Num,
+        exponents: impl Iterator<Item = &'a Self::Num>,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num>
+    where
+        Self::Num: 'a;
+
+    /// Computes the inverse of an element of the class group with discriminant `-discriminant`.
+    ///
+    /// Returns `None` if the input doesn't represent a group element.
+    fn class_group_inverse(x: &Self::Num, discriminant: &Self::Num) -> Option<Self::Num>;
+
+    /// Returns whether given number represents an element of the
+    /// class group with discriminant `-discriminant`.
+    fn class_group_is_element(x: &Self::Num, discriminant: &Self::Num) -> bool;
 }
 
 #[cfg(feature = "num-bigint-dig")]
@@ -183,6 +220,44 @@ impl Big for BigNumDig {
     fn rand_prime_256bit(rng: &mut impl CryptoRngCore) -> Self::Num {
         rng.gen_prime(256)
     }
+
+    fn class_group_compose(
+        x: &Self::Num,
+        y: &Self::Num,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num> {
+        let (x, y, discriminant) = (signed(x), signed(y), signed(discriminant));
+        class_group::compose(&x, &y, &discriminant).map(unsigned)
+    }
+
+    fn class_group_pow_product<'a>(
+        base: &Self::Num,
+        exponents: impl Iterator<Item = &'a Self::Num>,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num> {
+        let exponents = exponents.map(signed).collect::<Vec<_>>();
+        class_group::pow_product(&signed(base), exponents.iter(), &signed(discriminant))
+            .map(unsigned)
+    }
+
+    fn class_group_inverse(x: &Self::Num, discriminant: &Self::Num) -> Option<Self::Num> {
+        class_group::inverse(&signed(x), &signed(discriminant)).map(unsigned)
+    }
+
+    fn class_group_is_element(x: &Self::Num, discriminant: &Self::Num) -> bool {
+        class_group::is_element(&signed(x), &signed(discriminant))
+    }
+}
+
+#[cfg(feature = "num-bigint-dig")]
+fn signed(n: &BigUint) -> BigInt {
+    BigInt::from_biguint(Sign::Plus, n.clone())
+}
+
+/// Class group elements are always represented as positive numbers
+#[cfg(feature = "num-bigint-dig")]
+fn unsigned(n: BigInt) -> BigUint {
+    n.to_biguint().unwrap_or_default()
 }
 
 #[cfg(feature = "rug")]
@@ -270,6 +345,30 @@ impl Big for BigNumRug {
         debug_assert!(prime.is_positive());
         prime
     }
+
+    fn class_group_compose(
+        x: &Self::Num,
+        y: &Self::Num,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num> {
+        class_group::compose(x, y, discriminant)
+    }
+
+    fn class_group_pow_product<'a>(
+        base: &Self::Num,
+        exponents: impl Iterator<Item = &'a Self::Num>,
+        discriminant: &Self::Num,
+    ) -> Option<Self::Num> {
+        class_group::pow_product(base, exponents, discriminant)
+    }
+
+    fn class_group_inverse(x: &Self::Num, discriminant: &Self::Num) -> Option<Self::Num> {
+        class_group::inverse(x, discriminant)
+    }
+
+    fn class_group_is_element(x: &Self::Num, discriminant: &Self::Num) -> bool {
+        class_group::is_element(x, discriminant)
+    }
 }
 
 #[cfg(feature = "rug")]
@@ -1,13 +1,14 @@
 use crate::{
-    builder::encode_unixfs_pb,
-    protobufs,
+    builder::{encode_unixfs_pb, DagOptions},
+    protobufs::{self, UnixTime},
+    trickle_tree::stream_trickle_tree,
     types::Block,
     unixfs::{DataType, Node, UnixFsFile},
 };
 use anyhow::Result;
 use async_stream::try_stream;
 use bytes::Bytes;
-use futures::{Stream, StreamExt};
+use futures::{future::Either, Stream, StreamExt};
 use libipld::Cid;
 use std::collections::VecDeque;
 use wnfs_common::{utils::CondSend, BlockStore};
@@ -21,6 +22,9 @@ pub enum TreeBuilder {
     /// TreeBuilder that builds a "balanced tree" with a max degree size of
     /// degree
     Balanced { degree: usize },
+    /// TreeBuilder that builds a "trickle tree" with a max degree size of
+    /// degree, see [`crate::trickle_tree`]
+    Trickle { degree: usize },
 }
 
 impl TreeBuilder {
@@ -33,19 +37,34 @@ impl TreeBuilder {
         TreeBuilder::Balanced { degree }
     }
 
+    pub fn trickle_tree() -> Self {
+        Self::trickle_tree_with_degree(DEFAULT_DEGREE)
+    }
+
+    pub fn trickle_tree_with_degree(degree: usize) -> Self {
+        assert!(degree > 1);
+        TreeBuilder::Trickle { degree }
+    }
+
     pub fn stream_tree<'a>(
         &self,
         chunks: impl Stream<Item = std::io::Result<Bytes>> + CondSend + 'a,
+        options: DagOptions,
         store: &'a impl BlockStore,
     ) -> impl Stream<Item = Result<(Cid, Block)>> + 'a {
         match self {
-            TreeBuilder::Balanced { degree } => stream_balanced_tree(chunks, *degree, store),
+            TreeBuilder::Balanced { degree } => {
+                Either::Left(stream_balanced_tree(chunks, *degree, options, store))
+            }
+            TreeBuilder::Trickle { degree } => {
+                Either::Right(stream_trickle_tree(chunks, *degree, options, store))
+            }
         }
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct LinkInfo {
+pub(crate) struct LinkInfo {
     raw_data_len: u64,
     encoded_len: u64,
 }
@@ -53,6 +72,7 @@ struct LinkInfo {
 fn stream_balanced_tree<'a>(
     in_stream: impl Stream<Item = std::io::Result<Bytes>> + CondSend + 'a,
     degree: usize,
+    options: DagOptions,
     store: &'a impl BlockStore,
 ) -> impl Stream<Item = Result<(Cid, Block)>> + 'a {
     try_stream! {
@@ -81,7 +101,7 @@ fn stream_balanced_tree<'a>(
         let mut tree: VecDeque<Vec<(Cid, LinkInfo)>> = VecDeque::new();
         tree.push_back(Vec::with_capacity(degree));
 
-        let in_stream = in_stream.map(|chunk| TreeNode::Leaf(chunk?).encode());
+        let in_stream = in_stream.map(|chunk| TreeNode::Leaf(chunk?).encode(&options));
 
         tokio::pin!(in_stream);
 
@@ -106,8 +126,8 @@ fn stream_balanced_tree<'a>(
 
                     // create node, keeping the cid
                     let links = std::mem::replace(&mut tree[i], Vec::with_capacity(degree));
-                    let (block, link_info) = TreeNode::Stem(links).encode()?;
-                    let cid = block.store(store).await?;
+                    let (block, link_info) = TreeNode::Stem(links).encode(&options)?;
+                    let cid = block.store_with(&options, store).await?;
                     yield (cid, block);
 
                     // add link_info to parent node
@@ -120,15 +140,16 @@ fn stream_balanced_tree<'a>(
 
             // now that we know the tree is in a "healthy" state to
             // recieve more links, add the link to the tree
-            let cid = block.store(store).await?;
+            let cid = block.store_with(&options, store).await?;
             tree[0].push((cid, link_info));
             yield (cid, block);
             // at this point, the leaf node may have `degree` number of
             // links, but no other stem node will
         }
 
-        // our stream had 1 chunk that we have already yielded
-        if tree.len() == 1 && tree[0].len() == 1 {
+        // our stream had 1 chunk that we have already yielded,
+        // unless the root needs to carry metadata which raw leaves can't
+        if tree.len() == 1 && tree[0].len() == 1 && !options.has_metadata() {
             return
         }
 
@@ -136,8 +157,13 @@ fn stream_balanced_tree<'a>(
         // since all the stem nodes are able to recieve links
         // we don't have to worry about "overflow"
         while let Some(links) = tree.pop_front() {
-            let (block, link_info) = TreeNode::Stem(links).encode()?;
-            let cid = block.store(store).await?;
+            let node = if tree.is_empty() {
+                TreeNode::Root(links)
+            } else {
+                TreeNode::Stem(links)
+            };
+            let (block, link_info) = node.encode(&options)?;
+            let cid = block.store_with(&options, store).await?;
             yield (cid, block);
 
             if let Some(front) = tree.front_mut() {
@@ -149,16 +175,19 @@ fn stream_balanced_tree<'a>(
     }
 }
 
-fn create_unixfs_node_from_links(links: Vec<(Cid, LinkInfo)>) -> Result<UnixFsFile> {
+fn create_unixfs_node_from_links(
+    links: Vec<(Cid, LinkInfo)>,
+    mode: Option<u32>,
+    mtime: Option<UnixTime>,
+) -> Result<UnixFsFile> {
     let blocksizes: Vec<u64> = links.iter().map(|l| l.1.raw_data_len).collect();
     let filesize: u64 = blocksizes.iter().sum();
     let links = links
         .into_iter()
         .map(|(cid, l)| protobufs::PbLink {
             hash: Some(cid.to_bytes()),
-            // ALL "stem" nodes have `name: None`.
-            // In kubo, nodes that have links to `leaf` nodes have `name: Some("".to_string())`
-            name: None,
+            // kubo (go-merkledag) always encodes the name, even if it's empty
+            name: Some(String::new()),
             // tsize has no strict definition
             // Iroh's definiton of `tsize` is "the cumulative size of the encoded tree
             // pointed to by this link", so not just the size of the raw content, but including
@@ -177,6 +206,8 @@ fn create_unixfs_node_from_links(links: Vec<(Cid, LinkInfo)>) -> Result<UnixFsFi
         filesize: Some(filesize),
         // sizes of the raw data pointed to by each link in this node
         blocksizes,
+        mode,
+        mtime,
         ..Default::default()
     };
 
@@ -187,18 +218,35 @@ fn create_unixfs_node_from_links(links: Vec<(Cid, LinkInfo)>) -> Result<UnixFsFi
     Ok(UnixFsFile::Node(Node { inner, outer }))
 }
 
+fn create_unixfs_leaf_node(bytes: Bytes) -> Result<UnixFsFile> {
+    let inner = protobufs::Data {
+        r#type: DataType::File as i32,
+        filesize: Some(bytes.len() as u64),
+        // go-unixfs omits the data field for empty leaves
+        data: (!bytes.is_empty()).then_some(bytes),
+        ..Default::default()
+    };
+
+    let outer = encode_unixfs_pb(&inner, Vec::new())?;
+
+    Ok(UnixFsFile::Node(Node { inner, outer }))
+}
+
 // Leaf and Stem nodes are the two types of nodes that can exist in the tree
-// Leaf nodes encode to `UnixfsNode::Raw`
+// Leaf nodes encode to `UnixfsNode::Raw`, or to a `UnixfsNode::File` without
+// links if raw leaves are disabled
 // Stem nodes encode to `UnixfsNode::File`
-enum TreeNode {
+// Root nodes are stem nodes that also carry the file's mode and mtime
+pub(crate) enum TreeNode {
     Leaf(Bytes),
     Stem(Vec<(Cid, LinkInfo)>),
+    Root(Vec<(Cid, LinkInfo)>),
 }
 
 impl TreeNode {
-    fn encode(self) -> Result<(Block, LinkInfo)> {
+    pub(crate) fn encode(self, options: &DagOptions) -> Result<(Block, LinkInfo)> {
         match self {
-            TreeNode::Leaf(bytes) => {
+            TreeNode::Leaf(bytes) if options.raw_leaves => {
                 let len = bytes.len();
                 let node = UnixFsFile::Raw(bytes);
                 let block = node.encode()?;
@@ -210,26 +258,43 @@ impl TreeNode {
                 };
                 Ok((block, link_info))
             }
-            TreeNode::Stem(links) => {
-                let mut encoded_len: u64 = links.iter().map(|(_, l)| l.encoded_len).sum();
-                let node = create_unixfs_node_from_links(links)?;
+            TreeNode::Leaf(bytes) => {
+                let len = bytes.len();
+                let node = create_unixfs_leaf_node(bytes)?;
                 let block = node.encode()?;
-                encoded_len += block.data().len() as u64;
-                let raw_data_len = node
-                    .filesize()
-                    .expect("UnixfsNode::File will have a filesize");
-                Ok((
-                    block,
-                    LinkInfo {
-                        raw_data_len,
-                        encoded_len,
-                    },
-                ))
+                let link_info = LinkInfo {
+                    raw_data_len: len as u64,
+                    encoded_len: block.data().len() as u64,
+                };
+                Ok((block, link_info))
             }
+            TreeNode::Stem(links) => encode_stem(links, None, None),
+            TreeNode::Root(links) => encode_stem(links, options.mode, options.mtime),
         }
     }
 }
 
+fn encode_stem(
+    links: Vec<(Cid, LinkInfo)>,
+    mode: Option<u32>,
+    mtime: Option<UnixTime>,
+) -> Result<(Block, LinkInfo)> {
+    let mut encoded_len: u64 = links.iter().map(|(_, l)| l.encoded_len).sum();
+    let node = create_unixfs_node_from_links(links, mode, mtime)?;
+    let block = node.encode()?;
+    encoded_len += block.data().len() as u64;
+    let raw_data_len = node
+        .filesize()
+        .expect("UnixfsNode::File will have a filesize");
+    Ok((
+        block,
+        LinkInfo {
+            raw_data_len,
+            encoded_len,
+        },
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,7 +318,7 @@ mod tests {
         if num_chunks / degree == 0 {
             let chunk = chunks.next().await.unwrap().unwrap();
             let leaf = TreeNode::Leaf(chunk);
-            let (block, _) = leaf.encode().unwrap();
+            let (block, _) = leaf.encode(&DagOptions::default()).unwrap();
             let cid = block.store(store).await.unwrap();
             tree[0].push((cid, block));
             return tree;
@@ -262,7 +327,7 @@ mod tests {
         while let Some(chunk) = chunks.next().await {
             let chunk = chunk.unwrap();
             let leaf = TreeNode::Leaf(chunk);
-            let (block, link_info) = leaf.encode().unwrap();
+            let (block, link_info) = leaf.encode(&DagOptions::default()).unwrap();
             let cid = block.store(store).await.unwrap();
             links[0].push((cid, link_info));
             tree[0].push((cid, block));
@@ -275,7 +340,7 @@ mod tests {
             let mut links_layer = Vec::with_capacity(count);
             for links in prev_layer.chunks(degree) {
                 let stem = TreeNode::Stem(links.to_vec());
-                let (block, link_info) = stem.encode().unwrap();
+                let (block, link_info) = stem.encode(&DagOptions::default()).unwrap();
                 let cid = block.store(store).await.unwrap();
                 links_layer.push((cid, link_info));
                 tree_layer.push((cid, block));
@@ -340,7 +405,7 @@ mod tests {
 
     async fn make_leaf(data: usize, store: &impl BlockStore) -> (Cid, Block, LinkInfo) {
         let (block, link_info) = TreeNode::Leaf(BytesMut::from(&data.to_be_bytes()[..]).freeze())
-            .encode()
+            .encode(&DagOptions::default())
             .unwrap();
         let cid = block.store(store).await.unwrap();
         (cid, block, link_info)
@@ -350,7 +415,9 @@ mod tests {
         links: Vec<(Cid, LinkInfo)>,
         store: &impl BlockStore,
     ) -> (Cid, Block, LinkInfo) {
-        let (block, link_info) = TreeNode::Stem(links).encode().unwrap();
+        let (block, link_info) = TreeNode::Stem(links)
+            .encode(&DagOptions::default())
+            .unwrap();
         let cid = block.store(store).await.unwrap();
         (cid, block, link_info)
     }
@@ -486,7 +553,7 @@ mod tests {
         let store = &MemoryBlockStore::new();
         let num_chunks = 1;
         let expect = build_expect(num_chunks, 3).await;
-        let got = stream_balanced_tree(test_chunk_stream(1), 3, store);
+        let got = stream_balanced_tree(test_chunk_stream(1), 3, DagOptions::default(), store);
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -497,7 +564,12 @@ mod tests {
         let num_chunks = 3;
         let degrees = 3;
         let expect = build_expect(num_chunks, degrees).await;
-        let got = stream_balanced_tree(test_chunk_stream(num_chunks), degrees, store);
+        let got = stream_balanced_tree(
+            test_chunk_stream(num_chunks),
+            degrees,
+            DagOptions::default(),
+            store,
+        );
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -508,7 +580,12 @@ mod tests {
         let degrees = 3;
         let num_chunks = 9;
         let expect = build_expect(num_chunks, degrees).await;
-        let got = stream_balanced_tree(test_chunk_stream(num_chunks), degrees, store);
+        let got = stream_balanced_tree(
+            test_chunk_stream(num_chunks),
+            degrees,
+            DagOptions::default(),
+            store,
+        );
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -519,7 +596,12 @@ mod tests {
         let degrees = 3;
         let num_chunks = 10;
         let expect = build_expect(num_chunks, degrees).await;
-        let got = stream_balanced_tree(test_chunk_stream(num_chunks), degrees, store);
+        let got = stream_balanced_tree(
+            test_chunk_stream(num_chunks),
+            degrees,
+            DagOptions::default(),
+            store,
+        );
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -530,7 +612,12 @@ mod tests {
         let num_chunks = 125;
         let degrees = 5;
         let expect = build_expect(num_chunks, degrees).await;
-        let got = stream_balanced_tree(test_chunk_stream(num_chunks), degrees, store);
+        let got = stream_balanced_tree(
+            test_chunk_stream(num_chunks),
+            degrees,
+            DagOptions::default(),
+            store,
+        );
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -541,7 +628,12 @@ mod tests {
         let num_chunks = 780;
         let degrees = 11;
         let expect = build_expect(num_chunks, degrees).await;
-        let got = stream_balanced_tree(test_chunk_stream(num_chunks), degrees, store);
+        let got = stream_balanced_tree(
+            test_chunk_stream(num_chunks),
+            degrees,
+            DagOptions::default(),
+            store,
+        );
         tokio::pin!(got);
         ensure_equal(expect, got, num_chunks as u64 * CHUNK_SIZE).await;
     }
@@ -1,13 +1,13 @@
 use crate::{
     balanced_tree::{TreeBuilder, DEFAULT_DEGREE},
-    chunker::{self, Chunker, ChunkerConfig, DEFAULT_CHUNK_SIZE_LIMIT},
-    protobufs,
+    chunker::{self, Chunker, DEFAULT_CHUNK_SIZE_LIMIT},
+    protobufs::{self, UnixTime},
     types::{Block, BoxAsyncRead},
 };
 use anyhow::{anyhow, ensure, Result};
 use bytes::Bytes;
 use futures::{Stream, TryStreamExt};
-use libipld::Cid;
+use libipld::{cid::Version, multihash::Code, Cid};
 use prost::Message;
 use std::fmt::Debug;
 use tokio::io::AsyncRead;
@@ -18,6 +18,7 @@ pub struct File<'a> {
     content: BoxAsyncRead<'a>,
     tree_builder: TreeBuilder,
     chunker: Chunker,
+    options: DagOptions,
 }
 
 impl<'a> Debug for File<'a> {
@@ -29,6 +30,7 @@ impl<'a> Debug for File<'a> {
             )
             .field("tree_builder", &self.tree_builder)
             .field("chunker", &self.chunker)
+            .field("options", &self.options)
             .finish()
     }
 }
@@ -39,7 +41,7 @@ impl<'a> File<'a> {
         store: &'a impl BlockStore,
     ) -> Result<impl Stream<Item = Result<(Cid, Block)>> + '_> {
         let chunks = self.chunker.chunks(self.content);
-        Ok(self.tree_builder.stream_tree(chunks, store))
+        Ok(self.tree_builder.stream_tree(chunks, self.options, store))
     }
 
     pub async fn store(self, store: &impl BlockStore) -> Result<Cid> {
@@ -61,6 +63,8 @@ pub struct FileBuilder<'a> {
     reader: Option<BoxAsyncRead<'a>>,
     chunker: Chunker,
     degree: usize,
+    trickle: bool,
+    options: DagOptions,
 }
 
 impl<'a> Default for FileBuilder<'a> {
@@ -69,6 +73,8 @@ impl<'a> Default for FileBuilder<'a> {
             reader: None,
             chunker: Chunker::Fixed(chunker::Fixed::default()),
             degree: DEFAULT_DEGREE,
+            trickle: false,
+            options: DagOptions::default(),
         }
     }
 }
@@ -83,6 +89,8 @@ impl<'a> Debug for FileBuilder<'a> {
         f.debug_struct("FileBuilder")
             .field("chunker", &self.chunker)
             .field("degree", &self.degree)
+            .field("trickle", &self.trickle)
+            .field("options", &self.options)
             .field("reader", &reader)
             .finish()
     }
@@ -116,6 +124,53 @@ impl<'a> FileBuilder<'a> {
         self
     }
 
+    /// Lay out the file as a trickle DAG instead of a balanced tree.
+    ///
+    /// Trickle DAGs are optimized for reading the file sequentially, e.g. for streaming media.
+    pub fn trickle(mut self, trickle: bool) -> Self {
+        self.trickle = trickle;
+        self
+    }
+
+    /// Encode leaves as raw blocks (the default) or as dag-pb `File` nodes.
+    pub fn raw_leaves(mut self, raw_leaves: bool) -> Self {
+        self.options.raw_leaves = raw_leaves;
+        self
+    }
+
+    /// Set the CID version for dag-pb nodes. Defaults to CIDv1.
+    ///
+    /// CIDv0 requires the sha2-256 hash function.
+    pub fn cid_version(mut self, cid_version: Version) -> Self {
+        self.options.cid_version = cid_version;
+        self
+    }
+
+    /// Set the hash function used for all blocks.
+    /// By default the hash function is chosen by the blockstore.
+    pub fn hash(mut self, hash: Code) -> Self {
+        self.options.hash = Some(hash);
+        self
+    }
+
+    /// Set the UnixFS v1.5 permission bits of the file, e.g. `0o644`.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.options.mode = Some(mode);
+        self
+    }
+
+    /// Set the UnixFS v1.5 modification time of the file.
+    pub fn mtime(mut self, mtime: UnixTime) -> Self {
+        self.options.mtime = Some(mtime);
+        self
+    }
+
+    /// Set all DAG encoding options at once.
+    pub fn options(mut self, options: DagOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn content_bytes(mut self, content: impl Into<Bytes>) -> Self {
         let bytes = content.into();
         self.reader = Some(Box::pin(std::io::Cursor::new(bytes)));
@@ -130,13 +185,25 @@ impl<'a> FileBuilder<'a> {
     pub fn build(self) -> Result<File<'a>> {
         let degree = self.degree;
         let chunker = self.chunker;
-        let tree_builder = TreeBuilder::balanced_tree_with_degree(degree);
+        let options = self.options;
+        let tree_builder = if self.trickle {
+            TreeBuilder::trickle_tree_with_degree(degree)
+        } else {
+            TreeBuilder::balanced_tree_with_degree(degree)
+        };
+
+        ensure!(
+            options.cid_version == Version::V1
+                || options.hash.unwrap_or(Code::Sha2_256) == Code::Sha2_256,
+            "CIDv0 requires the sha2-256 hash function"
+        );
 
         if let Some(reader) = self.reader {
             return Ok(File {
                 content: reader,
                 chunker,
                 tree_builder,
+                options,
             });
         }
 
@@ -161,18 +228,58 @@ pub(crate) fn encode_unixfs_pb(
     })
 }
 
-/// Configuration for adding unixfs content
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Config {
-    /// Should the outer object be wrapped in a directory?
-    pub wrap: bool,
-    pub chunker: Option<ChunkerConfig>,
+/// Options for encoding the blocks of a unixfs DAG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DagOptions {
+    /// Encode leaves as raw blocks instead of dag-pb `File` nodes.
+    pub raw_leaves: bool,
+    /// The CID version of dag-pb nodes. Raw leaves always use CIDv1.
+    pub cid_version: Version,
+    /// The hash function for all blocks, or `None` to let the blockstore choose.
+    pub hash: Option<Code>,
+    /// The UnixFS v1.5 permission bits of the root node.
+    pub mode: Option<u32>,
+    /// The UnixFS v1.5 modification time of the root node.
+    pub mtime: Option<UnixTime>,
+}
+
+impl DagOptions {
+    /// The options used by go-ipfs/kubo before CIDv1 became the default:
+    /// dag-pb leaves, CIDv0 and sha2-256.
+    pub fn cid_v0() -> Self {
+        Self {
+            raw_leaves: false,
+            cid_version: Version::V0,
+            hash: Some(Code::Sha2_256),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the root node needs to carry UnixFS v1.5 metadata.
+    pub fn has_metadata(&self) -> bool {
+        self.mode.is_some() || self.mtime.is_some()
+    }
+}
+
+impl Default for DagOptions {
+    fn default() -> Self {
+        Self {
+            raw_leaves: true,
+            cid_version: Version::V1,
+            hash: None,
+            mode: None,
+            mtime: None,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chunker::DEFAULT_CHUNKS_SIZE;
+    use crate::{
+        chunker::{ChunkerConfig, DEFAULT_CHUNKS_SIZE},
+        unixfs::UnixFsFile,
+    };
     use wnfs_common::MemoryBlockStore;
 
     #[tokio::test]
@@ -220,6 +327,179 @@ mod tests {
         Ok(())
     }
 
+    async fn store_bytes(content: &'static [u8], options: DagOptions) -> Result<Cid> {
+        let store = &MemoryBlockStore::new();
+        FileBuilder::new()
+            .content_bytes(content)
+            .options(options)
+            .build()?
+            .store(store)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_cid_v0_matches_kubo() -> Result<()> {
+        // `echo "hello world" | ipfs add --cid-version=0`
+        let cid = store_bytes(b"hello world\n", DagOptions::cid_v0()).await?;
+        assert_eq!(
+            cid.to_string(),
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o"
+        );
+
+        // `ipfs add --cid-version=0` of an empty file
+        let cid = store_bytes(b"", DagOptions::cid_v0()).await?;
+        assert_eq!(
+            cid.to_string(),
+            "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_multi_chunk_dags_are_stable() -> Result<()> {
+        // 200 chunks of 4 bytes, so the balanced tree has two levels with 174 links per node.
+        // Guards the CIDs of multi-level balanced and trickle DAGs against regressions.
+        let content: Vec<u8> = (0..200)
+            .flat_map(|i| format!("{i:03}\n").into_bytes())
+            .collect();
+        let cid_v1 = DagOptions {
+            hash: Some(Code::Sha2_256),
+            ..Default::default()
+        };
+
+        for (trickle, options, expected) in [
+            (
+                false,
+                DagOptions::cid_v0(),
+                "QmSt27GRGqu5ZP7rmm1HocNATBLtKQDhG4fFvPordhEPqP",
+            ),
+            (
+                true,
+                DagOptions::cid_v0(),
+                "QmWGafDprbdyPLmvSwJpPzr9bRxoqBQvcwMyvU8kcg5V9i",
+            ),
+            (
+                false,
+                cid_v1,
+                "bafybeiakdtwrfeodesgnfbjuv24xwysoe2q36nibdvgfmbmoaovzcqzwha",
+            ),
+        ] {
+            let store = &MemoryBlockStore::new();
+            let cid = FileBuilder::new()
+                .content_bytes(content.clone())
+                .fixed_chunker(4)
+                .trickle(trickle)
+                .options(options)
+                .build()?
+                .store(store)
+                .await?;
+            assert_eq!(cid.to_string(), expected, "trickle: {trickle}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_raw_leaves_match_kubo() -> Result<()> {
+        // `echo -n "hello world" | ipfs add --cid-version=1`
+        let options = DagOptions {
+            hash: Some(Code::Sha2_256),
+            ..Default::default()
+        };
+        let cid = store_bytes(b"hello world", options).await?;
+        assert_eq!(
+            cid.to_string(),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_stops_after_failed_block() -> Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let store = &MemoryBlockStore::new();
+        let cid = FileBuilder::new()
+            .content_bytes(b"000\n001\n002\n".to_vec())
+            .fixed_chunker(4)
+            .build()?
+            .store(store)
+            .await?;
+
+        // A store that's missing the second chunk
+        let partial_store = &MemoryBlockStore::new();
+        let root = UnixFsFile::load(&cid, store).await?;
+        partial_store
+            .put_block_keyed(cid, store.get_block(&cid).await?)
+            .await?;
+        for (i, link) in root.links().enumerate() {
+            let link_cid = link?.cid;
+            if i != 1 {
+                partial_store
+                    .put_block_keyed(link_cid, store.get_block(&link_cid).await?)
+                    .await?;
+            }
+        }
+
+        let mut reader = root
+            .into_content_reader(partial_store, None)?
+            .with_read_ahead(3);
+        let mut buf = [0u8; 4];
+        assert_eq!(reader.read(&mut buf).await?, 4);
+        assert_eq!(&buf, b"000\n");
+        assert!(reader.read(&mut buf).await.is_err());
+        assert!(reader.read(&mut buf).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cid_v0_requires_sha2_256() {
+        let result = FileBuilder::new()
+            .content_bytes(&b"hello world"[..])
+            .cid_version(Version::V0)
+            .hash(Code::Blake3_256)
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mode_and_mtime_are_set_on_root() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let mtime = UnixTime::new(1_700_000_000, 500);
+        for (len, trickle) in [
+            (3, false),
+            (3, true),
+            (1024 * 1024, false),
+            (1024 * 1024, true),
+        ] {
+            let content = vec![42u8; len];
+            let root_cid = FileBuilder::new()
+                .content_bytes(content.clone())
+                .trickle(trickle)
+                .mode(0o644)
+                .mtime(mtime)
+                .build()?
+                .store(store)
+                .await?;
+
+            let file = crate::unixfs::UnixFsFile::load(&root_cid, store).await?;
+            assert_eq!(file.mode(), Some(0o644));
+            assert_eq!(file.mtime(), Some(mtime));
+            assert_eq!(file.filesize(), Some(len as u64));
+
+            let mut buffer = Vec::new();
+            let mut reader = file.into_content_reader(store, None)?;
+            tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut buffer).await?;
+            assert_eq!(buffer, content);
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_chunk_config_from_str() {
         assert_eq!(
@@ -248,7 +528,7 @@ mod tests {
 #[cfg(test)]
 mod proptests {
     use super::*;
-    use crate::unixfs::UnixFsFile;
+    use crate::{chunker::ChunkerConfig, unixfs::UnixFsFile};
     use proptest::{option, strategy::Strategy};
     use rand_chacha::ChaCha12Rng;
     use rand_core::{RngCore, SeedableRng};
@@ -271,6 +551,9 @@ mod proptests {
         #[strategy(2..DEFAULT_DEGREE)] degree: usize,
         #[strategy(0usize..5_000_000)] len: usize,
         #[strategy(arb_chunker())] chunker: ChunkerConfig,
+        #[strategy(1usize..8)] read_ahead: usize,
+        trickle: bool,
+        raw_leaves: bool,
     ) {
         let store = &MemoryBlockStore::new();
         let rng = &mut ChaCha12Rng::seed_from_u64(seed);
@@ -282,6 +565,8 @@ mod proptests {
                 .content_bytes(data.clone())
                 .chunker(chunker)
                 .degree(degree)
+                .trickle(trickle)
+                .raw_leaves(raw_leaves)
                 .build()?
                 .store(store)
                 .await?;
@@ -290,7 +575,9 @@ mod proptests {
             assert_eq!(file.filesize(), Some(len as u64));
 
             let mut buffer = Vec::new();
-            let mut reader = file.into_content_reader(store, None)?;
+            let mut reader = file
+                .into_content_reader(store, None)?
+                .with_read_ahead(read_ahead);
             reader.read_to_end(&mut buffer).await?;
 
             assert_eq!(buffer, data);
@@ -308,6 +595,7 @@ mod proptests {
         #[strategy(0usize..100_000)] seek_start: usize,
         #[strategy(0usize..1_000)] seek_len: usize,
         #[strategy(arb_chunker())] chunker: ChunkerConfig,
+        #[strategy(1usize..8)] read_ahead: usize,
     ) {
         let store = &MemoryBlockStore::new();
         let rng = &mut ChaCha12Rng::seed_from_u64(seed);
@@ -330,7 +618,9 @@ mod proptests {
             assert_eq!(file.filesize(), Some(len as u64));
 
             let mut buffer = vec![0; seek_len];
-            let mut reader = file.into_content_reader(store, None)?;
+            let mut reader = file
+                .into_content_reader(store, None)?
+                .with_read_ahead(read_ahead);
             reader.seek(SeekFrom::Start(seek_start as u64)).await?;
             let read = reader.read_exact(&mut buffer).await?;
 
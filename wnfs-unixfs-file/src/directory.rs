@@ -0,0 +1,847 @@
+use crate::{
+    builder::encode_unixfs_pb,
+    codecs::Codec,
+    protobufs,
+    types::{Block, Link},
+    unixfs::{DataType, Node, UnixFsFile},
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use bytes::Bytes;
+use libipld::Cid;
+use prost::Message;
+use std::collections::BTreeMap;
+use wnfs_common::{
+    utils::{boxed_fut, BoxFuture},
+    BlockStore,
+};
+
+/// Directories whose estimated size exceeds this many bytes are sharded, taken from kubo's defaults
+/// <https://github.com/ipfs/kubo/blob/master/docs/config.md#internalunixfshamtdirectorysizethreshold>
+pub const DEFAULT_HAMT_THRESHOLD: usize = 256 * 1024;
+
+/// Default fanout of HAMT-sharded directories, taken from unixfs specs
+/// <https://github.com/ipfs/specs/blob/main/UNIXFS.md#hamt-directories>
+pub const DEFAULT_HAMT_FANOUT: u64 = 256;
+
+/// The multicodec code of the murmur3-x64-64 hash function used for sharding directories.
+pub const HAMT_HASH_TYPE: u64 = 0x22;
+
+/// Constructs a UnixFS directory from links to its entries.
+///
+/// If the directory is expected to get too large for a single block, it's
+/// encoded as a HAMT-sharded directory instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryBuilder {
+    entries: Vec<Link>,
+    hamt_threshold: usize,
+    fanout: u64,
+}
+
+/// Representation of a constructed directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directory {
+    entries: BTreeMap<String, Link>,
+    fanout: Option<u64>,
+}
+
+/// A decoded UnixFS directory node.
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnixFsDirectory {
+    /// A directory that lists all of its entries in a single node.
+    Basic(Node),
+    /// The root node of a HAMT-sharded directory.
+    Sharded(Node),
+}
+
+/// Any UnixFS node that can appear in a directory tree.
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnixFsEntry {
+    File(UnixFsFile),
+    Directory(UnixFsDirectory),
+    /// A symbolic link with its target path.
+    Symlink(String),
+}
+
+#[derive(Debug, Default)]
+struct Shard {
+    slots: BTreeMap<usize, ShardSlot>,
+}
+
+#[derive(Debug)]
+enum ShardSlot {
+    Entry(Link, [u8; 8]),
+    Shard(Shard),
+}
+
+impl Default for DirectoryBuilder {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            hamt_threshold: DEFAULT_HAMT_THRESHOLD,
+            fanout: DEFAULT_HAMT_FANOUT,
+        }
+    }
+}
+
+impl DirectoryBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an entry with given name, pointing to given CID.
+    ///
+    /// `tsize` is the cumulative size of the linked DAG, if known.
+    pub fn add_link(mut self, name: impl Into<String>, cid: Cid, tsize: Option<u64>) -> Self {
+        self.entries.push(Link {
+            cid,
+            name: Some(name.into()),
+            tsize,
+        });
+        self
+    }
+
+    /// Set the estimated size in bytes above which the directory gets sharded.
+    ///
+    /// The estimate is the sum of the lengths of all entry names and CIDs.
+    pub fn hamt_threshold(mut self, hamt_threshold: usize) -> Self {
+        self.hamt_threshold = hamt_threshold;
+        self
+    }
+
+    /// Set the fanout of sharded directory nodes.
+    ///
+    /// Needs to be a power of two and a multiple of 8.
+    pub fn fanout(mut self, fanout: u64) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    pub fn build(self) -> Result<Directory> {
+        ensure!(
+            self.fanout.is_power_of_two() && self.fanout >= 8,
+            "fanout must be a power of two and a multiple of 8, got {}",
+            self.fanout
+        );
+
+        let mut estimated_size = 0;
+        let mut entries = BTreeMap::new();
+        for link in self.entries {
+            let name = link.name.clone().unwrap_or_default();
+            ensure!(!name.is_empty(), "directory entries need a name");
+            estimated_size += name.len() + link.cid.encoded_len();
+            if entries.insert(name.clone(), link).is_some() {
+                bail!("duplicate directory entry: {name}");
+            }
+        }
+
+        let fanout = (estimated_size > self.hamt_threshold).then_some(self.fanout);
+
+        Ok(Directory { entries, fanout })
+    }
+}
+
+impl Directory {
+    /// Whether this directory gets encoded as a HAMT-sharded directory.
+    pub fn is_sharded(&self) -> bool {
+        self.fanout.is_some()
+    }
+
+    pub async fn store(self, store: &impl BlockStore) -> Result<Cid> {
+        let (cid, _) = self.store_with_tsize(store).await?;
+        Ok(cid)
+    }
+
+    /// Stores the directory, returning its CID and the cumulative size of the stored DAG.
+    ///
+    /// The cumulative size includes the sizes of the linked entries, as far as
+    /// they were given when adding them.
+    pub async fn store_with_tsize(self, store: &impl BlockStore) -> Result<(Cid, u64)> {
+        let Some(fanout) = self.fanout else {
+            let inner = protobufs::Data {
+                r#type: DataType::Directory as i32,
+                ..Default::default()
+            };
+            let links = self.entries.into_values().map(pb_link).collect();
+            return store_pb_node(&inner, links, store).await;
+        };
+
+        let mut shard = Shard::default();
+        for link in self.entries.into_values() {
+            let hash = murmur3_x64_64(link.name.as_deref().unwrap_or_default().as_bytes());
+            shard.insert(link, hash, 0, fanout)?;
+        }
+
+        shard.store(fanout, store).await
+    }
+}
+
+impl Shard {
+    fn insert(&mut self, link: Link, hash: [u8; 8], depth: u32, fanout: u64) -> Result<()> {
+        let index = hash_index(&hash, depth, fanout)?;
+        match self.slots.remove(&index) {
+            None => {
+                self.slots.insert(index, ShardSlot::Entry(link, hash));
+            }
+            Some(ShardSlot::Entry(other, other_hash)) => {
+                let mut child = Shard::default();
+                child.insert(other, other_hash, depth + 1, fanout)?;
+                child.insert(link, hash, depth + 1, fanout)?;
+                self.slots.insert(index, ShardSlot::Shard(child));
+            }
+            Some(ShardSlot::Shard(mut child)) => {
+                child.insert(link, hash, depth + 1, fanout)?;
+                self.slots.insert(index, ShardSlot::Shard(child));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn store<'a>(
+        self,
+        fanout: u64,
+        store: &'a impl BlockStore,
+    ) -> BoxFuture<'a, Result<(Cid, u64)>> {
+        boxed_fut(async move {
+            let mut bitfield = vec![0u8; fanout as usize / 8];
+            let mut links = Vec::with_capacity(self.slots.len());
+            for (index, slot) in self.slots {
+                let len = bitfield.len();
+                bitfield[len - 1 - index / 8] |= 1 << (index % 8);
+
+                let prefix = slot_prefix(index, fanout);
+                links.push(match slot {
+                    ShardSlot::Entry(link, _) => {
+                        let name = format!("{prefix}{}", link.name.as_deref().unwrap_or_default());
+                        protobufs::PbLink {
+                            name: Some(name),
+                            ..pb_link(link)
+                        }
+                    }
+                    ShardSlot::Shard(child) => {
+                        let (cid, tsize) = child.store(fanout, store).await?;
+                        protobufs::PbLink {
+                            hash: Some(cid.to_bytes()),
+                            name: Some(prefix),
+                            tsize: Some(tsize),
+                        }
+                    }
+                });
+            }
+
+            let inner = protobufs::Data {
+                r#type: DataType::HamtShard as i32,
+                data: Some(bitfield.into()),
+                hash_type: Some(HAMT_HASH_TYPE),
+                fanout: Some(fanout),
+                ..Default::default()
+            };
+
+            store_pb_node(&inner, links, store).await
+        })
+    }
+}
+
+impl UnixFsDirectory {
+    pub async fn load(cid: &Cid, store: &impl BlockStore) -> Result<Self> {
+        let block = store.get_block(cid).await?;
+        Self::decode(cid, block)
+    }
+
+    pub fn decode(cid: &Cid, buf: Bytes) -> Result<Self> {
+        ensure!(
+            cid.codec() == Codec::DagPb as u64,
+            "unixfs directories must be dag-pb encoded, got codec {}",
+            cid.codec()
+        );
+
+        let outer = protobufs::PbNode::decode(buf)?;
+        let inner_data = outer
+            .data
+            .as_ref()
+            .cloned()
+            .ok_or_else(|| anyhow!("missing data"))?;
+        let inner = protobufs::Data::decode(inner_data)?;
+        let typ: DataType = inner.r#type.try_into()?;
+        let node = Node { outer, inner };
+
+        match typ {
+            DataType::Directory => Ok(UnixFsDirectory::Basic(node)),
+            DataType::HamtShard => {
+                shard_fanout(&node)?;
+                Ok(UnixFsDirectory::Sharded(node))
+            }
+            _ => bail!("not a unixfs directory: {typ:?}"),
+        }
+    }
+
+    pub fn is_sharded(&self) -> bool {
+        matches!(self, UnixFsDirectory::Sharded(_))
+    }
+
+    /// Looks up the directory entry with given name.
+    pub async fn get(&self, name: &str, store: &impl BlockStore) -> Result<Option<Link>> {
+        match self {
+            UnixFsDirectory::Basic(node) => node
+                .links()
+                .find(|link| matches!(link, Ok(link) if link.name == Some(name)))
+                .map(|link| Ok(link?.to_owned()))
+                .transpose(),
+            UnixFsDirectory::Sharded(node) => {
+                let hash = murmur3_x64_64(name.as_bytes());
+                shard_get(node, name, hash, 0, store).await
+            }
+        }
+    }
+
+    /// Lists all entries of this directory.
+    ///
+    /// For sharded directories, this loads all the shards and strips the
+    /// shard prefixes from the entry names.
+    pub async fn entries(&self, store: &impl BlockStore) -> Result<Vec<Link>> {
+        match self {
+            UnixFsDirectory::Basic(node) => node.links().map(|link| Ok(link?.to_owned())).collect(),
+            UnixFsDirectory::Sharded(node) => {
+                let mut entries = Vec::new();
+                shard_entries(node, &mut entries, store).await?;
+                entries.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(entries)
+            }
+        }
+    }
+}
+
+impl UnixFsEntry {
+    pub async fn load(cid: &Cid, store: &impl BlockStore) -> Result<Self> {
+        let block = store.get_block(cid).await?;
+        Self::decode(cid, block)
+    }
+
+    pub fn decode(cid: &Cid, buf: Bytes) -> Result<Self> {
+        if cid.codec() == Codec::Raw as u64 {
+            return Ok(UnixFsEntry::File(UnixFsFile::decode(cid, buf)?));
+        }
+
+        let outer = protobufs::PbNode::decode(buf.clone())?;
+        let inner_data = outer.data.ok_or_else(|| anyhow!("missing data"))?;
+        let inner = protobufs::Data::decode(inner_data)?;
+        let typ: DataType = inner.r#type.try_into()?;
+
+        match typ {
+            DataType::File => Ok(UnixFsEntry::File(UnixFsFile::decode(cid, buf)?)),
+            DataType::Directory | DataType::HamtShard => {
+                Ok(UnixFsEntry::Directory(UnixFsDirectory::decode(cid, buf)?))
+            }
+            DataType::Symlink => {
+                let target = inner.data.unwrap_or_default();
+                Ok(UnixFsEntry::Symlink(String::from_utf8(target.to_vec())?))
+            }
+            _ => bail!("unixfs data type unsupported: {typ:?}"),
+        }
+    }
+}
+
+/// Stores a UnixFS symlink pointing to given target path.
+pub async fn store_symlink(target: &str, store: &impl BlockStore) -> Result<Cid> {
+    let inner = protobufs::Data {
+        r#type: DataType::Symlink as i32,
+        data: Some(Bytes::copy_from_slice(target.as_bytes())),
+        ..Default::default()
+    };
+
+    let (cid, _) = store_pb_node(&inner, Vec::new(), store).await?;
+    Ok(cid)
+}
+
+/// Computes the cumulative size of the DAG at given CID, i.e. the sum of the sizes of its blocks.
+///
+/// This is the `Tsize` to use when linking to the DAG. The sizes recorded in dag-pb
+/// links are trusted, only links without a recorded size are followed.
+pub fn cumulative_size<'a>(cid: &'a Cid, store: &'a impl BlockStore) -> BoxFuture<'a, Result<u64>> {
+    boxed_fut(async move {
+        let block = store.get_block(cid).await?;
+        let mut size = block.len() as u64;
+        if cid.codec() != Codec::DagPb as u64 {
+            return Ok(size);
+        }
+
+        for link in protobufs::PbNode::decode(block)?.links {
+            size += match link.tsize {
+                Some(tsize) => tsize,
+                None => {
+                    let cid = Cid::try_from(link.hash.as_deref().unwrap_or_default())?;
+                    cumulative_size(&cid, store).await?
+                }
+            };
+        }
+
+        Ok(size)
+    })
+}
+
+/// Resolves a path of entry names, starting at the directory at `root`.
+///
+/// Returns `None` if any entry along the path doesn't exist, and errors
+/// if any node before the last segment isn't a directory.
+pub async fn resolve_path(
+    root: &Cid,
+    path: &[impl AsRef<str>],
+    store: &impl BlockStore,
+) -> Result<Option<Cid>> {
+    let mut cid = *root;
+    for segment in path {
+        let dir = UnixFsDirectory::load(&cid, store).await?;
+        match dir.get(segment.as_ref(), store).await? {
+            Some(link) => cid = link.cid,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(cid))
+}
+
+fn shard_get<'a>(
+    node: &'a Node,
+    name: &'a str,
+    hash: [u8; 8],
+    depth: u32,
+    store: &'a impl BlockStore,
+) -> BoxFuture<'a, Result<Option<Link>>> {
+    boxed_fut(async move {
+        let fanout = shard_fanout(node)?;
+        let prefix = slot_prefix(hash_index(&hash, depth, fanout)?, fanout);
+
+        for link in node.links() {
+            let link = link?;
+            let Some(link_name) = link.name.and_then(|n| n.strip_prefix(&prefix)) else {
+                continue;
+            };
+
+            if link_name.is_empty() {
+                let child = match UnixFsDirectory::load(&link.cid, store).await? {
+                    UnixFsDirectory::Sharded(child) => child,
+                    UnixFsDirectory::Basic(_) => bail!("expected a hamt shard at {}", link.cid),
+                };
+                return shard_get(&child, name, hash, depth + 1, store).await;
+            }
+
+            if link_name == name {
+                return Ok(Some(Link {
+                    name: Some(name.to_string()),
+                    ..link.to_owned()
+                }));
+            }
+        }
+
+        Ok(None)
+    })
+}
+
+fn shard_entries<'a>(
+    node: &'a Node,
+    entries: &'a mut Vec<Link>,
+    store: &'a impl BlockStore,
+) -> BoxFuture<'a, Result<()>> {
+    boxed_fut(async move {
+        let prefix_len = slot_prefix(0, shard_fanout(node)?).len();
+
+        for link in node.links() {
+            let link = link?;
+            let name = link.name.unwrap_or_default();
+            let (Some(prefix), Some(entry_name)) = (name.get(..prefix_len), name.get(prefix_len..))
+            else {
+                bail!("invalid hamt shard link: {name}");
+            };
+            ensure!(
+                prefix
+                    .bytes()
+                    .all(|b| matches!(b, b'0'..=b'9' | b'A'..=b'F')),
+                "invalid hamt shard link: {name}"
+            );
+
+            if entry_name.is_empty() {
+                match UnixFsDirectory::load(&link.cid, store).await? {
+                    UnixFsDirectory::Sharded(child) => {
+                        shard_entries(&child, entries, store).await?
+                    }
+                    UnixFsDirectory::Basic(_) => bail!("expected a hamt shard at {}", link.cid),
+                }
+            } else {
+                entries.push(Link {
+                    name: Some(entry_name.to_string()),
+                    ..link.to_owned()
+                });
+            }
+        }
+
+        Ok(())
+    })
+}
+
+fn shard_fanout(node: &Node) -> Result<u64> {
+    ensure!(
+        node.inner.hash_type == Some(HAMT_HASH_TYPE),
+        "unsupported hamt hash type: {:?}",
+        node.inner.hash_type
+    );
+
+    let fanout = node
+        .inner
+        .fanout
+        .ok_or_else(|| anyhow!("hamt shard is missing fanout"))?;
+    ensure!(
+        fanout.is_power_of_two() && fanout >= 8,
+        "invalid hamt fanout: {fanout}"
+    );
+
+    Ok(fanout)
+}
+
+/// The link name prefix for given slot index: The index as uppercase hex, padded to the fanout's width.
+fn slot_prefix(index: usize, fanout: u64) -> String {
+    let width = format!("{:X}", fanout - 1).len();
+    format!("{index:0width$X}")
+}
+
+/// Reads the slot index at given depth from the hash, most significant bits first.
+fn hash_index(hash: &[u8; 8], depth: u32, fanout: u64) -> Result<usize> {
+    let bits = fanout.trailing_zeros();
+    let start = depth * bits;
+    ensure!(
+        start + bits <= u64::BITS,
+        "hamt shard depth exceeds the hash length"
+    );
+
+    let hash = u64::from_be_bytes(*hash);
+    Ok(((hash << start) >> (u64::BITS - bits)) as usize)
+}
+
+fn pb_link(link: Link) -> protobufs::PbLink {
+    protobufs::PbLink {
+        hash: Some(link.cid.to_bytes()),
+        name: link.name,
+        tsize: link.tsize,
+    }
+}
+
+async fn store_pb_node(
+    inner: &protobufs::Data,
+    links: Vec<protobufs::PbLink>,
+    store: &impl BlockStore,
+) -> Result<(Cid, u64)> {
+    let tsize_links = links
+        .iter()
+        .map(|l| l.tsize.unwrap_or_default())
+        .sum::<u64>();
+    let cids = links
+        .iter()
+        .map(|l| Ok(Cid::try_from(l.hash.as_deref().unwrap_or_default())?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let outer = encode_unixfs_pb(inner, links)?;
+    let data = Bytes::from(outer.encode_to_vec());
+    let tsize = data.len() as u64 + tsize_links;
+
+    let cid = Block::new(Codec::DagPb, data, cids).store(store).await?;
+    Ok((cid, tsize))
+}
+
+/// The first 64 bits of the 128 bit x64 variant of murmur3 with seed 0, big-endian.
+fn murmur3_x64_64(data: &[u8]) -> [u8; 8] {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    fn fmix64(mut k: u64) -> u64 {
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        k ^ (k >> 33)
+    }
+
+    let mut h1 = 0u64;
+    let mut h2 = 0u64;
+
+    let blocks = data.chunks_exact(16);
+    let tail = blocks.remainder();
+    for block in blocks {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    let mut k1 = 0u64;
+    let mut k2 = 0u64;
+    for (i, byte) in tail.iter().enumerate() {
+        if i < 8 {
+            k1 |= (*byte as u64) << (8 * i);
+        } else {
+            k2 |= (*byte as u64) << (8 * (i - 8));
+        }
+    }
+
+    if tail.len() > 8 {
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+    }
+
+    if !tail.is_empty() {
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    }
+
+    h1 ^= data.len() as u64;
+    h2 ^= data.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+
+    h1.to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::FileBuilder;
+    use wnfs_common::MemoryBlockStore;
+
+    async fn store_file(content: &str, store: &impl BlockStore) -> Result<Cid> {
+        FileBuilder::new()
+            .content_bytes(content.to_string())
+            .build()?
+            .store(store)
+            .await
+    }
+
+    #[test]
+    fn test_murmur3_reference_vector() {
+        // h1 from the reference x64 128 bit implementation
+        let hash = murmur3_x64_64(b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(u64::from_be_bytes(hash), 0xe34b_bc7b_bc07_1b6c);
+        assert_eq!(murmur3_x64_64(b""), [0; 8]);
+    }
+
+    #[test]
+    fn test_hash_index_reads_most_significant_bits_first() -> Result<()> {
+        let hash = [0xAB, 0xCD, 0, 0, 0, 0, 0, 0x12];
+        assert_eq!(hash_index(&hash, 0, 256)?, 0xAB);
+        assert_eq!(hash_index(&hash, 1, 256)?, 0xCD);
+        assert_eq!(hash_index(&hash, 0, 16)?, 0xA);
+        assert_eq!(hash_index(&hash, 7, 256)?, 0x12);
+        assert!(hash_index(&hash, 8, 256).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_basic_directory_resolves_paths() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let hello = store_file("hello", store).await?;
+        let world = store_file("world", store).await?;
+
+        let inner = DirectoryBuilder::new()
+            .add_link("world.txt", world, None)
+            .build()?;
+        assert!(!inner.is_sharded());
+        let inner = inner.store(store).await?;
+
+        let root = DirectoryBuilder::new()
+            .add_link("hello.txt", hello, None)
+            .add_link("inner", inner, None)
+            .build()?
+            .store(store)
+            .await?;
+
+        assert_eq!(
+            resolve_path(&root, &["hello.txt"], store).await?,
+            Some(hello)
+        );
+        assert_eq!(
+            resolve_path(&root, &["inner", "world.txt"], store).await?,
+            Some(world)
+        );
+        assert_eq!(resolve_path(&root, &["missing"], store).await?, None);
+        assert!(resolve_path(&root, &["hello.txt", "nested"], store)
+            .await
+            .is_err());
+
+        let names = UnixFsDirectory::load(&root, store)
+            .await?
+            .entries(store)
+            .await?
+            .into_iter()
+            .map(|link| link.name.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["hello.txt", "inner"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sharded_directory_resolves_all_entries() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let file = store_file("content", store).await?;
+
+        let mut builder = DirectoryBuilder::new().hamt_threshold(0).fanout(16);
+        for i in 0..500 {
+            builder = builder.add_link(format!("file-{i}"), file, Some(7));
+        }
+        let dir = builder.build()?;
+        assert!(dir.is_sharded());
+
+        let root = dir.store(store).await?;
+        let dir = UnixFsDirectory::load(&root, store).await?;
+        assert!(dir.is_sharded());
+
+        for i in 0..500 {
+            let link = dir.get(&format!("file-{i}"), store).await?.unwrap();
+            assert_eq!(link.cid, file);
+            assert_eq!(link.name, Some(format!("file-{i}")));
+        }
+        assert_eq!(dir.get("file-500", store).await?, None);
+
+        let entries = dir.entries(store).await?;
+        assert_eq!(entries.len(), 500);
+
+        let parent = DirectoryBuilder::new()
+            .add_link("sharded", root, None)
+            .build()?
+            .store(store)
+            .await?;
+        assert_eq!(
+            resolve_path(&parent, &["sharded", "file-42"], store).await?,
+            Some(file)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sharded_directory_with_malformed_link_names_fails() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let file = store_file("content", store).await?;
+
+        for name in ["1é", "zzfile", "1"] {
+            let inner = protobufs::Data {
+                r#type: DataType::HamtShard as i32,
+                data: Some(vec![0u8; 32].into()),
+                hash_type: Some(HAMT_HASH_TYPE),
+                fanout: Some(256),
+                ..Default::default()
+            };
+            let link = protobufs::PbLink {
+                hash: Some(file.to_bytes()),
+                name: Some(name.to_string()),
+                tsize: None,
+            };
+            let (cid, _) = store_pb_node(&inner, vec![link], store).await?;
+
+            let dir = UnixFsDirectory::load(&cid, store).await?;
+            assert!(dir.entries(store).await.is_err(), "link name: {name}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_directory_tsize_is_cumulative_size() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let file = store_file("content", store).await?;
+        let file_size = cumulative_size(&file, store).await?;
+        assert_eq!(file_size, store.get_block(&file).await?.len() as u64);
+
+        let (basic, basic_size) = DirectoryBuilder::new()
+            .add_link("file", file, Some(file_size))
+            .build()?
+            .store_with_tsize(store)
+            .await?;
+        let basic_block_size = store.get_block(&basic).await?.len() as u64;
+        assert_eq!(basic_size, basic_block_size + file_size);
+
+        let mut builder = DirectoryBuilder::new().hamt_threshold(0).fanout(16);
+        for i in 0..100 {
+            builder = builder.add_link(format!("file-{i}"), file, Some(file_size));
+        }
+        let (sharded, sharded_size) = builder.build()?.store_with_tsize(store).await?;
+        assert_eq!(sharded_size, cumulative_size(&sharded, store).await?);
+
+        // Links without a recorded size are followed
+        let (parent, _) = DirectoryBuilder::new()
+            .add_link("sharded", sharded, None)
+            .build()?
+            .store_with_tsize(store)
+            .await?;
+        let parent_block_size = store.get_block(&parent).await?.len() as u64;
+        assert_eq!(
+            cumulative_size(&parent, store).await?,
+            parent_block_size + sharded_size
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_directories_get_sharded() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let file = store_file("content", store).await?;
+
+        let mut builder = DirectoryBuilder::new();
+        for i in 0..5_000 {
+            builder = builder.add_link(format!("a-somewhat-long-file-name-{i}"), file, None);
+        }
+
+        assert!(builder.build()?.is_sharded());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_entries_can_be_loaded_by_type() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let file = store_file("content", store).await?;
+        let symlink = store_symlink("../content", store).await?;
+        let dir = DirectoryBuilder::new()
+            .add_link("file", file, None)
+            .add_link("symlink", symlink, None)
+            .build()?
+            .store(store)
+            .await?;
+
+        assert!(matches!(
+            UnixFsEntry::load(&file, store).await?,
+            UnixFsEntry::File(_)
+        ));
+        assert!(matches!(
+            UnixFsEntry::load(&dir, store).await?,
+            UnixFsEntry::Directory(_)
+        ));
+        assert_eq!(
+            UnixFsEntry::load(&symlink, store).await?,
+            UnixFsEntry::Symlink("../content".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_entries_are_rejected() {
+        let result = DirectoryBuilder::new()
+            .add_link("a", Cid::default(), None)
+            .add_link("a", Cid::default(), None)
+            .build();
+
+        assert!(result.is_err());
+    }
+}
@@ -2,7 +2,9 @@ pub mod balanced_tree;
 pub mod builder;
 pub mod chunker;
 pub mod codecs;
+pub mod directory;
 pub mod protobufs;
+pub mod trickle_tree;
 mod types;
 pub mod unixfs;
 
@@ -1,5 +1,8 @@
-use bytes::Bytes;
-use prost::{Enumeration, Message};
+use bytes::{Buf, BufMut, Bytes};
+use prost::{
+    encoding::{self, DecodeContext, WireType},
+    DecodeError, Enumeration, Message,
+};
 
 /// An IPFS MerkleDAG Link
 #[derive(Clone, PartialEq, Eq, Message)]
@@ -16,13 +19,14 @@ pub struct PbLink {
 }
 
 /// An IPFS MerkleDAG Node
-#[derive(Clone, PartialEq, Eq, Message)]
+///
+/// `Message` is implemented by hand, since the canonical dag-pb encoding has
+/// the links before the data, while prost would encode fields in tag order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct PbNode {
     /// refs to other objects
-    #[prost(message, repeated, tag = "2")]
     pub links: Vec<PbLink>,
     /// opaque user data
-    #[prost(bytes = "bytes", optional, tag = "1")]
     pub data: Option<Bytes>,
 }
 
@@ -34,12 +38,87 @@ pub struct Data {
     pub data: Option<Bytes>,
     #[prost(uint64, optional, tag = "3")]
     pub filesize: Option<u64>,
-    #[prost(uint64, repeated, tag = "4")]
+    /// not packed, as in go-unixfs' proto2 schema
+    #[prost(uint64, repeated, packed = "false", tag = "4")]
     pub blocksizes: Vec<u64>,
     #[prost(uint64, optional, tag = "5")]
     pub hash_type: Option<u64>,
     #[prost(uint64, optional, tag = "6")]
     pub fanout: Option<u64>,
+    /// unix permission bits, UnixFS v1.5
+    #[prost(uint32, optional, tag = "7")]
+    pub mode: Option<u32>,
+    /// modification time, UnixFS v1.5
+    #[prost(message, optional, tag = "8")]
+    pub mtime: Option<UnixTime>,
+}
+
+/// A point in time as defined by UnixFS v1.5
+#[derive(Clone, Copy, PartialEq, Eq, Message)]
+pub struct UnixTime {
+    /// seconds since (or before, if negative) the unix epoch
+    #[prost(int64, required, tag = "1")]
+    pub seconds: i64,
+    /// nanoseconds in the range `1..=999_999_999`, omitted if zero
+    #[prost(fixed32, optional, tag = "2")]
+    pub fractional_nanoseconds: Option<u32>,
+}
+
+impl Message for PbNode {
+    fn encode_raw<B>(&self, buf: &mut B)
+    where
+        B: BufMut,
+    {
+        for link in &self.links {
+            encoding::message::encode(2, link, buf);
+        }
+        if let Some(data) = &self.data {
+            encoding::bytes::encode(1, data, buf);
+        }
+    }
+
+    fn merge_field<B>(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        match tag {
+            1 => {
+                let data = self.data.get_or_insert_with(Default::default);
+                encoding::bytes::merge(wire_type, data, buf, ctx)
+            }
+            2 => encoding::message::merge_repeated(wire_type, &mut self.links, buf, ctx),
+            _ => encoding::skip_field(wire_type, tag, buf, ctx),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        encoding::message::encoded_len_repeated(2, &self.links)
+            + self
+                .data
+                .as_ref()
+                .map_or(0, |data| encoding::bytes::encoded_len(1, data))
+    }
+
+    fn clear(&mut self) {
+        self.links.clear();
+        self.data = None;
+    }
+}
+
+impl UnixTime {
+    /// Creates a timestamp, omitting the nanoseconds if they're zero.
+    pub fn new(seconds: i64, nanoseconds: u32) -> Self {
+        Self {
+            seconds,
+            fractional_nanoseconds: (nanoseconds != 0).then_some(nanoseconds),
+        }
+    }
 }
 /// Nested message and enum types in `Data`.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
@@ -0,0 +1,189 @@
+//! Trickle DAG layout, as implemented by go-unixfs' `importer/trickle` package.
+//!
+//! A trickle DAG's root first links to up to `degree` leaves, followed by
+//! `LAYER_REPEAT` subtrees of depth 1, `LAYER_REPEAT` subtrees of depth 2 and so on.
+//! Each subtree of depth `d` has the same shape, limited to depth `d`.
+//!
+//! Since data is only ever added at the "end" of the tree, this layout is well suited
+//! for sequential reads and appends, e.g. for streaming media.
+
+use crate::{
+    balanced_tree::{LinkInfo, TreeNode},
+    builder::DagOptions,
+    types::Block,
+};
+use anyhow::Result;
+use async_stream::try_stream;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use libipld::Cid;
+use wnfs_common::{utils::CondSend, BlockStore};
+
+/// The number of subtrees of each depth that are added to a trickle node,
+/// taken from go-unixfs.
+pub const LAYER_REPEAT: usize = 4;
+
+/// A trickle node that is still being filled.
+struct TrickleFrame {
+    links: Vec<(Cid, LinkInfo)>,
+    /// The maximum depth of this node, `None` for the root.
+    max_depth: Option<usize>,
+    /// The depth of the next subtree to add.
+    depth: usize,
+    /// The number of subtrees of `depth` that were added so far.
+    repeat: usize,
+}
+
+impl TrickleFrame {
+    fn new(max_depth: Option<usize>, degree: usize) -> Self {
+        Self {
+            links: Vec::with_capacity(degree),
+            max_depth,
+            depth: 1,
+            repeat: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.max_depth.is_some_and(|max| self.depth >= max)
+    }
+
+    fn push_subtree(&mut self, link: (Cid, LinkInfo)) {
+        self.links.push(link);
+        self.repeat += 1;
+        if self.repeat == LAYER_REPEAT {
+            self.depth += 1;
+            self.repeat = 0;
+        }
+    }
+}
+
+pub(crate) fn stream_trickle_tree<'a>(
+    in_stream: impl Stream<Item = std::io::Result<Bytes>> + CondSend + 'a,
+    degree: usize,
+    options: DagOptions,
+    store: &'a impl BlockStore,
+) -> impl Stream<Item = Result<(Cid, Block)>> + 'a {
+    try_stream! {
+        let in_stream = in_stream.peekable();
+        tokio::pin!(in_stream);
+
+        // The stack of nodes on the path from the root to the node that's currently being
+        // filled. Blocks are yielded in post-order, like in the balanced tree.
+        let mut stack = vec![TrickleFrame::new(None, degree)];
+        let mut fill_leaves = true;
+
+        loop {
+            if fill_leaves {
+                let frame = stack.last_mut().expect("stack is never empty here");
+                while frame.links.len() < degree {
+                    let Some(chunk) = in_stream.next().await else {
+                        break;
+                    };
+
+                    let (block, link_info) = TreeNode::Leaf(chunk?).encode(&options)?;
+                    let cid = block.store_with(&options, store).await?;
+                    frame.links.push((cid, link_info));
+                    yield (cid, block);
+                }
+
+                fill_leaves = false;
+            }
+
+            let done = in_stream.as_mut().peek().await.is_none();
+            let frame = stack.last().expect("stack is never empty here");
+            if done || frame.is_full() {
+                let frame = stack.pop().expect("stack is never empty here");
+                let node = if stack.is_empty() {
+                    TreeNode::Root(frame.links)
+                } else {
+                    TreeNode::Stem(frame.links)
+                };
+                let (block, link_info) = node.encode(&options)?;
+                let cid = block.store_with(&options, store).await?;
+                yield (cid, block);
+
+                match stack.last_mut() {
+                    Some(parent) => parent.push_subtree((cid, link_info)),
+                    // final root, nothing to do
+                    None => break,
+                }
+            } else {
+                stack.push(TrickleFrame::new(Some(frame.depth), degree));
+                fill_leaves = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unixfs::UnixFsFile;
+    use futures::TryStreamExt;
+    use wnfs_common::MemoryBlockStore;
+
+    fn test_chunk_stream(num_chunks: usize) -> impl Stream<Item = std::io::Result<Bytes>> {
+        futures::stream::iter((0..num_chunks).map(|n| Ok(n.to_be_bytes().to_vec().into())))
+    }
+
+    /// Returns the number of links in each node, in depth-first order.
+    async fn shape(cid: &Cid, store: &impl BlockStore) -> Vec<usize> {
+        let mut out = vec![];
+        let mut stack = vec![*cid];
+        while let Some(cid) = stack.pop() {
+            let node = UnixFsFile::load(&cid, store).await.unwrap();
+            let links = node.links_owned().unwrap();
+            if links.is_empty() {
+                continue;
+            }
+            out.push(links.len());
+            stack.extend(links.into_iter().rev().map(|l| l.cid));
+        }
+        out
+    }
+
+    async fn build(num_chunks: usize, degree: usize, store: &impl BlockStore) -> Cid {
+        let blocks: Vec<_> = stream_trickle_tree(
+            test_chunk_stream(num_chunks),
+            degree,
+            DagOptions::default(),
+            store,
+        )
+        .try_collect()
+        .await
+        .unwrap();
+
+        let (root, _) = blocks.last().unwrap();
+        *root
+    }
+
+    #[tokio::test]
+    async fn trickle_tree_has_expected_shape() {
+        let store = &MemoryBlockStore::new();
+
+        // A single chunk is still wrapped in a root node
+        let root = build(1, 3, store).await;
+        assert_eq!(shape(&root, store).await, vec![1]);
+
+        // 3 leaves, then 4 subtrees of depth 1 with up to 3 leaves each
+        let root = build(3 + 4 * 3, 3, store).await;
+        assert_eq!(shape(&root, store).await, vec![7, 3, 3, 3, 3]);
+
+        // The first subtree of depth 2 has 3 leaves and up to 4 subtrees of depth 1
+        let root = build(3 + 4 * 3 + 3 + 2 * 3 + 1, 3, store).await;
+        assert_eq!(shape(&root, store).await, vec![8, 3, 3, 3, 3, 6, 3, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn trickle_tree_filesize_is_sum_of_chunks() {
+        let store = &MemoryBlockStore::new();
+        let num_chunks = 500;
+        let root = build(num_chunks, 4, store).await;
+        let node = UnixFsFile::load(&root, store).await.unwrap();
+        assert_eq!(
+            node.filesize(),
+            Some((num_chunks * std::mem::size_of::<usize>()) as u64)
+        );
+    }
+}
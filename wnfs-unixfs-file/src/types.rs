@@ -1,10 +1,14 @@
-use crate::{codecs::Codec, parse_links, protobufs};
-use anyhow::{anyhow, Result};
+use crate::{builder::DagOptions, codecs::Codec, parse_links, protobufs};
+use anyhow::{anyhow, ensure, Result};
 use bytes::Bytes;
-use libipld::Cid;
+use libipld::{
+    cid::Version,
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
 use std::{io::Cursor, pin::Pin};
 use tokio::io::AsyncRead;
-use wnfs_common::BlockStore;
+use wnfs_common::{BlockStore, MAX_BLOCK_SIZE};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Block {
@@ -43,6 +47,34 @@ impl Block {
             .await?)
     }
 
+    /// Stores the block using the CID version and hash function from the given options.
+    ///
+    /// CIDv0 only applies to dag-pb blocks, raw blocks are always addressed with CIDv1.
+    /// If no hash function is configured, CIDv1 blocks are hashed by the blockstore.
+    pub async fn store_with(&self, options: &DagOptions, store: &impl BlockStore) -> Result<Cid> {
+        let version = match self.codec {
+            Codec::DagPb => options.cid_version,
+            _ => Version::V1,
+        };
+
+        let code = match (version, options.hash) {
+            (_, Some(code)) => code,
+            (Version::V0, None) => Code::Sha2_256,
+            (Version::V1, None) => return self.store(store).await,
+        };
+
+        ensure!(
+            self.data.len() <= MAX_BLOCK_SIZE,
+            "block is too large: {} bytes",
+            self.data.len()
+        );
+
+        let cid = Cid::new(version, self.codec.into(), code.digest(&self.data))?;
+        store.put_block_keyed(cid, self.data.clone()).await?;
+
+        Ok(cid)
+    }
+
     /// Validate the block. Will return an error if the links are wrong.
     pub fn validate(&self) -> Result<()> {
         // check that the links are complete
@@ -1,7 +1,7 @@
 use crate::{
     chunker::DEFAULT_CHUNK_SIZE_LIMIT,
     codecs::Codec,
-    protobufs,
+    protobufs::{self, UnixTime},
     types::{Block, Link, LinkRef, Links, PbLinks},
 };
 use anyhow::{anyhow, bail, ensure, Result};
@@ -68,6 +68,14 @@ impl Node {
         &self.inner.blocksizes
     }
 
+    pub fn mode(&self) -> Option<u32> {
+        self.inner.mode
+    }
+
+    pub fn mtime(&self) -> Option<UnixTime> {
+        self.inner.mtime
+    }
+
     pub fn size(&self) -> Option<usize> {
         if self.outer.links.is_empty() {
             return Some(
@@ -180,6 +188,24 @@ impl UnixFsFile {
         }
     }
 
+    /// Returns the UnixFS v1.5 permission bits.
+    /// Raw leaves can't have a mode.
+    pub fn mode(&self) -> Option<u32> {
+        match self {
+            UnixFsFile::Raw(_) => None,
+            UnixFsFile::Node(node) => node.mode(),
+        }
+    }
+
+    /// Returns the UnixFS v1.5 modification time.
+    /// Raw leaves can't have a modification time.
+    pub fn mtime(&self) -> Option<UnixTime> {
+        match self {
+            UnixFsFile::Raw(_) => None,
+            UnixFsFile::Node(node) => node.mtime(),
+        }
+    }
+
     pub fn links(&self) -> Links<'_> {
         match self {
             UnixFsFile::Raw(_) => Links::Leaf,
@@ -217,6 +243,7 @@ impl UnixFsFile {
             pos_max,
             current_node: CurrentNodeState::Outer,
             current_links,
+            prefetch: Prefetch::new(DEFAULT_READ_AHEAD),
             store,
         })
     }
@@ -258,9 +285,11 @@ pub struct UnixFsFileReader<'a, B: BlockStore> {
     /// Absolute max position in bytes, only used for clipping responses
     pos_max: Option<usize>,
     /// Current node being operated on, only used for nested nodes (not the root).
-    current_node: CurrentNodeState<'a>,
+    current_node: CurrentNodeState,
     /// Stack of links left to traverse.
     current_links: Vec<VecDeque<Link>>,
+    /// Block loads that were started ahead of time.
+    prefetch: Prefetch<'a>,
     store: &'a B,
 }
 
@@ -269,6 +298,18 @@ impl<'a, B: BlockStore> UnixFsFileReader<'a, B> {
     pub fn size(&self) -> Option<u64> {
         self.root_node.filesize()
     }
+
+    /// Sets how many blocks may be fetched from the blockstore concurrently.
+    ///
+    /// Upcoming sibling blocks are requested ahead of time, while bytes are
+    /// still returned in order. This helps on high-latency blockstores.
+    ///
+    /// Defaults to `1`, which loads one block at a time. Values of `0` are
+    /// treated as `1`.
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.prefetch = Prefetch::new(read_ahead);
+        self
+    }
 }
 
 impl<'a, B: BlockStore + 'a> AsyncRead for UnixFsFileReader<'a, B> {
@@ -283,6 +324,7 @@ impl<'a, B: BlockStore + 'a> AsyncRead for UnixFsFileReader<'a, B> {
             pos_max,
             current_node,
             current_links,
+            prefetch,
             store,
         } = &mut *self;
 
@@ -301,6 +343,7 @@ impl<'a, B: BlockStore + 'a> AsyncRead for UnixFsFileReader<'a, B> {
                 buf,
                 current_links,
                 current_node,
+                prefetch,
             ),
         }
         // let bytes_read = *pos - pos_old; // Unused, used to be used for metrics
@@ -315,11 +358,13 @@ impl<'a, B: BlockStore + 'a> AsyncSeek for UnixFsFileReader<'a, B> {
             pos,
             current_node,
             current_links,
+            prefetch,
             ..
         } = &mut *self;
         let data_len = root_node.size();
         *current_node = CurrentNodeState::Outer;
         *current_links = vec![root_node.links_owned().unwrap()];
+        prefetch.clear();
         match position {
             std::io::SeekFrom::Start(offset) => {
                 let mut i = offset as usize;
@@ -411,7 +456,7 @@ pub fn find_block(node: &UnixFsFile, pos: u64, node_offset: u64) -> (u64, Option
 }
 
 #[allow(clippy::large_enum_variant)]
-pub enum CurrentNodeState<'a> {
+pub enum CurrentNodeState {
     // Initial state
     Outer,
     // Need to load next node from the list
@@ -427,11 +472,13 @@ pub enum CurrentNodeState<'a> {
     // Ongoing loading of the node
     Loading {
         node_offset: usize,
-        fut: BoxFuture<'a, Result<UnixFsFile>>,
+        cid: Cid,
     },
+    // Loading a node failed, reads keep failing until the reader seeks
+    Failed(String),
 }
 
-impl<'a> Debug for CurrentNodeState<'a> {
+impl Debug for CurrentNodeState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CurrentNodeState::Outer => write!(f, "CurrentNodeState::Outer"),
@@ -448,15 +495,117 @@ impl<'a> Debug for CurrentNodeState<'a> {
                     "CurrentNodeState::Loaded({node_offset:?}, {node_pos:?}, {node:?})"
                 )
             }
-            CurrentNodeState::Loading { .. } => write!(f, "CurrentNodeState::Loading(Fut)"),
+            CurrentNodeState::Loading { node_offset, cid } => {
+                write!(f, "CurrentNodeState::Loading({node_offset:?}, {cid})")
+            }
+            CurrentNodeState::Failed(message) => {
+                write!(f, "CurrentNodeState::Failed({message:?})")
+            }
         }
     }
 }
 
+/// Default number of blocks loaded concurrently by a `UnixFsFileReader`.
+pub const DEFAULT_READ_AHEAD: usize = 1;
+
+/// Keeps track of blocks that are being loaded ahead of time.
+///
+/// At most `read_ahead` blocks are in flight or waiting to be consumed at once.
+struct Prefetch<'a> {
+    read_ahead: usize,
+    in_flight: Vec<(Cid, BoxFuture<'a, Result<UnixFsFile>>)>,
+    ready: Vec<(Cid, Result<UnixFsFile>)>,
+}
+
+impl<'a> Prefetch<'a> {
+    fn new(read_ahead: usize) -> Self {
+        Self {
+            read_ahead: read_ahead.max(1),
+            in_flight: Vec::new(),
+            ready: Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.in_flight.len() + self.ready.len()
+    }
+
+    fn contains(&self, cid: &Cid) -> bool {
+        self.in_flight.iter().any(|(c, _)| c == cid) || self.ready.iter().any(|(c, _)| c == cid)
+    }
+
+    fn clear(&mut self) {
+        self.in_flight.clear();
+        self.ready.clear();
+    }
+
+    /// Starts loading the given block, if it isn't being loaded already.
+    fn request(&mut self, cid: Cid, store: &'a impl BlockStore) {
+        if !self.contains(&cid) {
+            let fut = boxed_fut(async move {
+                let block = store.get_block(&cid).await?;
+                UnixFsFile::decode(&cid, block)
+            });
+            self.in_flight.push((cid, fut));
+        }
+    }
+
+    /// Starts loading upcoming blocks until `read_ahead` loads are pending.
+    fn fill<'l>(&mut self, upcoming: impl Iterator<Item = &'l Link>, store: &'a impl BlockStore) {
+        for link in upcoming {
+            if self.len() >= self.read_ahead {
+                break;
+            }
+
+            self.request(link.cid, store);
+        }
+    }
+
+    /// Drives all pending loads and returns the block with given CID, once it's loaded.
+    fn poll_take(&mut self, cx: &mut Context<'_>, cid: &Cid) -> Poll<Result<UnixFsFile>> {
+        let mut i = 0;
+        while i < self.in_flight.len() {
+            match self.in_flight[i].1.poll_unpin(cx) {
+                Poll::Ready(result) => {
+                    let (cid, _) = self.in_flight.swap_remove(i);
+                    self.ready.push((cid, result));
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+
+        match self.ready.iter().position(|(c, _)| c == cid) {
+            Some(index) => Poll::Ready(self.ready.swap_remove(index).1),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<'a> Debug for Prefetch<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Prefetch")
+            .field("read_ahead", &self.read_ahead)
+            .field(
+                "in_flight",
+                &self
+                    .in_flight
+                    .iter()
+                    .map(|(cid, _)| cid)
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "ready",
+                &self.ready.iter().map(|(cid, _)| cid).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
 fn load_next_node<'a>(
     next_node_offset: usize,
-    current_node: &mut CurrentNodeState<'a>,
+    current_node: &mut CurrentNodeState,
     current_links: &mut Vec<VecDeque<Link>>,
+    prefetch: &mut Prefetch<'a>,
     store: &'a impl BlockStore,
 ) -> bool {
     let links = loop {
@@ -476,15 +625,12 @@ fn load_next_node<'a>(
 
     let link = links.pop_front().unwrap();
 
-    let fut = boxed_fut(async move {
-        let block = store.get_block(&link.cid).await?;
-        let node = UnixFsFile::decode(&link.cid, block)?;
+    prefetch.request(link.cid, store);
+    prefetch.fill(links.iter(), store);
 
-        Ok(node)
-    });
     *current_node = CurrentNodeState::Loading {
         node_offset: next_node_offset,
-        fut,
+        cid: link.cid,
     };
     true
 }
@@ -498,7 +644,8 @@ fn poll_read_file_at<'a>(
     pos_max: Option<usize>,
     buf: &mut tokio::io::ReadBuf<'_>,
     current_links: &mut Vec<VecDeque<Link>>,
-    current_node: &mut CurrentNodeState<'a>,
+    current_node: &mut CurrentNodeState,
+    prefetch: &mut Prefetch<'a>,
 ) -> Poll<std::io::Result<()>> {
     loop {
         if let Some(pos_max) = pos_max {
@@ -528,14 +675,19 @@ fn poll_read_file_at<'a>(
                 };
             }
             CurrentNodeState::NextNodeRequested { next_node_offset } => {
-                let loaded_next_node =
-                    load_next_node(*next_node_offset, current_node, current_links, store);
+                let loaded_next_node = load_next_node(
+                    *next_node_offset,
+                    current_node,
+                    current_links,
+                    prefetch,
+                    store,
+                );
                 if !loaded_next_node {
                     return Poll::Ready(Ok(()));
                 }
             }
-            CurrentNodeState::Loading { node_offset, fut } => {
-                match fut.poll_unpin(cx) {
+            CurrentNodeState::Loading { node_offset, cid } => {
+                match prefetch.poll_take(cx, cid) {
                     Poll::Pending => {
                         return Poll::Pending;
                     }
@@ -562,25 +714,24 @@ fn poll_read_file_at<'a>(
                                 }
                             }
                             Err(e) => {
-                                return Poll::Ready(Err(std::io::Error::new(
-                                    std::io::ErrorKind::InvalidData,
-                                    e.to_string(),
-                                )));
+                                prefetch.clear();
+                                *current_node = CurrentNodeState::Failed(e.to_string());
                             }
                         }
                         // TODO: do one read
                     }
                     Poll::Ready(Err(e)) => {
-                        *current_node = CurrentNodeState::NextNodeRequested {
-                            next_node_offset: *node_offset,
-                        };
-                        return Poll::Ready(Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            e.to_string(),
-                        )));
+                        prefetch.clear();
+                        *current_node = CurrentNodeState::Failed(e.to_string());
                     }
                 }
             }
+            CurrentNodeState::Failed(message) => {
+                return Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    message.clone(),
+                )));
+            }
             CurrentNodeState::Loaded {
                 ref node_offset,
                 ref mut node_pos,
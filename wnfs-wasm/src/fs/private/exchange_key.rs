@@ -1,9 +1,15 @@
 use crate::fs::utils::anyhow_error;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use js_sys::{Promise, Uint8Array};
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen_futures::JsFuture;
-use wnfs::private::{ExchangeKey as WnfsExchangeKey, PrivateKey as WnfsPrivateKey};
+use wnfs::{
+    error::ExchangeKeyError,
+    private::{
+        EncodedExchangeKey, ExchangeKey as WnfsExchangeKey, ExchangeKeyType,
+        PrivateKey as WnfsPrivateKey,
+    },
+};
 
 //--------------------------------------------------------------------------------------------------
 // Externs
@@ -45,11 +51,20 @@ pub struct ForeignPrivateKey(pub(crate) PrivateKey);
 //--------------------------------------------------------------------------------------------------
 
 impl WnfsExchangeKey for ForeignExchangeKey {
-    async fn from_modulus(modulus: &[u8]) -> Result<Self>
+    fn supports(key_type: ExchangeKeyType) -> bool {
+        // Foreign exchange keys are created from RSA moduli
+        key_type == ExchangeKeyType::Rsa
+    }
+
+    async fn from_encoded(key: &EncodedExchangeKey) -> Result<Self>
     where
         Self: Sized,
     {
-        let v = JsFuture::from(ExchangeKey::from_modulus(modulus))
+        if !Self::supports(key.key_type()) {
+            bail!(ExchangeKeyError::UnsupportedKeyType(key.key_type()));
+        }
+
+        let v = JsFuture::from(ExchangeKey::from_modulus(key.key_bytes()))
             .await
             .map_err(anyhow_error("Cannot create from modulus: {:?}"))?;
 
@@ -1,8 +1,9 @@
 use super::{AccessKey, ForeignExchangeKey, ForeignPrivateKey, PrivateForest, PrivateNode};
 use crate::{
-    fs::{utils::error, BlockStore, ForeignBlockStore, JsResult, Name, PrivateKey},
+    fs::{utils::error, BlockStore, ForeignBlockStore, JsResult, Name, PrivateKey, Rng},
     value,
 };
+use chrono::Utc;
 use js_sys::Promise;
 use libipld_core::cid::Cid;
 use std::rc::Rc;
@@ -25,6 +26,7 @@ pub fn share(
     recipient_exchange_root: Vec<u8>,
     forest: &PrivateForest,
     store: BlockStore,
+    mut rng: Rng,
 ) -> JsResult<Promise> {
     let mut forest = Rc::clone(&forest.0);
     let cid = Cid::try_from(&recipient_exchange_root[..]).map_err(error("Invalid CID"))?;
@@ -38,6 +40,7 @@ pub fn share(
             PublicLink::from_cid(cid),
             &mut forest,
             &store,
+            &mut rng,
         )
         .await
         .map_err(error("Cannot share item"))?;
@@ -102,9 +105,11 @@ pub fn receive_share(
     let forest = Rc::clone(&forest.0);
 
     Ok(future_to_promise(async move {
-        let node = recipient::receive_share(&share_name.0, &recipient_key, &forest, &store)
-            .await
-            .map_err(error("Cannot receive share"))?;
+        let node =
+            recipient::receive_share(&share_name.0, &recipient_key, Utc::now(), &forest, &store)
+                .await
+                .and_then(|node| node.into_node())
+                .map_err(error("Cannot receive share"))?;
 
         Ok(value!(PrivateNode(node)))
     }))
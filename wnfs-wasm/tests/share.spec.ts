@@ -52,7 +52,8 @@ test.describe("Share", () => {
         sharerRootDid,
         recipientExchRootCid,
         forest2,
-        store
+        store,
+        rng
       );
 
       const modulus = await recipientKey.getPublicKey().getPublicKeyModulus();
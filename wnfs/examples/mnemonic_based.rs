@@ -1,17 +1,15 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use chrono::Utc;
 use rand_chacha::ChaCha12Rng;
 use rand_core::SeedableRng;
-use rsa::{traits::PublicKeyParts, BigUint, Oaep, RsaPrivateKey, RsaPublicKey};
-use sha2::Sha256;
 use std::sync::Arc;
 use wnfs::{
     common::{BlockStore, MemoryBlockStore},
     private::{
         forest::{hamt::HamtForest, traits::PrivateForest},
-        share::{recipient, sharer},
-        AccessKey, ExchangeKey, PrivateDirectory, PrivateKey, PrivateNode, PUBLIC_KEY_EXPONENT,
+        share::{recipient, sharer, TYPED_EXCHANGE_KEY_NAME},
+        AccessKey, PrivateDirectory, PrivateNode, X25519PrivateKey, X25519PublicKey,
     },
     public::{PublicDirectory, PublicLink},
 };
@@ -86,15 +84,16 @@ async fn setup_seeded_keypair_access(
     // Create a random mnemonic and derive a keypair from it
     let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
     let seed = Seed::new(&mnemonic, /* optional password */ "");
-    let exchange_keypair = SeededExchangeKey::from_bip39_seed(seed)?;
+    let exchange_keypair = keypair_from_bip39_seed(seed)?;
+    let public_key = exchange_keypair.get_public_key().to_encoded();
 
     // Store the public key inside some public WNFS.
     // Building from scratch in this case. Would actually be stored next to the private forest usually.
     let mut exchange_root = PublicDirectory::new_rc(Utc::now());
     exchange_root
         .write(
-            &["main".into(), "v1.exchange_key".into()],
-            exchange_keypair.encode_public_key(),
+            &["main".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+            public_key.as_bytes().to_vec(),
             Utc::now(),
             store,
         )
@@ -108,7 +107,7 @@ async fn setup_seeded_keypair_access(
     let counter = recipient::find_latest_share_counter(
         0,
         1000,
-        &exchange_keypair.encode_public_key(),
+        public_key.as_bytes(),
         root_did,
         forest,
         store,
@@ -118,13 +117,15 @@ async fn setup_seeded_keypair_access(
     .unwrap_or_default();
 
     // Write the encrypted AccessKey into the forest
-    sharer::share::<PublicExchangeKey>(
+    let rng = &mut ChaCha12Rng::from_entropy();
+    sharer::share::<X25519PublicKey>(
         &access_key,
         counter,
         root_did,
         exchange_root,
         forest,
         store,
+        rng,
     )
     .await?;
 
@@ -138,14 +139,15 @@ async fn regain_access_from_mnemonic(
 ) -> Result<PrivateNode> {
     // Re-derive the same private key from the seed phrase
     let seed = Seed::new(&mnemonic, /* optional password */ "");
-    let exchange_keypair = SeededExchangeKey::from_bip39_seed(seed)?;
+    let exchange_keypair = keypair_from_bip39_seed(seed)?;
+    let public_key = exchange_keypair.get_public_key().to_encoded();
     let root_did = "did:key:zExample";
 
     // Re-load private node from forest
     let counter = recipient::find_latest_share_counter(
         0,
         1000,
-        &exchange_keypair.encode_public_key(),
+        public_key.as_bytes(),
         root_did,
         forest,
         store,
@@ -153,58 +155,19 @@ async fn regain_access_from_mnemonic(
     .await?
     .unwrap_or_default();
 
-    let name = sharer::create_share_name(
-        counter,
-        root_did,
-        &exchange_keypair.encode_public_key(),
-        forest,
-    );
+    let name = sharer::create_share_name(counter, root_did, public_key.as_bytes(), forest);
 
-    let node = recipient::receive_share(&name, &exchange_keypair, forest, store).await?;
+    let node =
+        recipient::receive_share(&name, &exchange_keypair, Utc::now(), forest, store).await?;
     let latest_node = node.search_latest(forest, store).await?;
     Ok(latest_node)
 }
 
 //--------------------------------------------------------------------------------------------------
-// Structs & Implementations
+// Helpers
 //--------------------------------------------------------------------------------------------------
 
-struct SeededExchangeKey(RsaPrivateKey);
-
-struct PublicExchangeKey(RsaPublicKey);
-
-impl SeededExchangeKey {
-    pub fn from_bip39_seed(seed: Seed) -> Result<Self> {
-        let seed_bytes: [u8; 32] = seed.as_bytes()[..32].try_into()?;
-        let rng = &mut ChaCha12Rng::from_seed(seed_bytes);
-        let private_key = RsaPrivateKey::new(rng, 2048)?;
-        Ok(Self(private_key))
-    }
-
-    pub fn encode_public_key(&self) -> Vec<u8> {
-        self.0.n().to_bytes_be()
-    }
-}
-
-impl PrivateKey for SeededExchangeKey {
-    async fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
-        let padding = Oaep::new::<Sha256>();
-        self.0.decrypt(padding, ciphertext).map_err(|e| anyhow!(e))
-    }
-}
-
-impl ExchangeKey for PublicExchangeKey {
-    async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let padding = Oaep::new::<Sha256>();
-        self.0
-            .encrypt(&mut ChaCha12Rng::from_entropy(), padding, data)
-            .map_err(|e| anyhow!(e))
-    }
-
-    async fn from_modulus(modulus: &[u8]) -> Result<Self> {
-        let n = BigUint::from_bytes_be(modulus);
-        let e = BigUint::from(PUBLIC_KEY_EXPONENT);
-
-        Ok(Self(rsa::RsaPublicKey::new(n, e).map_err(|e| anyhow!(e))?))
-    }
+fn keypair_from_bip39_seed(seed: Seed) -> Result<X25519PrivateKey> {
+    let seed_bytes: [u8; 32] = seed.as_bytes()[..32].try_into()?;
+    Ok(X25519PrivateKey::from_bytes(seed_bytes))
 }
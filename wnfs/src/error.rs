@@ -1,5 +1,6 @@
 //! WNFS Errors
 
+use crate::private::ExchangeKeyType;
 use semver::Version;
 use skip_ratchet::PreviousErr;
 use thiserror::Error;
@@ -51,6 +52,9 @@ pub enum FsError {
     #[error("Cannot merge or compare forests, incompatible accumulator setups")]
     IncompatibleAccumulatorSetups,
 
+    #[error("Forest was created with hasher {found:?}, but is loaded with {expected:?}")]
+    IncompatibleHasher { expected: String, found: String },
+
     #[error("Mismatch between PrivateNode name {0} and its mountpoint {0}")]
     MountPointAndDeserializedNameMismatch(String, String),
 
@@ -66,6 +70,41 @@ pub enum ShareError {
 
     #[error("Access key not found")]
     AccessKeyNotFound,
+
+    #[error("Share expired at {0}")]
+    ShareExpired(chrono::DateTime<chrono::Utc>),
+}
+
+/// Key recovery related errors.
+#[derive(Debug, Error)]
+pub enum RecoveryError {
+    #[error("Invalid threshold {threshold} for {shares} recovery shares")]
+    InvalidThreshold { threshold: u8, shares: u8 },
+
+    #[error("No recovery shares given")]
+    NoShares,
+
+    #[error("Need {threshold} distinct recovery shares, found {found}")]
+    NotEnoughShares { threshold: u8, found: usize },
+
+    #[error("Recovery shares don't belong to the same access key")]
+    InconsistentShares,
+
+    #[error("Recovery share not found")]
+    RecoveryShareNotFound,
+}
+
+/// Password-based key wrapping errors.
+#[derive(Debug, Error)]
+pub enum PasswordError {
+    #[error("Invalid key derivation parameters: {0}")]
+    InvalidKdfParams(String),
+
+    #[error("Unsupported password-wrapped access key: {0}")]
+    UnsupportedEnvelope(String),
+
+    #[error("Wrong password or corrupted password-wrapped access key")]
+    DecryptionFailed,
 }
 
 /// Symmetric encryption errors.
@@ -79,7 +118,6 @@ pub enum CryptError {
 }
 
 /// RSA related errors
-#[cfg(test)]
 #[derive(Debug, Error)]
 pub enum RsaError {
     #[error("Invalid public key: {0}")]
@@ -92,6 +130,25 @@ pub enum RsaError {
     DecryptionFailed(anyhow::Error),
 }
 
+/// Exchange key related errors.
+#[derive(Debug, Error)]
+pub enum ExchangeKeyError {
+    #[error("Unknown exchange key type")]
+    UnknownKeyType,
+
+    #[error("Unsupported exchange key type: {0:?}")]
+    UnsupportedKeyType(ExchangeKeyType),
+
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+
+    #[error("Encryption failed: {0}")]
+    EncryptionFailed(String),
+
+    #[error("Decryption failed")]
+    DecryptionFailed,
+}
+
 #[derive(Debug, Error)]
 pub enum VerificationError {
     #[error("Couldn't verify write for label {0}")]
@@ -106,4 +163,7 @@ pub enum VerificationError {
 pub enum AccessKeyError {
     #[error("Snapshot access keys cannot be used to derive private refs")]
     UnsupportedSnapshotPrivateRefDerive,
+
+    #[error("Node was shared as a snapshot, which only gives read access to a single revision")]
+    SnapshotOnly,
 }
@@ -1,12 +1,15 @@
 use super::{
-    encrypted::Encrypted, forest::traits::PrivateForest, link::PrivateLink,
-    PrivateDirectoryContentSerializable, PrivateFile, PrivateNode, PrivateNodeContentSerializable,
-    PrivateNodeHeader, PrivateRef, TemporalKey,
+    encrypted::Encrypted, forest::traits::PrivateForest, link::PrivateLink, DeferredStore,
+    KeyProvider, PrivateDirectoryContentSerializable, PrivateFile, PrivateNode,
+    PrivateNodeContentSerializable, PrivateNodeHeader, PrivateRef, TemporalKey,
+};
+use crate::{
+    error::FsError, is_readable_wnfs_version, traits::Id, utils, SearchResult, WNFS_VERSION,
 };
-use crate::{error::FsError, is_readable_wnfs_version, traits::Id, SearchResult, WNFS_VERSION};
 use anyhow::{bail, ensure, Result};
 use async_once_cell::OnceCell;
 use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt, TryStreamExt};
 use libipld_core::cid::Cid;
 use rand_core::CryptoRngCore;
 use std::{
@@ -1084,6 +1087,113 @@ impl PrivateDirectory {
         Ok(removed_node)
     }
 
+    /// Revokes access to the file or directory at the given path.
+    ///
+    /// Every node in the subtree gets a fresh inumber and ratchet, and externally stored
+    /// file content is re-encrypted under fresh keys. Links to previous revisions are
+    /// dropped, so none of the keys handed out before give access to the new revisions.
+    /// This directory is updated to point to the new names, and needs to be stored for
+    /// the change to persist.
+    ///
+    /// If `remove_old_labels` is set, the labels of the subtree's current revisions and
+    /// their content blocks are removed from the forest. Older revisions stay readable
+    /// to anyone who had access to them. Content blocks that files elsewhere in this
+    /// directory still use, like copies made with [`PrivateDirectory::cp`], are kept.
+    /// Finding those loads every node in this directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::{
+    ///         PrivateDirectory, PrivateNode,
+    ///         forest::{hamt::HamtForest, traits::PrivateForest},
+    ///     },
+    ///     common::MemoryBlockStore,
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::default();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///     let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+    ///     let path = ["shared".into(), "notes.txt".into()];
+    ///
+    ///     root_dir
+    ///         .write(&path, true, Utc::now(), b"secret".to_vec(), forest, store, rng)
+    ///         .await?;
+    ///     root_dir.as_node().store(forest, store, rng).await?;
+    ///
+    ///     let shared = root_dir.get_node(&["shared".into()], true, forest, store).await?;
+    ///     let shared_key = shared.unwrap().store(forest, store, rng).await?;
+    ///
+    ///     root_dir
+    ///         .revoke(&["shared".into()], true, true, forest, store, rng)
+    ///         .await?;
+    ///     root_dir.as_node().store(forest, store, rng).await?;
+    ///
+    ///     assert!(PrivateNode::load(&shared_key, forest, store, None).await.is_err());
+    ///     assert_eq!(root_dir.read(&path, true, forest, store).await?, b"secret");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub async fn revoke(
+        self: &mut Arc<Self>,
+        path_segments: &[String],
+        search_latest: bool,
+        remove_old_labels: bool,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        let (path, node_name) = crate::utils::split_last(path_segments)?;
+        let SearchResult::Found(dir) = self
+            .get_leaf_dir_mut(path, search_latest, forest, store)
+            .await?
+        else {
+            bail!(FsError::NotFound)
+        };
+
+        let mut node = match dir.content.entries.get(node_name) {
+            Some(link) => link
+                .resolve_node(forest, store, Some(dir.header.name.clone()))
+                .await?
+                .clone(),
+            None => bail!(FsError::NotFound),
+        };
+
+        let mut old_names = Vec::new();
+        let parent_name = dir.header.name.clone();
+        node.rotate_keys(&parent_name, &mut old_names, forest, store, rng)
+            .await?;
+
+        dir.content
+            .entries
+            .insert(node_name.clone(), PrivateLink::from(node));
+
+        if remove_old_labels {
+            let mut labels_in_use = BTreeSet::new();
+            self.as_node()
+                .collect_content_labels(&mut labels_in_use, forest, store)
+                .await?;
+
+            for name in old_names {
+                if !labels_in_use.contains(&forest.get_label(&name)) {
+                    forest.remove_encrypted(&name, store).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Attaches a node to the specified directory.
     ///
     /// Fixes up the subtree bare names to refer to the new parent.
@@ -1323,10 +1433,51 @@ impl PrivateDirectory {
             .into_private_ref(content_cid))
     }
 
+    /// Stores this PrivateDirectory and its unstored descendants in the BlockStore,
+    /// but returns the insertions into the PrivateForest instead of applying them.
+    ///
+    /// Up to `concurrency` children are stored concurrently.
+    pub(crate) async fn store_deferred(
+        &self,
+        concurrency: usize,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<(PrivateRef, DeferredStore)> {
+        let header_cid = self.header.store(store, forest).await?;
+        let temporal_key = self.header.derive_temporal_key();
+        let name_with_revision = self.header.get_revision_name();
+
+        let mut deferred = DeferredStore::default();
+        let content_cid = self
+            .content
+            .store_deferred(
+                header_cid,
+                &temporal_key,
+                concurrency,
+                forest,
+                store,
+                rng,
+                &mut deferred,
+            )
+            .await?;
+
+        deferred
+            .insertions
+            .push((name_with_revision, [header_cid, content_cid]));
+
+        let private_ref = self
+            .header
+            .derive_revision_ref(forest)
+            .into_private_ref(content_cid);
+
+        Ok((private_ref, deferred))
+    }
+
     /// Creates a new [`PrivateDirectory`] from a [`PrivateDirectoryContentSerializable`].
     pub(crate) async fn from_serializable(
         serializable: PrivateDirectoryContentSerializable,
-        temporal_key: &TemporalKey,
+        temporal_key: &impl KeyProvider,
         cid: Cid,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
@@ -1339,7 +1490,7 @@ impl PrivateDirectory {
         let mut entries_decrypted = BTreeMap::new();
         for (name, private_ref_serializable) in serializable.entries {
             let private_ref =
-                PrivateRef::from_serializable(private_ref_serializable, temporal_key)?;
+                PrivateRef::from_serializable(private_ref_serializable, temporal_key).await?;
             entries_decrypted.insert(name, PrivateLink::from_ref(private_ref));
         }
 
@@ -1502,11 +1653,24 @@ impl PrivateDirectoryContent {
         let mut entries = BTreeMap::new();
 
         for (name, private_link) in self.entries.iter() {
-            let private_ref_serializable = private_link
-                .resolve_ref(forest, store, rng)
-                .await?
-                .to_serializable(temporal_key)?;
-            entries.insert(name.clone(), private_ref_serializable);
+            let private_ref = private_link.resolve_ref(forest, store, rng).await?;
+            entries.insert(name.clone(), private_ref);
+        }
+
+        self.to_dag_cbor_with_refs(temporal_key, header_cid, entries)
+    }
+
+    /// Serializes the directory to dag-cbor, given already resolved references to its entries.
+    fn to_dag_cbor_with_refs(
+        &self,
+        temporal_key: &TemporalKey,
+        header_cid: Cid,
+        entry_refs: BTreeMap<String, PrivateRef>,
+    ) -> Result<Vec<u8>> {
+        let mut entries = BTreeMap::new();
+
+        for (name, private_ref) in entry_refs {
+            entries.insert(name, private_ref.to_serializable(temporal_key)?);
         }
 
         Ok(serde_ipld_dagcbor::to_vec(
@@ -1558,6 +1722,64 @@ impl PrivateDirectoryContent {
             })
             .await?)
     }
+
+    /// Like `store`, but stores up to `concurrency` entries concurrently, each
+    /// with their own random number generator derived from `rng`.
+    ///
+    /// Instead of inserting into the forest, the necessary insertions for this
+    /// directory's descendants are added to `deferred`. Unlike `store`, this
+    /// doesn't cache the resulting CID, as a cached CID means the descendants
+    /// are already in the forest. See [`DeferredStore::commit`].
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn store_deferred(
+        &self,
+        header_cid: Cid,
+        temporal_key: &TemporalKey,
+        concurrency: usize,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+        deferred: &mut DeferredStore,
+    ) -> Result<Cid> {
+        if let Some(cid) = self.persisted_as.get() {
+            return Ok(*cid);
+        }
+
+        let entry_rngs = self
+            .entries
+            .keys()
+            .map(|_| utils::split_rng(rng))
+            .collect::<Vec<_>>();
+
+        let entry_futures = self
+            .entries
+            .iter()
+            .zip(entry_rngs)
+            .map(|((name, private_link), mut entry_rng)| async move {
+                let (private_ref, entry_deferred) = private_link
+                    .resolve_ref_deferred(concurrency, forest, store, &mut entry_rng)
+                    .await?;
+                Ok::<_, anyhow::Error>((name.clone(), private_ref, entry_deferred))
+            })
+            .collect::<Vec<_>>();
+
+        let resolved: Vec<_> = stream::iter(entry_futures)
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await?;
+
+        let mut entry_refs = BTreeMap::new();
+        for (name, private_ref, entry_deferred) in resolved {
+            deferred.extend(entry_deferred);
+            entry_refs.insert(name, private_ref);
+        }
+
+        let snapshot_key = temporal_key.derive_snapshot_key();
+        let bytes = self.to_dag_cbor_with_refs(temporal_key, header_cid, entry_refs)?;
+        let block = snapshot_key.encrypt(&bytes, rng)?;
+
+        Ok(store.put_block(block, CODEC_RAW).await?)
+    }
 }
 
 impl PartialEq for PrivateDirectoryContent {
@@ -1597,7 +1819,7 @@ impl Id for PrivateDirectory {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::private::forest::hamt::HamtForest;
+    use crate::private::{forest::hamt::HamtForest, FileContent};
     use rand_chacha::ChaCha12Rng;
     use rand_core::SeedableRng;
     use test_log::test;
@@ -2266,6 +2488,132 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[async_std::test]
+    async fn revoke_rotates_keys_and_reencrypts_content() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let path = &["docs".into(), "notes.txt".into()];
+        let content = b"Hello, World!".to_vec();
+
+        root_dir
+            .write(path, true, Utc::now(), content.clone(), forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        let old_docs = root_dir.get_node(&path[..1], true, forest, store).await?;
+        let old_docs_key = old_docs.unwrap().store(forest, store, rng).await?;
+        let old_file = root_dir.get_node(path, true, forest, store).await?;
+        let FileContent::External(old_content) = &old_file.unwrap().as_file()?.content.content
+        else {
+            panic!("Expected external content")
+        };
+
+        root_dir
+            .revoke(&path[..1], true, false, forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        assert_eq!(root_dir.read(path, true, forest, store).await?, content);
+
+        let new_file = root_dir.get_node(path, true, forest, store).await?;
+        let new_file = new_file.unwrap().as_file()?;
+        let FileContent::External(new_content) = &new_file.content.content else {
+            panic!("Expected external content")
+        };
+        assert_ne!(new_content.key, old_content.key);
+        assert!(new_file.content.previous.is_empty());
+
+        // The old revision is still around, but doesn't lead to the new one.
+        let old_docs = PrivateNode::load(&old_docs_key, forest, store, None).await?;
+        let latest_old_docs = old_docs.search_latest(forest, store).await?;
+        assert_eq!(latest_old_docs, old_docs);
+
+        let new_docs = root_dir.get_node(&path[..1], true, forest, store).await?;
+        assert_ne!(
+            new_docs.unwrap().get_header().get_name(),
+            old_docs.get_header().get_name()
+        );
+
+        for name in old_content.get_block_names() {
+            assert!(forest.get_encrypted(&name, store).await?.is_some());
+        }
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn revoke_can_remove_old_labels() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let path = &["docs".into(), "notes.txt".into()];
+        let content = b"Hello, World!".to_vec();
+
+        root_dir
+            .write(path, true, Utc::now(), content.clone(), forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        let old_file = root_dir.get_node(path, true, forest, store).await?.unwrap();
+        let old_file_key = old_file.store(forest, store, rng).await?;
+        let FileContent::External(old_content) = &old_file.as_file()?.content.content else {
+            panic!("Expected external content")
+        };
+
+        root_dir
+            .revoke(&path[..1], true, true, forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        assert_eq!(root_dir.read(path, true, forest, store).await?, content);
+        assert!(PrivateNode::load(&old_file_key, forest, store, None)
+            .await
+            .is_err());
+        for name in old_content.get_block_names() {
+            assert!(forest.get_encrypted(&name, store).await?.is_none());
+        }
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn revoke_keeps_content_of_copies() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let path = &["docs".into(), "notes.txt".into()];
+        let copy_path = &["backup".into(), "notes.txt".into()];
+        let content = b"Hello, World!".to_vec();
+
+        root_dir
+            .write(path, true, Utc::now(), content.clone(), forest, store, rng)
+            .await?;
+        root_dir
+            .mkdir(&copy_path[..1], true, Utc::now(), forest, store, rng)
+            .await?;
+        root_dir
+            .cp(path, copy_path, true, Utc::now(), forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        root_dir
+            .revoke(&path[..1], true, true, forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        assert_eq!(
+            root_dir.read(copy_path, true, forest, store).await?,
+            content
+        );
+        assert_eq!(root_dir.read(path, true, forest, store).await?, content);
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn write_doesnt_generate_previous_link() {
         let rng = &mut ChaCha12Rng::from_entropy();
@@ -2317,6 +2665,149 @@ mod tests {
         assert_eq!(new_dir.content.previous.len(), 1);
     }
 
+    #[async_std::test]
+    async fn store_concurrent_is_deterministic() -> TestResult {
+        use chrono::TimeZone;
+        use wnfs_common::Storable;
+
+        let time = Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap();
+        let paths = [
+            vec!["text.txt".into()],
+            vec!["music".into(), "jazz".into(), "a.mp3".into()],
+            vec!["music".into(), "rock".into(), "b.mp3".into()],
+            vec!["videos".into(), "movies".into(), "anime".into()],
+        ];
+
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let rng = &mut ChaCha12Rng::seed_from_u64(0);
+            let store = &MemoryBlockStore::new();
+            let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+            let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), time, rng);
+
+            for path in paths.iter() {
+                root_dir
+                    .write(path, true, time, b"Hello".to_vec(), forest, store, rng)
+                    .await?;
+            }
+
+            let access_key = root_dir
+                .as_node()
+                .store_concurrent(2, forest, store, rng)
+                .await?;
+
+            let node = PrivateNode::load(&access_key, forest, store, None).await?;
+            for path in paths.iter() {
+                let content = node.as_dir()?.read(path, true, forest, store).await?;
+                assert_eq!(content, b"Hello");
+            }
+
+            results.push((access_key, forest.store(store).await?));
+        }
+
+        assert_eq!(results[0], results[1]);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn store_concurrent_can_be_retried_after_failing() -> TestResult {
+        use bytes::Bytes;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wnfs_common::{utils::CondSend, BlockStoreError};
+
+        /// Fails the `fail_at`-th put.
+        #[derive(Default)]
+        struct FlakyBlockStore {
+            inner: MemoryBlockStore,
+            puts: AtomicUsize,
+            fail_at: Option<usize>,
+        }
+
+        impl BlockStore for FlakyBlockStore {
+            async fn get_block(&self, cid: &Cid) -> Result<Bytes, BlockStoreError> {
+                self.inner.get_block(cid).await
+            }
+
+            async fn put_block_keyed(
+                &self,
+                cid: Cid,
+                bytes: impl Into<Bytes> + CondSend,
+            ) -> Result<(), BlockStoreError> {
+                if Some(self.puts.fetch_add(1, Ordering::SeqCst)) == self.fail_at {
+                    return Err(anyhow::anyhow!("Flaky put").into());
+                }
+
+                self.inner.put_block_keyed(cid, bytes).await
+            }
+
+            async fn has_block(&self, cid: &Cid) -> Result<bool, BlockStoreError> {
+                self.inner.has_block(cid).await
+            }
+        }
+
+        let path = &["music".into(), "jazz".into(), "a.mp3".into()];
+
+        // Count the puts of a successful store, so we can fail the last one,
+        // the root's content block, after all descendants were stored.
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &FlakyBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        root_dir
+            .write(
+                path,
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await?;
+        root_dir
+            .as_node()
+            .store_concurrent(1, forest, store, rng)
+            .await?;
+        let fail_at = store.puts.load(Ordering::SeqCst) - 1;
+
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &FlakyBlockStore {
+            fail_at: Some(fail_at),
+            ..Default::default()
+        };
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        root_dir
+            .write(
+                path,
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await?;
+
+        let result = root_dir
+            .as_node()
+            .store_concurrent(1, forest, store, rng)
+            .await;
+        assert!(result.is_err());
+
+        let access_key = root_dir
+            .as_node()
+            .store_concurrent(1, forest, store, rng)
+            .await?;
+
+        let node = PrivateNode::load(&access_key, forest, store, None).await?;
+        let content = node.as_dir()?.read(path, true, forest, store).await?;
+        assert_eq!(content, b"Hello");
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn search_latest_also_searches_the_root() -> Result<()> {
         let rng = &mut ChaCha12Rng::from_entropy();
@@ -1,7 +1,8 @@
 use super::{
-    encrypted::Encrypted, forest::traits::PrivateForest, PrivateFileContentSerializable,
-    PrivateNode, PrivateNodeContentSerializable, PrivateNodeHeader, PrivateRef, SnapshotKey,
-    TemporalKey, AUTHENTICATION_TAG_SIZE, BLOCK_SEGMENT_DSI, HIDING_SEGMENT_DSI, NONCE_SIZE,
+    encrypted::Encrypted, forest::traits::PrivateForest, DeferredInsertion, KeyProvider,
+    PrivateFileContentSerializable, PrivateNode, PrivateNodeContentSerializable, PrivateNodeHeader,
+    PrivateRef, SnapshotKey, AUTHENTICATION_TAG_SIZE, BLOCK_SEGMENT_DSI, HIDING_SEGMENT_DSI,
+    NONCE_SIZE,
 };
 use crate::{
     error::FsError, is_readable_wnfs_version, traits::Id, utils::OnceCellDebug, WNFS_VERSION,
@@ -10,7 +11,7 @@ use anyhow::{bail, Result};
 use async_once_cell::OnceCell;
 use async_stream::try_stream;
 use chrono::{DateTime, Utc};
-use futures::{future, AsyncRead, Stream, StreamExt, TryStreamExt};
+use futures::{future, stream, AsyncRead, Stream, StreamExt, TryStreamExt};
 use libipld_core::{
     cid::Cid,
     ipld::Ipld,
@@ -24,6 +25,7 @@ use wnfs_common::{
     BlockStore, Metadata, CODEC_RAW, MAX_BLOCK_SIZE,
 };
 use wnfs_nameaccumulator::{Name, NameAccumulator, NameSegment};
+use zeroize::Zeroizing;
 
 //--------------------------------------------------------------------------------------------------
 // Constants
@@ -505,6 +507,66 @@ impl PrivateFile {
         }
     }
 
+    /// Like `stream_content`, but fetches & decrypts up to `read_ahead`
+    /// blocks concurrently, while still yielding them in order.
+    ///
+    /// Use this when reading from blockstores with high latency.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::{PrivateFile, forest::{hamt::HamtForest, traits::PrivateForest}},
+    ///     common::{MemoryBlockStore, utils::get_random_bytes},
+    /// };
+    /// use futures::TryStreamExt;
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///
+    ///     let content = get_random_bytes::<100>(rng).to_vec();
+    ///     let file = PrivateFile::with_content(
+    ///         &forest.empty_name(),
+    ///         Utc::now(),
+    ///         content.clone(),
+    ///         forest,
+    ///         store,
+    ///         rng,
+    ///     )
+    ///     .await?;
+    ///
+    ///     let chunks: Vec<Vec<u8>> = file
+    ///         .stream_content_with_read_ahead(0, 8, forest, store)
+    ///         .try_collect()
+    ///         .await?;
+    ///
+    ///     assert_eq!(content, chunks.concat());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream_content_with_read_ahead<'a>(
+        &'a self,
+        block_index: u64,
+        read_ahead: usize,
+        forest: &'a impl PrivateForest,
+        store: &'a impl BlockStore,
+    ) -> BoxStream<'a, Result<Vec<u8>>> {
+        match &self.content.content {
+            FileContent::External(content) => {
+                Box::pin(content.stream_with_read_ahead(block_index, read_ahead, forest, store))
+            }
+            FileContent::Inline { .. } => self.stream_content(block_index, forest, store),
+        }
+    }
+
     /// Read the contents of this file.
     /// You can provide a byte offset from which to start reading,
     /// and you can provide a maximum amount of bytes you want to read.
@@ -572,6 +634,28 @@ impl PrivateFile {
         }
     }
 
+    /// Like `read_at`, but the returned plaintext is wiped from memory when it's dropped.
+    ///
+    /// Decrypted content blocks are wiped as well, also if reading fails part way.
+    pub async fn read_at_zeroizing<'a>(
+        &'a self,
+        byte_offset: u64,
+        len_limit: Option<usize>,
+        forest: &'a impl PrivateForest,
+        store: &'a impl BlockStore,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        match &self.content.content {
+            FileContent::Inline { .. } => Ok(Zeroizing::new(
+                self.read_at(byte_offset, len_limit, forest, store).await?,
+            )),
+            FileContent::External(external) => {
+                external
+                    .read_at_zeroizing(byte_offset, len_limit, forest, store)
+                    .await
+            }
+        }
+    }
+
     /// Gets the metadata of the file
     pub fn get_metadata(&self) -> &Metadata {
         &self.content.metadata
@@ -678,6 +762,15 @@ impl PrivateFile {
         self.read_at(0, None, forest, store).await
     }
 
+    /// Like `get_content`, but the returned plaintext is wiped from memory when it's dropped.
+    pub async fn get_content_zeroizing(
+        &self,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        self.read_at_zeroizing(0, None, forest, store).await
+    }
+
     /// Sets the content of a file.
     pub async fn set_content(
         &mut self,
@@ -824,6 +917,21 @@ impl PrivateFile {
         store: &impl BlockStore,
         rng: &mut impl CryptoRngCore,
     ) -> Result<PrivateRef> {
+        let (private_ref, insertions) = self.store_deferred(forest, store, rng).await?;
+
+        forest.put_encrypted_many(insertions, store).await?;
+
+        Ok(private_ref)
+    }
+
+    /// Stores this PrivateFile in the BlockStore, but returns the insertions
+    /// into the PrivateForest instead of applying them.
+    pub(crate) async fn store_deferred(
+        &self,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<(PrivateRef, Vec<DeferredInsertion>)> {
         let header_cid = self.header.store(store, forest).await?;
         let temporal_key = self.header.derive_temporal_key();
         let snapshot_key = temporal_key.derive_snapshot_key();
@@ -834,20 +942,21 @@ impl PrivateFile {
             .store(header_cid, &snapshot_key, store, rng)
             .await?;
 
-        forest
-            .put_encrypted(&name_with_revision, [header_cid, content_cid], store)
-            .await?;
-
-        Ok(self
+        let private_ref = self
             .header
             .derive_revision_ref(forest)
-            .into_private_ref(content_cid))
+            .into_private_ref(content_cid);
+
+        Ok((
+            private_ref,
+            vec![(name_with_revision, [header_cid, content_cid])],
+        ))
     }
 
     /// Creates a new [`PrivateFile`] from a [`PrivateFileContentSerializable`].
     pub(crate) async fn from_serializable(
         serializable: PrivateFileContentSerializable,
-        temporal_key: &TemporalKey,
+        temporal_key: &impl KeyProvider,
         cid: Cid,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
@@ -857,6 +966,13 @@ impl PrivateFile {
             bail!(FsError::UnexpectedVersion(serializable.version));
         }
 
+        // Content block labels are derived from the base name, so it needs to be a group element
+        if let FileContent::External(external) = &serializable.content {
+            external
+                .base_name
+                .validate(forest.get_accumulator_setup())?;
+        }
+
         let content = PrivateFileContent {
             persisted_as: OnceCell::new_with(cid),
             previous: serializable.previous.into_iter().collect(),
@@ -1108,18 +1224,49 @@ impl PrivateForestContent {
         forest: &'a impl PrivateForest,
         store: &'a impl BlockStore,
     ) -> impl Stream<Item = Result<Vec<u8>>> + 'a {
-        try_stream! {
-            for name in Self::generate_shard_labels(
-                &self.key,
-                block_index,
-                self.block_count,
-                &Name::new(self.base_name.clone(), []),
-            ) {
+        self.stream_with_read_ahead(block_index, 1, forest, store)
+    }
+
+    /// Like `stream`, but fetches & decrypts up to `read_ahead` blocks concurrently.
+    ///
+    /// Blocks are still yielded in order. This is useful for blockstores with
+    /// high latency. A `read_ahead` of `0` is treated as `1`.
+    pub fn stream_with_read_ahead<'a>(
+        &'a self,
+        block_index: u64,
+        read_ahead: usize,
+        forest: &'a impl PrivateForest,
+        store: &'a impl BlockStore,
+    ) -> impl Stream<Item = Result<Vec<u8>>> + 'a {
+        self.stream_zeroizing(block_index, read_ahead, forest, store)
+            .map_ok(|mut block| std::mem::take(&mut *block))
+    }
+
+    /// Like `stream_with_read_ahead`, but yields blocks that are wiped from memory
+    /// when they're dropped, including blocks that were read ahead but not consumed.
+    fn stream_zeroizing<'a>(
+        &'a self,
+        block_index: u64,
+        read_ahead: usize,
+        forest: &'a impl PrivateForest,
+        store: &'a impl BlockStore,
+    ) -> impl Stream<Item = Result<Zeroizing<Vec<u8>>>> + 'a {
+        let base_name = Name::new(self.base_name.clone(), []);
+        stream::iter(block_index..self.block_count)
+            .map(move |index| {
+                let name = Self::create_block_name(&self.key, index, &base_name);
                 // TODO(matheus23): take block_content_size into account
-                let bytes = Self::decrypt_block(&self.key, &name, forest, store).await?;
-                yield bytes
-            }
-        }
+                async move { Self::decrypt_block(&self.key, &name, forest, store).await }
+            })
+            .buffered(read_ahead.max(1))
+            // Blocks that were read ahead aren't yielded after a block failed to load.
+            .scan(false, |failed, block| {
+                if *failed {
+                    return future::ready(None);
+                }
+                *failed = block.is_err();
+                future::ready(Some(block))
+            })
     }
 
     /// Reads a number of bytes starting from a given offset.
@@ -1130,6 +1277,22 @@ impl PrivateForestContent {
         forest: &'a impl PrivateForest,
         store: &'a impl BlockStore,
     ) -> Result<Vec<u8>> {
+        let mut bytes = self
+            .read_at_zeroizing(byte_offset, len_limit, forest, store)
+            .await?;
+        Ok(std::mem::take(&mut *bytes))
+    }
+
+    /// Like `read_at`, but the returned plaintext is wiped from memory when it's dropped.
+    ///
+    /// Decrypted blocks are wiped as well, also if reading fails part way.
+    pub async fn read_at_zeroizing<'a>(
+        &'a self,
+        byte_offset: u64,
+        len_limit: Option<usize>,
+        forest: &'a impl PrivateForest,
+        store: &'a impl BlockStore,
+    ) -> Result<Zeroizing<Vec<u8>>> {
         let block_content_size = MAX_BLOCK_CONTENT_SIZE as u64;
         let mut chunk_size_upper_bound = (self.get_size_upper_bound() - byte_offset) as usize;
 
@@ -1138,14 +1301,16 @@ impl PrivateForestContent {
         }
 
         if chunk_size_upper_bound == 0 {
-            return Ok(vec![]);
+            return Ok(Zeroizing::new(vec![]));
         }
 
         let first_block = byte_offset / block_content_size;
         let last_block = len_limit.map(|len| (byte_offset + len as u64) / block_content_size);
 
-        let mut bytes = Vec::with_capacity(chunk_size_upper_bound);
-        let mut content_stream = Box::pin(self.stream(first_block, forest, store)).enumerate();
+        // Allocated up front, so growing it doesn't leave copies of the plaintext behind.
+        let mut bytes = Zeroizing::new(Vec::with_capacity(chunk_size_upper_bound));
+        let mut content_stream =
+            Box::pin(self.stream_zeroizing(first_block, 1, forest, store)).enumerate();
 
         while let Some((i, chunk)) = content_stream.next().await {
             let chunk = chunk?;
@@ -1179,7 +1344,7 @@ impl PrivateForestContent {
         store: &impl BlockStore,
     ) -> Result<Vec<u8>> {
         let mut content = Vec::with_capacity(Self::get_size_upper_bound(self) as usize);
-        self.stream(0, forest, store)
+        self.stream_zeroizing(0, 1, forest, store)
             .try_for_each(|chunk| {
                 content.extend_from_slice(&chunk);
                 future::ready(Ok(()))
@@ -1206,6 +1371,45 @@ impl PrivateForestContent {
         Ok(size_without_last_block + size_last_block)
     }
 
+    /// Re-encrypts all content blocks under a fresh key and stores them under
+    /// a new base name derived from given file name.
+    ///
+    /// The old blocks are left in the forest. Use `get_block_names` to find them.
+    pub(crate) async fn rotate_key(
+        &self,
+        file_name: &Name,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self> {
+        let (key, base_name) = Self::prepare_key_and_base_name(file_name, rng);
+        let old_names = self.get_block_names();
+        let new_names = Self::generate_shard_labels(&key, 0, self.block_count, &base_name);
+
+        for (old_name, new_name) in old_names.iter().zip(new_names) {
+            let bytes = Self::decrypt_block(&self.key, old_name, forest, store).await?;
+            let enc_bytes = key.encrypt(&bytes, rng)?;
+            let content_cid = store.put_block(enc_bytes, CODEC_RAW).await?;
+
+            forest
+                .put_encrypted(&new_name, Some(content_cid), store)
+                .await?;
+        }
+
+        Ok(PrivateForestContent {
+            key,
+            base_name: forest.get_accumulated_name(&base_name),
+            block_count: self.block_count,
+            block_content_size: self.block_content_size,
+        })
+    }
+
+    /// Gets the names of the labels the content blocks are stored at.
+    pub(crate) fn get_block_names(&self) -> Vec<Name> {
+        let base_name = Name::new(self.base_name.clone(), []);
+        Self::generate_shard_labels(&self.key, 0, self.block_count, &base_name).collect()
+    }
+
     /// Generates the labels for all of the content shard blocks.
     pub(crate) fn generate_shard_labels<'a>(
         key: &'a SnapshotKey,
@@ -1229,7 +1433,7 @@ impl PrivateForestContent {
         name: &Name,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
-    ) -> Result<Vec<u8>> {
+    ) -> Result<Zeroizing<Vec<u8>>> {
         let cid = forest
             .get_encrypted(name, store)
             .await?
@@ -1241,7 +1445,7 @@ impl PrivateForestContent {
         let enc_bytes = store.get_block(cid).await?;
         let bytes = key.decrypt(&enc_bytes)?;
 
-        Ok(bytes)
+        Ok(Zeroizing::new(bytes))
     }
 
     fn create_block_name(key: &SnapshotKey, index: u64, base_name: &Name) -> Name {
@@ -1375,6 +1579,120 @@ mod tests {
         );
     }
 
+    #[async_std::test]
+    async fn can_read_content_into_zeroizing_buffers() {
+        let mut content = vec![0u8; MAX_BLOCK_CONTENT_SIZE * 2 + 100];
+        rand::thread_rng().fill(&mut content[..]);
+
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let file = PrivateFile::with_content(
+            &forest.empty_name(),
+            Utc::now(),
+            content.clone(),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        let all_content = file.get_content_zeroizing(forest, store).await.unwrap();
+        assert_eq!(*all_content, content);
+
+        let offset = MAX_BLOCK_CONTENT_SIZE - 10;
+        let partial = file
+            .read_at_zeroizing(offset as u64, Some(20), forest, store)
+            .await
+            .unwrap();
+        assert_eq!(*partial, content[offset..offset + 20]);
+    }
+
+    #[async_std::test]
+    async fn can_stream_content_with_read_ahead() {
+        let mut content = vec![0u8; MAX_BLOCK_CONTENT_SIZE * 5 + 100];
+        rand::thread_rng().fill(&mut content[..]);
+
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let file = PrivateFile::with_content(
+            &forest.empty_name(),
+            Utc::now(),
+            content.clone(),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        for read_ahead in [0, 1, 3, 10] {
+            let chunks: Vec<Vec<u8>> = file
+                .stream_content_with_read_ahead(1, read_ahead, forest, store)
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(chunks.len(), 5);
+            assert_eq!(chunks.concat(), content[MAX_BLOCK_CONTENT_SIZE..]);
+        }
+    }
+
+    #[async_std::test]
+    async fn stream_with_read_ahead_stops_after_error() {
+        let mut content = vec![0u8; MAX_BLOCK_CONTENT_SIZE * 3];
+        rand::thread_rng().fill(&mut content[..]);
+
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let file = PrivateFile::with_content(
+            &forest.empty_name(),
+            Utc::now(),
+            content.clone(),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        let FileContent::External(external) = &file.content.content else {
+            panic!("expected external content");
+        };
+
+        // Corrupt the second block
+        let names = external.get_block_names();
+        let cids = forest
+            .get_encrypted(&names[1], store)
+            .await
+            .unwrap()
+            .unwrap();
+        let cid = cids.iter().next().unwrap();
+        let mut bytes = store.get_block(cid).await.unwrap().to_vec();
+        *bytes.last_mut().unwrap() ^= 1;
+        store.put_block_keyed(*cid, bytes).await.unwrap();
+
+        for read_ahead in [1, 3] {
+            let results = file
+                .stream_content_with_read_ahead(0, read_ahead, forest, store)
+                .collect::<Vec<_>>()
+                .await;
+
+            assert_eq!(results.len(), 2);
+            assert_eq!(
+                results[0].as_ref().unwrap(),
+                &content[..MAX_BLOCK_CONTENT_SIZE]
+            );
+            assert!(results[1].is_err());
+        }
+    }
+
     #[async_std::test]
     async fn can_construct_file_from_stream() {
         let disk_file = File::open("./test/fixtures/Clara Schumann, Scherzo no. 2, Op. 14.mp3")
@@ -1,20 +1,20 @@
 use super::traits::PrivateForest;
 use crate::error::FsError;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use libipld_core::cid::Cid;
 use quick_cache::sync::Cache;
 use rand_core::CryptoRngCore;
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use wnfs_common::{
     impl_storable_from_serde,
-    utils::{Arc, CondSend},
+    utils::{Arc, CondSend, CondSync},
     BlockStore, HashOutput, Link, Storable,
 };
 use wnfs_hamt::{
-    constants::HAMT_VERSION, merge, serializable::NodeSerializable, Hamt, Hasher, KeyValueChange,
-    Node, Pair,
+    constants::HAMT_VERSION, merge, serializable::NodeSerializable, Hamt, HamtProof,
+    KeyValueChange, NamedHasher, Node, Pair,
 };
 use wnfs_nameaccumulator::{AccumulatorSetup, ElementsProof, Name, NameAccumulator};
 
@@ -45,9 +45,12 @@ const NAME_CACHE_CAPACITY: usize = 2_000_000 / APPROX_CACHE_ENTRY_SIZE;
 ///
 /// println!("{:?}", forest);
 /// ```
+///
+/// The forest is generic over the hash function `H` used to derive labels from name accumulators.
+/// Its identifier is stored alongside the forest, so loading a forest with a different hasher fails.
 #[derive(Debug, Clone)]
-pub struct HamtForest {
-    hamt: Hamt<NameAccumulator, Ciphertexts, blake3::Hasher>,
+pub struct HamtForest<H: NamedHasher + CondSync = blake3::Hasher> {
+    hamt: Hamt<NameAccumulator, Ciphertexts, H>,
     accumulator: AccumulatorSetup,
     name_cache: Arc<Cache<Name, (NameAccumulator, ElementsProof)>>,
 }
@@ -58,6 +61,8 @@ pub struct HamtForestSerializable {
     pub(crate) version: Version,
     pub(crate) structure: String,
     pub(crate) accumulator: AccumulatorSetup,
+    #[serde(default = "default_hasher", skip_serializing_if = "is_default_hasher")]
+    pub(crate) hasher: String,
 }
 
 /// Links to ciphertexts
@@ -70,14 +75,21 @@ impl_storable_from_serde! { Ciphertexts }
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
+// Forests using blake3 omit the hasher, so their encoding stays the same as before
+// the hasher was recorded.
+
+fn default_hasher() -> String {
+    blake3::Hasher::NAME.to_string()
+}
+
+fn is_default_hasher(hasher: &String) -> bool {
+    hasher == blake3::Hasher::NAME
+}
+
 impl HamtForest {
     /// Create a new, empty hamt forest with given pre-run accumulator setup
     pub fn new(setup: AccumulatorSetup) -> Self {
-        Self {
-            hamt: Hamt::new(),
-            accumulator: setup,
-            name_cache: Arc::new(Cache::new(NAME_CACHE_CAPACITY)),
-        }
+        Self::with_hasher(setup)
     }
 
     /// Create a new, empty hamt forest with given pre-run accumulator setup wrapped in an `Arc`.
@@ -124,6 +136,80 @@ impl HamtForest {
         Arc::new(Self::new_trusted(rng))
     }
 
+    /// Create a new, empty hamt forest with an accumulator setup in a
+    /// class group, which doesn't need a trusted setup.
+    ///
+    /// It uses a precomputed 2040-bit discriminant that's derived from a
+    /// public seed. Accumulator operations in class groups are considerably
+    /// slower than in the RSA group.
+    pub fn new_class_group_2040(rng: &mut impl CryptoRngCore) -> Self {
+        Self::new(AccumulatorSetup::from_class_group_2040(rng))
+    }
+
+    /// Creates an `Arc` of a new, empty hamt forest with an accumulator setup
+    /// in a class group.
+    pub fn new_class_group_2040_rc(rng: &mut impl CryptoRngCore) -> Arc<Self> {
+        Arc::new(Self::new_class_group_2040(rng))
+    }
+
+    /// Loads a hamt forest that derives labels with the default hasher from the store.
+    ///
+    /// This lets the hasher be inferred in `HamtForest::load`. Load forests using
+    /// other hashers via [`Storable::load`], e.g. `HamtForest::<MyHasher>::load`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use wnfs::{
+    ///     common::{MemoryBlockStore, Storable},
+    ///     private::forest::{hamt::HamtForest, traits::PrivateForest},
+    /// };
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let forest = HamtForest::new_rsa_2048(&mut ChaCha12Rng::from_entropy());
+    ///     let cid = forest.store(store).await?;
+    ///
+    ///     let loaded = HamtForest::load(&cid, store).await?;
+    ///
+    ///     assert_eq!(loaded.get_accumulator_setup(), forest.get_accumulator_setup());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn load(cid: &Cid, store: &impl BlockStore) -> Result<Self> {
+        <Self as Storable>::load(cid, store).await
+    }
+}
+
+impl<H: NamedHasher + CondSync> HamtForest<H> {
+    /// Create a new, empty hamt forest that derives labels with the hasher `H`,
+    /// using the given pre-run accumulator setup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::private::forest::hamt::HamtForest;
+    /// use wnfs::nameaccumulator::AccumulatorSetup;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    ///
+    /// let setup = AccumulatorSetup::from_rsa_2048(&mut ChaCha12Rng::from_entropy());
+    /// let forest = HamtForest::<blake3::Hasher>::with_hasher(setup);
+    ///
+    /// println!("{:?}", forest);
+    /// ```
+    pub fn with_hasher(setup: AccumulatorSetup) -> Self {
+        Self {
+            hamt: Hamt::new(),
+            accumulator: setup,
+            name_cache: Arc::new(Cache::new(NAME_CACHE_CAPACITY)),
+        }
+    }
+
     /// Gets the difference in changes between two forests.
     #[inline]
     pub async fn diff(
@@ -228,9 +314,107 @@ impl HamtForest {
             name_cache,
         })
     }
+
+    /// Creates a proof that values are, or aren't, stored at the label of the given name
+    /// in the forest stored at `forest_cid`.
+    ///
+    /// See [`HamtForest::prove_by_hash`].
+    pub async fn prove(
+        forest_cid: &Cid,
+        name: &Name,
+        store: &impl BlockStore,
+    ) -> Result<HamtProof> {
+        let forest = <Self as Storable>::load(forest_cid, store).await?;
+        forest
+            .prove_stored(forest_cid, &forest.get_label(name), store)
+            .await
+    }
+
+    /// Creates a proof that values are, or aren't, stored at the given label in the forest
+    /// stored at `forest_cid`.
+    ///
+    /// The proof contains the forest's block and the blocks of all HAMT nodes on the label's
+    /// hash path. Anyone who knows the CID of the forest can check it using
+    /// [`HamtForest::verify_proof`], without access to the rest of the forest.
+    /// Blocks are only read from the store, so the forest needs to be stored beforehand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     common::{BlockStore, MemoryBlockStore, Storable, CODEC_RAW},
+    ///     nameaccumulator::NameSegment,
+    ///     private::forest::{hamt::HamtForest, traits::PrivateForest},
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///
+    ///     let name = forest.empty_name().with_segments_added(Some(NameSegment::new(rng)));
+    ///     let cid = store.put_block(vec![1, 2, 3], CODEC_RAW).await?;
+    ///     forest.put_encrypted(&name, Some(cid), store).await?;
+    ///     let forest_cid = forest.store(store).await?;
+    ///
+    ///     let label = forest.get_label(&name);
+    ///     let proof = HamtForest::<blake3::Hasher>::prove_by_hash(&forest_cid, &label, store).await?;
+    ///
+    ///     let cids = HamtForest::<blake3::Hasher>::verify_proof(&forest_cid, &label, &proof).await?;
+    ///     assert!(cids.unwrap().contains(&cid));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn prove_by_hash(
+        forest_cid: &Cid,
+        label: &HashOutput,
+        store: &impl BlockStore,
+    ) -> Result<HamtProof> {
+        let forest = <Self as Storable>::load(forest_cid, store).await?;
+        forest.prove_stored(forest_cid, label, store).await
+    }
+
+    async fn prove_stored(
+        &self,
+        forest_cid: &Cid,
+        label: &HashOutput,
+        store: &impl BlockStore,
+    ) -> Result<HamtProof> {
+        let mut proof = HamtProof::new();
+        proof.push_block(*forest_cid, store.get_block(forest_cid).await?.to_vec());
+        self.hamt
+            .root
+            .prove_path_by_hash(label, &mut proof, store)
+            .await?;
+
+        Ok(proof)
+    }
+
+    /// Verifies a proof created with [`HamtForest::prove_by_hash`] against the CID of a forest.
+    ///
+    /// Returns the CIDs of the ciphertexts stored at the label if the proof shows they're present,
+    /// or `None` if it shows that nothing is stored at the label.
+    /// Fails if the proof is incomplete or any of its blocks don't match their CIDs.
+    pub async fn verify_proof(
+        forest_cid: &Cid,
+        label: &HashOutput,
+        proof: &HamtProof,
+    ) -> Result<Option<BTreeSet<Cid>>> {
+        let store = &proof.clone().into_block_store().await?;
+        let forest = Self::load(forest_cid, store).await?;
+        let ciphertexts = forest.get_encrypted_by_hash(label, store).await?;
+        Ok(ciphertexts.cloned())
+    }
 }
 
-impl PrivateForest for HamtForest {
+impl<H: NamedHasher + CondSync> PrivateForest for HamtForest<H> {
+    type Hasher = H;
+
     fn empty_name(&self) -> Name {
         Name::empty(&self.accumulator)
     }
@@ -260,11 +444,7 @@ impl PrivateForest for HamtForest {
     }
 
     async fn has(&self, name: &Name, store: &impl BlockStore) -> Result<bool> {
-        self.has_by_hash(
-            &blake3::Hasher::hash(&self.get_accumulated_name(name)),
-            store,
-        )
-        .await
+        self.has_by_hash(&self.get_label(name), store).await
     }
 
     async fn put_encrypted<I>(
@@ -292,6 +472,42 @@ impl PrivateForest for HamtForest {
         Ok(accumulator)
     }
 
+    async fn put_encrypted_many<I, C>(
+        &mut self,
+        entries: I,
+        store: &impl BlockStore,
+    ) -> Result<Vec<NameAccumulator>>
+    where
+        I: IntoIterator<Item = (Name, C)> + CondSend,
+        I::IntoIter: CondSend,
+        C: IntoIterator<Item = Cid> + CondSend,
+        C::IntoIter: CondSend,
+    {
+        let mut accumulators = Vec::new();
+        let mut updates = BTreeMap::<HashOutput, (NameAccumulator, BTreeSet<Cid>)>::new();
+        for (name, values) in entries {
+            let accumulator = self.get_accumulated_name(&name);
+            updates
+                .entry(H::hash(&accumulator))
+                .or_insert_with(|| (accumulator.clone(), BTreeSet::new()))
+                .1
+                .extend(values);
+            accumulators.push(accumulator);
+        }
+
+        let mut pairs = Vec::with_capacity(updates.len());
+        for (_, (accumulator, mut values)) in updates {
+            if let Some(ciphers) = self.hamt.root.get(&accumulator, store).await? {
+                values.extend(ciphers.0.iter().copied());
+            }
+            pairs.push((accumulator, Ciphertexts(values)));
+        }
+
+        self.hamt.root.set_many(pairs, store).await?;
+
+        Ok(accumulators)
+    }
+
     #[inline]
     async fn get_encrypted_by_hash<'b>(
         &'b self,
@@ -311,7 +527,7 @@ impl PrivateForest for HamtForest {
         name: &Name,
         store: &impl BlockStore,
     ) -> Result<Option<&BTreeSet<Cid>>> {
-        let name_hash = &blake3::Hasher::hash(&self.get_accumulated_name(name));
+        let name_hash = &self.get_label(name);
         self.get_encrypted_by_hash(name_hash, store).await
     }
 
@@ -320,7 +536,7 @@ impl PrivateForest for HamtForest {
         name: &Name,
         store: &impl BlockStore,
     ) -> Result<Option<Pair<NameAccumulator, BTreeSet<Cid>>>> {
-        let name_hash = &blake3::Hasher::hash(&self.get_accumulated_name(name));
+        let name_hash = &self.get_label(name);
         Ok(self
             .hamt
             .root
@@ -333,7 +549,9 @@ impl PrivateForest for HamtForest {
     }
 }
 
-impl PrivateForest for Arc<HamtForest> {
+impl<H: NamedHasher + CondSync + Clone> PrivateForest for Arc<HamtForest<H>> {
+    type Hasher = H;
+
     fn empty_name(&self) -> Name {
         (**self).empty_name()
     }
@@ -367,6 +585,20 @@ impl PrivateForest for Arc<HamtForest> {
         Arc::make_mut(self).put_encrypted(name, values, store).await
     }
 
+    async fn put_encrypted_many<I, C>(
+        &mut self,
+        entries: I,
+        store: &impl BlockStore,
+    ) -> Result<Vec<NameAccumulator>>
+    where
+        I: IntoIterator<Item = (Name, C)> + CondSend,
+        I::IntoIter: CondSend,
+        C: IntoIterator<Item = Cid> + CondSend,
+        C::IntoIter: CondSend,
+    {
+        Arc::make_mut(self).put_encrypted_many(entries, store).await
+    }
+
     async fn get_encrypted_by_hash<'b>(
         &'b self,
         name_hash: &HashOutput,
@@ -392,7 +624,7 @@ impl PrivateForest for Arc<HamtForest> {
     }
 }
 
-impl Storable for HamtForest {
+impl<H: NamedHasher + CondSync> Storable for HamtForest<H> {
     type Serializable = HamtForestSerializable;
 
     async fn to_serializable(&self, store: &impl BlockStore) -> Result<Self::Serializable> {
@@ -401,6 +633,7 @@ impl Storable for HamtForest {
             version: HAMT_VERSION,
             accumulator: self.accumulator.to_serializable(store).await?,
             structure: "hamt".to_string(),
+            hasher: H::NAME.to_string(),
         })
     }
 
@@ -408,6 +641,13 @@ impl Storable for HamtForest {
         _cid: Option<&Cid>,
         serializable: Self::Serializable,
     ) -> Result<Self> {
+        if serializable.hasher != H::NAME {
+            bail!(FsError::IncompatibleHasher {
+                expected: H::NAME.to_string(),
+                found: serializable.hasher,
+            });
+        }
+
         Ok(Self {
             hamt: Hamt::with_root(Arc::new(
                 Node::from_serializable(None, serializable.root).await?,
@@ -430,8 +670,9 @@ mod tests {
     use chrono::Utc;
     use rand_chacha::ChaCha12Rng;
     use rand_core::SeedableRng;
-    use wnfs_common::MemoryBlockStore;
-    use wnfs_nameaccumulator::NameSegment;
+    use wnfs_common::{MemoryBlockStore, CODEC_RAW};
+    use wnfs_hamt::Hasher;
+    use wnfs_nameaccumulator::{AccumulatorGroup, NameSegment};
 
     #[async_std::test]
     async fn test_put_get() {
@@ -454,6 +695,32 @@ mod tests {
         assert_eq!(result, Some(&BTreeSet::from([cid])));
     }
 
+    #[async_std::test]
+    async fn put_encrypted_many_is_like_put_encrypted() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let forest_many = &mut Arc::clone(forest);
+
+        // Some names appear twice, so their CIDs need to be merged.
+        let mut entries = Vec::new();
+        for i in 0..20u8 {
+            let segment = NameSegment::new_hashed("Testing", [i % 15]);
+            let name = forest.empty_name().with_segments_added([segment]);
+            let cid = store.put_block(vec![i], CODEC_RAW).await?;
+            entries.push((name, BTreeSet::from([cid])));
+        }
+
+        for (name, cids) in entries.iter() {
+            forest.put_encrypted(name, cids.clone(), store).await?;
+        }
+        forest_many.put_encrypted_many(entries, store).await?;
+
+        assert_eq!(forest.store(store).await?, forest_many.store(store).await?);
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn inserted_items_can_be_fetched() {
         let store = &mut MemoryBlockStore::new();
@@ -527,6 +794,177 @@ mod tests {
         assert_eq!(retrieved, private_node);
         assert_eq!(retrieved_conflict, private_node_conflict);
     }
+
+    #[derive(Debug, Clone)]
+    struct Sha256Hasher;
+
+    impl Hasher for Sha256Hasher {
+        fn hash<D: AsRef<[u8]>>(data: &D) -> HashOutput {
+            use sha2::Digest;
+            sha2::Sha256::digest(data.as_ref()).into()
+        }
+    }
+
+    impl NamedHasher for Sha256Hasher {
+        const NAME: &'static str = "sha2-256";
+    }
+
+    #[async_std::test]
+    async fn forest_with_custom_hasher_can_store_and_load_nodes() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let setup = AccumulatorSetup::from_rsa_2048(rng);
+        let forest = &mut Arc::new(HamtForest::<Sha256Hasher>::with_hasher(setup.clone()));
+        let blake3_forest = HamtForest::new(setup);
+
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let private_node = PrivateNode::Dir(dir);
+        let access_key = private_node.store(forest, store, rng).await?;
+
+        let name = private_node.get_header().get_revision_name();
+        assert_eq!(access_key.get_label(), &forest.get_label(&name));
+        assert_ne!(forest.get_label(&name), blake3_forest.get_label(&name));
+
+        let cid = forest.store(store).await?;
+        let forest = HamtForest::<Sha256Hasher>::load(&cid, store).await?;
+        let retrieved =
+            PrivateNode::load(&access_key, &forest, store, Some(forest.empty_name())).await?;
+
+        assert_eq!(retrieved, private_node);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn class_group_forest_can_store_and_load_nodes() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_class_group_2040_rc(rng);
+
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let private_node = PrivateNode::Dir(dir);
+        let access_key = private_node.store(forest, store, rng).await?;
+
+        let cid = forest.store(store).await?;
+        let loaded_forest = HamtForest::load(&cid, store).await?;
+        assert_eq!(
+            loaded_forest.get_accumulator_setup().get_group(),
+            AccumulatorGroup::ClassGroup
+        );
+        assert_eq!(
+            loaded_forest.get_accumulator_setup(),
+            forest.get_accumulator_setup()
+        );
+
+        let retrieved = PrivateNode::load(
+            &access_key,
+            &loaded_forest,
+            store,
+            Some(loaded_forest.empty_name()),
+        )
+        .await?;
+
+        assert_eq!(retrieved, private_node);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn loading_forest_with_different_hasher_fails() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let setup = AccumulatorSetup::from_rsa_2048(rng);
+
+        let sha256_cid = HamtForest::<Sha256Hasher>::with_hasher(setup.clone())
+            .store(store)
+            .await?;
+        let blake3_cid = HamtForest::new(setup).store(store).await?;
+
+        let error = HamtForest::load(&sha256_cid, store).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FsError>(),
+            Some(FsError::IncompatibleHasher { .. })
+        ));
+
+        let error = HamtForest::<Sha256Hasher>::load(&blake3_cid, store)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FsError>(),
+            Some(FsError::IncompatibleHasher { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn forest_proofs_show_presence_and_absence_of_names() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let mut names = Vec::new();
+        for i in 0..50u8 {
+            let name = forest
+                .empty_name()
+                .with_segments_added(Some(NameSegment::new_hashed("Testing", [i])));
+            let cid = store.put_block(vec![i], CODEC_RAW).await?;
+            forest.put_encrypted(&name, Some(cid), store).await?;
+            names.push((name, cid));
+        }
+        let forest_cid = forest.store(store).await?;
+
+        for (name, cid) in names.iter().step_by(7) {
+            let proof = HamtForest::<blake3::Hasher>::prove(&forest_cid, name, store).await?;
+            let cids = HamtForest::<blake3::Hasher>::verify_proof(
+                &forest_cid,
+                &forest.get_label(name),
+                &proof,
+            )
+            .await?;
+            assert_eq!(cids, Some(BTreeSet::from([*cid])));
+        }
+
+        let missing = forest
+            .empty_name()
+            .with_segments_added(Some(NameSegment::new_hashed("Testing", b"missing")));
+        let label = forest.get_label(&missing);
+        let proof = HamtForest::<blake3::Hasher>::prove_by_hash(&forest_cid, &label, store).await?;
+        let cids = HamtForest::<blake3::Hasher>::verify_proof(&forest_cid, &label, &proof).await?;
+        assert_eq!(cids, None);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn forest_proof_fails_for_other_forest() -> Result<()> {
+        let store = &MemoryBlockStore::new();
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let name = forest
+            .empty_name()
+            .with_segments_added(Some(NameSegment::new_hashed("Testing", b"one")));
+        let cid = store.put_block(vec![1], CODEC_RAW).await?;
+        forest.put_encrypted(&name, Some(cid), store).await?;
+        let forest_cid = forest.store(store).await?;
+        let proof = HamtForest::<blake3::Hasher>::prove(&forest_cid, &name, store).await?;
+
+        forest
+            .put_encrypted(&name, Some(Cid::default()), store)
+            .await?;
+        let other_cid = forest.store(store).await?;
+
+        let result = HamtForest::<blake3::Hasher>::verify_proof(
+            &other_cid,
+            &forest.get_label(&name),
+            &proof,
+        )
+        .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
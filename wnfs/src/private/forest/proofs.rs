@@ -4,10 +4,10 @@ use anyhow::{bail, Result};
 use libipld_core::cid::Cid;
 use std::collections::{BTreeSet, HashMap};
 use wnfs_common::{
-    utils::{Arc, CondSend},
+    utils::{Arc, CondSend, CondSync},
     BlockStore, HashOutput,
 };
-use wnfs_hamt::Pair;
+use wnfs_hamt::{NamedHasher, Pair};
 use wnfs_nameaccumulator::{
     AccumulatorSetup, BatchedProofPart, BatchedProofVerification, ElementsProof, Name,
     NameAccumulator, UnbatchableProofPart,
@@ -35,8 +35,8 @@ pub struct ForestProofs {
 /// This can also be used for verifying that a private forest state is valid compared
 /// to a different private forest state.
 #[derive(Debug, Clone)]
-pub struct ProvingHamtForest {
-    pub forest: Arc<HamtForest>,
+pub struct ProvingHamtForest<H: NamedHasher + CondSync = blake3::Hasher> {
+    pub forest: Arc<HamtForest<H>>,
     pub proofs: ForestProofs,
 }
 
@@ -85,11 +85,11 @@ impl ForestProofs {
     }
 }
 
-impl ProvingHamtForest {
+impl<H: NamedHasher + CondSync + Clone> ProvingHamtForest<H> {
     /// Create a new proving forest from the state of an existing hamt forest.
     ///
     /// It will be initialized without proofs.
-    pub fn new(forest: Arc<HamtForest>) -> Self {
+    pub fn new(forest: Arc<HamtForest<H>>) -> Self {
         Self {
             forest,
             proofs: ForestProofs::new(),
@@ -98,7 +98,7 @@ impl ProvingHamtForest {
 
     /// Create a new proving forest with given pre-existing proofs and current
     /// state of a hamt forest.
-    pub fn from_proofs(proofs: ForestProofs, forest: Arc<HamtForest>) -> Self {
+    pub fn from_proofs(proofs: ForestProofs, forest: Arc<HamtForest<H>>) -> Self {
         Self { forest, proofs }
     }
 
@@ -109,7 +109,7 @@ impl ProvingHamtForest {
     /// are allowed to change between the previous and current state.
     pub async fn verify_against_previous_state(
         &self,
-        previous: &HamtForest,
+        previous: &HamtForest<H>,
         allowed_bases: &BTreeSet<NameAccumulator>,
         store: &impl BlockStore,
     ) -> Result<()> {
@@ -149,7 +149,9 @@ impl Default for ForestProofs {
     }
 }
 
-impl PrivateForest for ProvingHamtForest {
+impl<H: NamedHasher + CondSync + Clone> PrivateForest for ProvingHamtForest<H> {
+    type Hasher = H;
+
     fn empty_name(&self) -> Name {
         self.forest.empty_name()
     }
@@ -189,6 +191,30 @@ impl PrivateForest for ProvingHamtForest {
             .await
     }
 
+    async fn put_encrypted_many<I, C>(
+        &mut self,
+        entries: I,
+        store: &impl BlockStore,
+    ) -> Result<Vec<NameAccumulator>>
+    where
+        I: IntoIterator<Item = (Name, C)> + CondSend,
+        I::IntoIter: CondSend,
+        C: IntoIterator<Item = Cid> + CondSend,
+        C::IntoIter: CondSend,
+    {
+        let ProvingHamtForest { forest, proofs } = self;
+
+        let mut proven_entries = Vec::new();
+        for (name, values) in entries {
+            proofs.add_and_prove_name(&name, forest.get_accumulator_setup())?;
+            proven_entries.push((name, values));
+        }
+
+        Arc::make_mut(forest)
+            .put_encrypted_many(proven_entries, store)
+            .await
+    }
+
     async fn get_encrypted_by_hash<'b>(
         &'b self,
         name_hash: &HashOutput,
@@ -11,7 +11,7 @@ use wnfs_common::{
     utils::{BoxStream, CondSend, CondSync},
     BlockStore, HashOutput,
 };
-use wnfs_hamt::Pair;
+use wnfs_hamt::{Hasher, Pair};
 use wnfs_nameaccumulator::{AccumulatorSetup, ElementsProof, Name, NameAccumulator};
 
 /// A trait representing a (usually serializable) mapping from
@@ -21,6 +21,9 @@ use wnfs_nameaccumulator::{AccumulatorSetup, ElementsProof, Name, NameAccumulato
 /// name accumulator operations. Upon put or remove, it'll run
 /// these operations for the caller.
 pub trait PrivateForest: CondSync {
+    /// The hash function used for deriving labels from accumulated names.
+    type Hasher: Hasher;
+
     /// Construct what represents the empty name in this forest.
     ///
     /// It is forest-specific, as it depends on the specific forest's
@@ -56,6 +59,39 @@ pub trait PrivateForest: CondSync {
         self.get_proven_name(name).0
     }
 
+    /// Derives the label under which values for the given name are stored,
+    /// i.e. the hash of the accumulated name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::forest::{hamt::HamtForest, traits::PrivateForest},
+    ///     common::{BlockStore, MemoryBlockStore, CODEC_RAW},
+    ///     nameaccumulator::NameSegment,
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///
+    ///     let name = forest.empty_name().with_segments_added(Some(NameSegment::new(rng)));
+    ///     let cid = store.put_block(vec![1, 2, 3], CODEC_RAW).await?;
+    ///     forest.put_encrypted(&name, Some(cid), store).await?;
+    ///
+    ///     assert!(forest.has_by_hash(&forest.get_label(&name), store).await?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn get_label(&self, name: &Name) -> HashOutput {
+        Self::Hasher::hash(&self.get_accumulated_name(name))
+    }
+
     /// Checks that a value with the given saturated name hash key exists.
     ///
     /// # Examples
@@ -108,6 +144,68 @@ pub trait PrivateForest: CondSync {
         I: IntoIterator<Item = Cid> + CondSend,
         I::IntoIter: CondSend;
 
+    /// Adds new encrypted values at many names at once.
+    ///
+    /// This has the same effect as calling `put_encrypted` for each entry,
+    /// but implementations may batch the underlying insertions.
+    ///
+    /// Returns the accumulated names in the order of the given entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::forest::{hamt::HamtForest, traits::PrivateForest},
+    ///     common::{BlockStore, MemoryBlockStore, CODEC_RAW},
+    ///     nameaccumulator::NameSegment,
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///
+    ///     let mut entries = Vec::new();
+    ///     for i in 0..10u8 {
+    ///         let name = forest.empty_name().with_segments_added(Some(NameSegment::new(rng)));
+    ///         let cid = store.put_block(vec![i], CODEC_RAW).await?;
+    ///         entries.push((name, Some(cid)));
+    ///     }
+    ///
+    ///     forest.put_encrypted_many(entries.clone(), store).await?;
+    ///
+    ///     for (name, cid) in entries {
+    ///         let cids = forest.get_encrypted(&name, store).await?.unwrap();
+    ///         assert!(cids.contains(&cid.unwrap()));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    fn put_encrypted_many<I, C>(
+        &mut self,
+        entries: I,
+        store: &impl BlockStore,
+    ) -> impl Future<Output = Result<Vec<NameAccumulator>>> + CondSend
+    where
+        I: IntoIterator<Item = (Name, C)> + CondSend,
+        I::IntoIter: CondSend,
+        C: IntoIterator<Item = Cid> + CondSend,
+        C::IntoIter: CondSend,
+    {
+        async move {
+            let mut accumulators = Vec::new();
+            for (name, values) in entries {
+                accumulators.push(self.put_encrypted(&name, values, store).await?);
+            }
+
+            Ok(accumulators)
+        }
+    }
+
     /// Gets the CIDs to blocks of ciphertext by hash of name.
     fn get_encrypted_by_hash<'b>(
         &'b self,
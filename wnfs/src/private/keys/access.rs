@@ -79,6 +79,20 @@ impl AccessKey {
         }
     }
 
+    /// Derives an access key to only the revision this access key points to.
+    ///
+    /// Snapshot access keys are returned as they are.
+    pub fn to_snapshot(&self) -> Self {
+        match self {
+            Self::Temporal(key) => Self::Snapshot(SnapshotAccessKey {
+                label: key.label,
+                content_cid: key.content_cid,
+                snapshot_key: key.temporal_key.derive_snapshot_key(),
+            }),
+            Self::Snapshot(_) => self.clone(),
+        }
+    }
+
     pub(crate) fn derive_private_ref(&self) -> Result<PrivateRef> {
         // TODO(appcypher): SnapshotAccessKey currently not supported for PrivateRef.
         let Self::Temporal(key) = self else {
@@ -1,14 +1,17 @@
-#[cfg(test)]
-use crate::error::RsaError;
-#[cfg(test)]
-use anyhow::anyhow;
-use anyhow::Result;
+use crate::error::{ExchangeKeyError, RsaError};
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use futures::Future;
-#[cfg(test)]
+use hkdf::Hkdf;
+use rand_core::OsRng;
 use rsa::{traits::PublicKeyParts, BigUint, Oaep};
-#[cfg(test)]
 use sha2::Sha256;
 use wnfs_common::utils::CondSend;
+use x25519_dalek::{EphemeralSecret, SharedSecret, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 //--------------------------------------------------------------------------------------------------
 // Constants
@@ -17,31 +20,67 @@ use wnfs_common::utils::CondSend;
 pub const RSA_KEY_SIZE: usize = 2048;
 pub const PUBLIC_KEY_EXPONENT: u64 = 65537;
 
+/// The size of X25519 public and private keys in bytes.
+pub const X25519_KEY_SIZE: usize = 32;
+
+/// The HKDF info string binding derived keys to the X25519 share encryption scheme.
+const X25519_HKDF_INFO: &[u8] = b"wnfs/share/x25519-hkdf-sha256-chacha20poly1305";
+
+/// The size of a Poly1305 authentication tag in bytes.
+const TAG_SIZE: usize = 16;
+
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
 //--------------------------------------------------------------------------------------------------
 
-/// The `ExchangeKey` trait defines methods for creating an RSA public key from a modulus and encrypting data with the public key.
-/// Implementations of this trait can create an RSA public key using the `from_modulus` method, which takes a modulus as input.
+/// The `ExchangeKey` trait defines methods for creating a public exchange key from its encoded form
+/// and encrypting data with it.
 ///
-/// Data can be encrypted with the public key using the `encrypt` method, which takes a slice of bytes as input and returns the encrypted data as a vector of bytes.
+/// Implementations may support one or more [`ExchangeKeyType`]s. Sharers only encrypt to the
+/// exchange keys of recipients whose type is supported, see [`ExchangeKey::supports`].
+///
+/// Implementations need to provide [`ExchangeKey::from_encoded`], and [`ExchangeKey::supports`]
+/// for key types other than RSA.
 ///
 /// More on exchange keys [here][key].
 ///
 /// [key]: https://github.com/wnfs-wg/spec/blob/main/spec/shared-private-data.md#2-exchange-keys-partition
 pub trait ExchangeKey {
+    /// Returns whether this implementation can encrypt to public keys of the given type.
+    ///
+    /// Defaults to supporting RSA keys only.
+    fn supports(key_type: ExchangeKeyType) -> bool
+    where
+        Self: Sized,
+    {
+        key_type == ExchangeKeyType::Rsa
+    }
+
+    /// Creates a public exchange key from its encoded form.
+    ///
+    /// Fails if the key type isn't supported by this implementation.
+    fn from_encoded(key: &EncodedExchangeKey) -> impl Future<Output = Result<Self>> + CondSend
+    where
+        Self: Sized;
+
     /// Creates an RSA public key from the public key modulus.
     ///
-    /// The exponent is expected to be of the value [`PUBLIC_KEY_EXPONENT`](constant.PUBLIC_KEY_EXPONENT.html) constant.
+    /// The exponent is expected to be [`PUBLIC_KEY_EXPONENT`].
+    /// Defaults to creating an RSA key with [`ExchangeKey::from_encoded`].
     fn from_modulus(modulus: &[u8]) -> impl Future<Output = Result<Self>> + CondSend
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        let key = EncodedExchangeKey::from_legacy_modulus(modulus.to_vec());
+        async move { Self::from_encoded(&key).await }
+    }
 
     /// Encrypts data with the public key.
     fn encrypt(&self, data: &[u8]) -> impl Future<Output = Result<Vec<u8>>> + CondSend;
 }
 
-/// The `PrivateKey` trait represents a RSA private key type that can be used to decrypt data encrypted with corresponding public key.
+/// The `PrivateKey` trait represents a private exchange key that can be used to decrypt
+/// data encrypted with the corresponding public key.
 pub trait PrivateKey {
     /// Decrypts ciphertext with the private key.
     fn decrypt(&self, ciphertext: &[u8]) -> impl Future<Output = Result<Vec<u8>>> + CondSend;
@@ -49,34 +88,174 @@ pub trait PrivateKey {
 
 pub type PublicKeyModulus = Vec<u8>;
 
-#[cfg(test)]
+/// The algorithms exchange keys can be used with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExchangeKeyType {
+    /// RSA with SHA-256 OAEP padding. The public key is the big-endian modulus,
+    /// the exponent is always [`PUBLIC_KEY_EXPONENT`].
+    Rsa,
+    /// X25519 key agreement with an ephemeral key, HKDF-SHA256 and ChaCha20-Poly1305,
+    /// similar to HPKE base mode. The public key is the 32-byte u-coordinate.
+    X25519,
+}
+
+/// A public exchange key as published in an exchange keys partition.
+///
+/// The typed encoding is the multicodec code of the key type as an unsigned varint,
+/// followed by the public key. Exchange keys published before key types existed
+/// are bare RSA moduli, see [`EncodedExchangeKey::from_legacy_modulus`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncodedExchangeKey {
+    key_type: ExchangeKeyType,
+    bytes: Vec<u8>,
+    prefix_len: usize,
+}
+
+/// An RSA public key using SHA-256 OAEP padding.
 #[derive(Debug, Clone)]
 pub struct RsaPublicKey(rsa::RsaPublicKey);
 
-#[cfg(test)]
+/// An RSA private key using SHA-256 OAEP padding.
 #[derive(Debug, Clone)]
 pub struct RsaPrivateKey(rsa::RsaPrivateKey);
 
+/// An X25519 public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X25519PublicKey(x25519_dalek::PublicKey);
+
+/// An X25519 private key.
+///
+/// Data is encrypted to it by agreeing on a key with a fresh ephemeral key pair,
+/// deriving a ChaCha20-Poly1305 key with HKDF-SHA256 and sending the ephemeral
+/// public key along with the ciphertext.
+///
+/// The secret is wiped from memory when the key is dropped, since the wrapped
+/// `StaticSecret` zeroizes itself on drop.
+#[derive(Clone)]
+pub struct X25519PrivateKey(StaticSecret);
+
+/// A public exchange key of any of the supported types.
+///
+/// Sharing with this type encrypts to all exchange keys of a recipient that this crate supports.
+#[derive(Debug, Clone)]
+pub enum AnyExchangeKey {
+    Rsa(RsaPublicKey),
+    X25519(X25519PublicKey),
+}
+
 //--------------------------------------------------------------------------------------------------
 // Implementations
 //--------------------------------------------------------------------------------------------------
 
-#[cfg(test)]
+impl ExchangeKeyType {
+    /// Gets the varint-encoded multicodec code prefixing typed exchange keys of this type.
+    pub fn prefix(&self) -> &'static [u8] {
+        match self {
+            // rsa-pub, 0x1205
+            Self::Rsa => &[0x85, 0x24],
+            // x25519-pub, 0xec
+            Self::X25519 => &[0xec, 0x01],
+        }
+    }
+
+    /// Finds the key type the given bytes are prefixed with.
+    pub fn from_prefixed(bytes: &[u8]) -> Option<Self> {
+        [Self::Rsa, Self::X25519]
+            .into_iter()
+            .find(|key_type| bytes.starts_with(key_type.prefix()))
+    }
+}
+
+impl EncodedExchangeKey {
+    /// Encodes a public key of the given type with its key type prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::private::{EncodedExchangeKey, ExchangeKeyType};
+    ///
+    /// let key = EncodedExchangeKey::new(ExchangeKeyType::X25519, &[7; 32]);
+    /// let decoded = EncodedExchangeKey::decode(key.as_bytes().to_vec()).unwrap();
+    ///
+    /// assert_eq!(decoded.key_type(), ExchangeKeyType::X25519);
+    /// assert_eq!(decoded.key_bytes(), &[7; 32]);
+    /// ```
+    pub fn new(key_type: ExchangeKeyType, key: &[u8]) -> Self {
+        let prefix = key_type.prefix();
+        Self {
+            key_type,
+            bytes: [prefix, key].concat(),
+            prefix_len: prefix.len(),
+        }
+    }
+
+    /// Decodes a public key with a key type prefix.
+    pub fn decode(bytes: Vec<u8>) -> Result<Self> {
+        let key_type =
+            ExchangeKeyType::from_prefixed(&bytes).ok_or(ExchangeKeyError::UnknownKeyType)?;
+
+        Ok(Self {
+            key_type,
+            prefix_len: key_type.prefix().len(),
+            bytes,
+        })
+    }
+
+    /// Wraps an RSA public key modulus published without a key type prefix.
+    pub fn from_legacy_modulus(modulus: Vec<u8>) -> Self {
+        Self {
+            key_type: ExchangeKeyType::Rsa,
+            bytes: modulus,
+            prefix_len: 0,
+        }
+    }
+
+    /// Gets the type of the key.
+    pub fn key_type(&self) -> ExchangeKeyType {
+        self.key_type
+    }
+
+    /// Gets the public key without its key type prefix.
+    pub fn key_bytes(&self) -> &[u8] {
+        &self.bytes[self.prefix_len..]
+    }
+
+    /// Gets the key as it is published, including the key type prefix, if any.
+    ///
+    /// Share names are derived from these bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
 impl RsaPublicKey {
     /// Gets the public key modulus.
     pub fn get_public_key_modulus(&self) -> Result<Vec<u8>> {
         Ok(self.0.n().to_bytes_be())
     }
+
+    /// Encodes the public key with its key type prefix.
+    pub fn to_encoded(&self) -> EncodedExchangeKey {
+        EncodedExchangeKey::new(ExchangeKeyType::Rsa, &self.0.n().to_bytes_be())
+    }
+
+    /// Creates an RSA public key from the public key modulus.
+    ///
+    /// The exponent is expected to be [`PUBLIC_KEY_EXPONENT`].
+    pub fn from_modulus(modulus: &[u8]) -> Result<Self> {
+        let n = BigUint::from_bytes_be(modulus);
+        let e = BigUint::from(PUBLIC_KEY_EXPONENT);
+
+        Ok(Self(
+            rsa::RsaPublicKey::new(n, e).map_err(|e| RsaError::InvalidPublicKey(anyhow!(e)))?,
+        ))
+    }
 }
 
-#[cfg(test)]
 impl RsaPrivateKey {
     /// Constructs a new 2048-bit RSA private key.
     pub fn new() -> Result<Self> {
-        Ok(Self(rsa::RsaPrivateKey::new(
-            &mut rand::thread_rng(),
-            RSA_KEY_SIZE,
-        )?))
+        Ok(Self(rsa::RsaPrivateKey::new(&mut OsRng, RSA_KEY_SIZE)?))
     }
 
     /// Gets the public key.
@@ -85,26 +264,101 @@ impl RsaPrivateKey {
     }
 }
 
-#[cfg(test)]
+impl From<rsa::RsaPrivateKey> for RsaPrivateKey {
+    fn from(key: rsa::RsaPrivateKey) -> Self {
+        Self(key)
+    }
+}
+
+impl X25519PublicKey {
+    /// Creates a public key from its 32-byte encoding.
+    pub fn from_bytes(bytes: [u8; X25519_KEY_SIZE]) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Gets the 32-byte encoding of the public key.
+    pub fn as_bytes(&self) -> &[u8; X25519_KEY_SIZE] {
+        self.0.as_bytes()
+    }
+
+    /// Encodes the public key with its key type prefix.
+    pub fn to_encoded(&self) -> EncodedExchangeKey {
+        EncodedExchangeKey::new(ExchangeKeyType::X25519, self.as_bytes())
+    }
+}
+
+impl X25519PrivateKey {
+    /// Generates a new random private key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::private::{ExchangeKey, PrivateKey, X25519PrivateKey, X25519PublicKey};
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let private_key = X25519PrivateKey::new();
+    ///     let public_key = private_key.get_public_key();
+    ///
+    ///     let ciphertext = public_key.encrypt(b"Hello, world!").await?;
+    ///     let plaintext = private_key.decrypt(&ciphertext).await?;
+    ///
+    ///     assert_eq!(plaintext, b"Hello, world!");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng))
+    }
+
+    /// Creates a private key from 32 secret bytes, e.g. derived from a seed.
+    pub fn from_bytes(bytes: [u8; X25519_KEY_SIZE]) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Gets the public key.
+    pub fn get_public_key(&self) -> X25519PublicKey {
+        X25519PublicKey((&self.0).into())
+    }
+}
+
+impl std::fmt::Debug for X25519PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("X25519PrivateKey").field(&"..").finish()
+    }
+}
+
+impl Zeroize for X25519PrivateKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for X25519PrivateKey {}
+
 impl ExchangeKey for RsaPublicKey {
+    fn supports(key_type: ExchangeKeyType) -> bool {
+        key_type == ExchangeKeyType::Rsa
+    }
+
+    async fn from_encoded(key: &EncodedExchangeKey) -> Result<Self> {
+        if key.key_type() != ExchangeKeyType::Rsa {
+            bail!(ExchangeKeyError::UnsupportedKeyType(key.key_type()));
+        }
+
+        Self::from_modulus(key.key_bytes())
+    }
+
     async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
         let padding = Oaep::new::<Sha256>();
         self.0
-            .encrypt(&mut rand::thread_rng(), padding, data)
+            .encrypt(&mut OsRng, padding, data)
             .map_err(|e| anyhow!(RsaError::EncryptionFailed(anyhow!(e))))
     }
-
-    async fn from_modulus(modulus: &[u8]) -> Result<Self> {
-        let n = BigUint::from_bytes_be(modulus);
-        let e = BigUint::from(PUBLIC_KEY_EXPONENT);
-
-        Ok(Self(
-            rsa::RsaPublicKey::new(n, e).map_err(|e| RsaError::InvalidPublicKey(anyhow!(e)))?,
-        ))
-    }
 }
 
-#[cfg(test)]
 impl PrivateKey for RsaPrivateKey {
     async fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
         let padding = Oaep::new::<Sha256>();
@@ -114,6 +368,109 @@ impl PrivateKey for RsaPrivateKey {
     }
 }
 
+impl ExchangeKey for X25519PublicKey {
+    fn supports(key_type: ExchangeKeyType) -> bool {
+        key_type == ExchangeKeyType::X25519
+    }
+
+    async fn from_encoded(key: &EncodedExchangeKey) -> Result<Self> {
+        if key.key_type() != ExchangeKeyType::X25519 {
+            bail!(ExchangeKeyError::UnsupportedKeyType(key.key_type()));
+        }
+
+        let bytes = key.key_bytes().try_into().map_err(|_| {
+            ExchangeKeyError::InvalidPublicKey(format!(
+                "expected {X25519_KEY_SIZE} bytes, got {}",
+                key.key_bytes().len()
+            ))
+        })?;
+
+        Ok(Self::from_bytes(bytes))
+    }
+
+    async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral_public = X25519PublicKey((&ephemeral).into());
+        let shared_secret = ephemeral.diffie_hellman(&self.0);
+        let cipher = derive_cipher(shared_secret, &ephemeral_public, self)?;
+
+        // The key is only ever used for this one message, so a fixed nonce is fine.
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&[0; 12]), data)
+            .map_err(|e| ExchangeKeyError::EncryptionFailed(e.to_string()))?;
+
+        Ok([ephemeral_public.as_bytes().as_slice(), &ciphertext].concat())
+    }
+}
+
+impl PrivateKey for X25519PrivateKey {
+    async fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < X25519_KEY_SIZE + TAG_SIZE {
+            bail!(ExchangeKeyError::DecryptionFailed);
+        }
+
+        let (ephemeral_public, ciphertext) = ciphertext.split_at(X25519_KEY_SIZE);
+        let ephemeral_public = X25519PublicKey::from_bytes(ephemeral_public.try_into()?);
+        let shared_secret = self.0.diffie_hellman(&ephemeral_public.0);
+        let cipher = derive_cipher(shared_secret, &ephemeral_public, &self.get_public_key())?;
+
+        Ok(cipher
+            .decrypt(Nonce::from_slice(&[0; 12]), ciphertext)
+            .map_err(|_| ExchangeKeyError::DecryptionFailed)?)
+    }
+}
+
+impl ExchangeKey for AnyExchangeKey {
+    fn supports(key_type: ExchangeKeyType) -> bool {
+        RsaPublicKey::supports(key_type) || X25519PublicKey::supports(key_type)
+    }
+
+    async fn from_encoded(key: &EncodedExchangeKey) -> Result<Self> {
+        Ok(match key.key_type() {
+            ExchangeKeyType::Rsa => Self::Rsa(RsaPublicKey::from_encoded(key).await?),
+            ExchangeKeyType::X25519 => Self::X25519(X25519PublicKey::from_encoded(key).await?),
+        })
+    }
+
+    async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Rsa(key) => key.encrypt(data).await,
+            Self::X25519(key) => key.encrypt(data).await,
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Derives the cipher for a message from the shared secret between the ephemeral and the recipient key.
+///
+/// Fails if the shared secret is all-zero, i.e. one of the public keys has low order.
+fn derive_cipher(
+    shared_secret: SharedSecret,
+    ephemeral_public: &X25519PublicKey,
+    recipient_public: &X25519PublicKey,
+) -> Result<ChaCha20Poly1305> {
+    if !shared_secret.was_contributory() {
+        bail!(ExchangeKeyError::InvalidPublicKey(
+            "public key has low order".into()
+        ));
+    }
+
+    let salt = [
+        ephemeral_public.as_bytes().as_slice(),
+        recipient_public.as_bytes(),
+    ]
+    .concat();
+    let mut key = Zeroizing::new([0; 32]);
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret.as_bytes())
+        .expand(X25519_HKDF_INFO, key.as_mut())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
 //--------------------------------------------------------------------------------------------------
 // Tests
 //--------------------------------------------------------------------------------------------------
@@ -140,9 +497,11 @@ mod test {
         let pub_key = priv_key.get_public_key();
 
         let public_key_modulus = pub_key.get_public_key_modulus().unwrap();
-        let key_pair_from_modulus = RsaPublicKey::from_modulus(&public_key_modulus)
-            .await
-            .unwrap();
+        let key_pair_from_modulus = RsaPublicKey::from_encoded(
+            &EncodedExchangeKey::from_legacy_modulus(public_key_modulus),
+        )
+        .await
+        .unwrap();
 
         let plaintext = b"Hello, world!";
         let ciphertext = key_pair_from_modulus.encrypt(plaintext).await.unwrap();
@@ -150,4 +509,119 @@ mod test {
 
         assert_eq!(plaintext, &decrypted[..]);
     }
+
+    #[async_std::test]
+    async fn test_x25519_key_pair() {
+        let priv_key = X25519PrivateKey::new();
+        let encoded = priv_key.get_public_key().to_encoded();
+        let pub_key = X25519PublicKey::from_encoded(&encoded).await.unwrap();
+
+        let plaintext = b"Hello, world!";
+        let ciphertext = pub_key.encrypt(plaintext).await.unwrap();
+        let decrypted = priv_key.decrypt(&ciphertext).await.unwrap();
+
+        assert_eq!(plaintext, &decrypted[..]);
+
+        // Every message uses a fresh ephemeral key
+        assert_ne!(ciphertext, pub_key.encrypt(plaintext).await.unwrap());
+    }
+
+    #[async_std::test]
+    async fn x25519_decryption_fails_for_other_key_or_tampering() {
+        let priv_key = X25519PrivateKey::new();
+        let ciphertext = priv_key
+            .get_public_key()
+            .encrypt(b"Hello, world!")
+            .await
+            .unwrap();
+
+        let other_key = X25519PrivateKey::new();
+        assert!(other_key.decrypt(&ciphertext).await.is_err());
+
+        let mut tampered = ciphertext.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(priv_key.decrypt(&tampered).await.is_err());
+
+        assert!(priv_key.decrypt(&ciphertext[..40]).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn encoded_keys_carry_their_type() {
+        let x25519_key = X25519PrivateKey::new().get_public_key().to_encoded();
+        let rsa_key = RsaPrivateKey::new().unwrap().get_public_key().to_encoded();
+
+        let decoded = EncodedExchangeKey::decode(x25519_key.as_bytes().to_vec()).unwrap();
+        assert_eq!(decoded, x25519_key);
+        let decoded = EncodedExchangeKey::decode(rsa_key.as_bytes().to_vec()).unwrap();
+        assert_eq!(decoded.key_type(), ExchangeKeyType::Rsa);
+
+        assert!(EncodedExchangeKey::decode(vec![0x00, 0x01, 0x02]).is_err());
+        assert!(RsaPublicKey::from_encoded(&x25519_key).await.is_err());
+        assert!(X25519PublicKey::from_encoded(&rsa_key).await.is_err());
+
+        assert!(matches!(
+            AnyExchangeKey::from_encoded(&x25519_key).await.unwrap(),
+            AnyExchangeKey::X25519(_)
+        ));
+        assert!(matches!(
+            AnyExchangeKey::from_encoded(&rsa_key).await.unwrap(),
+            AnyExchangeKey::Rsa(_)
+        ));
+    }
+
+    #[async_std::test]
+    async fn rsa_only_implementations_only_need_from_encoded() {
+        struct RsaOnlyKey(RsaPublicKey);
+
+        impl ExchangeKey for RsaOnlyKey {
+            async fn from_encoded(key: &EncodedExchangeKey) -> Result<Self> {
+                Ok(Self(RsaPublicKey::from_encoded(key).await?))
+            }
+
+            async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+                self.0.encrypt(data).await
+            }
+        }
+
+        let priv_key = RsaPrivateKey::new().unwrap();
+        let encoded = priv_key.get_public_key().to_encoded();
+        let x25519_key = X25519PrivateKey::new().get_public_key().to_encoded();
+
+        assert!(RsaOnlyKey::supports(ExchangeKeyType::Rsa));
+        assert!(!RsaOnlyKey::supports(ExchangeKeyType::X25519));
+        assert!(RsaOnlyKey::from_encoded(&x25519_key).await.is_err());
+
+        let pub_key = RsaOnlyKey::from_encoded(&encoded).await.unwrap();
+        let ciphertext = pub_key.encrypt(b"Hello, world!").await.unwrap();
+        assert_eq!(
+            priv_key.decrypt(&ciphertext).await.unwrap(),
+            b"Hello, world!"
+        );
+
+        // `from_modulus` is provided on top of `from_encoded`
+        let modulus = priv_key.get_public_key().get_public_key_modulus().unwrap();
+        let pub_key = <RsaOnlyKey as ExchangeKey>::from_modulus(&modulus)
+            .await
+            .unwrap();
+        let ciphertext = pub_key.encrypt(b"Hello, world!").await.unwrap();
+        assert_eq!(
+            priv_key.decrypt(&ciphertext).await.unwrap(),
+            b"Hello, world!"
+        );
+
+        let pub_key = <AnyExchangeKey as ExchangeKey>::from_modulus(&modulus)
+            .await
+            .unwrap();
+        assert!(matches!(pub_key, AnyExchangeKey::Rsa(_)));
+    }
+
+    #[async_std::test]
+    async fn x25519_rejects_low_order_public_keys() {
+        let low_order_key = X25519PublicKey::from_bytes([0; X25519_KEY_SIZE]);
+        assert!(low_order_key.encrypt(b"Hello, world!").await.is_err());
+
+        let priv_key = X25519PrivateKey::new();
+        let ciphertext = [[0; X25519_KEY_SIZE].as_slice(), &[0; TAG_SIZE]].concat();
+        assert!(priv_key.decrypt(&ciphertext).await.is_err());
+    }
 }
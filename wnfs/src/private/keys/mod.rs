@@ -1,7 +1,12 @@
 mod access;
 mod exchange;
+mod password;
 mod privateref;
+mod provider;
 
 pub use self::exchange::*;
 pub use access::*;
+pub use password::*;
+pub use privateref::PrivateRef;
 pub(crate) use privateref::*;
+pub use provider::*;
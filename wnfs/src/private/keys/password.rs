@@ -0,0 +1,451 @@
+use crate::{
+    error::{CryptError, PasswordError},
+    private::AccessKey,
+    public::PublicDirectory,
+};
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use chrono::{DateTime, Utc};
+use libipld_core::cid::Cid;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use wnfs_common::{
+    utils::{self, Arc},
+    BlockStore, CODEC_DAG_CBOR,
+};
+use zeroize::Zeroizing;
+
+//--------------------------------------------------------------------------------------------------
+// Constants
+//--------------------------------------------------------------------------------------------------
+
+/// The type tag of password-wrapped access keys.
+const PASSWORD_WRAPPED_KEY_TYPE: &str = "wnfs/access-key/password";
+
+/// The current version of the password-wrapped access key envelope.
+pub const PASSWORD_WRAPPED_KEY_VERSION: u64 = 1;
+
+/// The size of the random salt for the key derivation.
+const SALT_SIZE: usize = 16;
+
+/// The maximum memory size in KiB accepted for the key derivation (1 GiB).
+pub const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// The maximum number of iterations accepted for the key derivation.
+pub const MAX_KDF_ITERATIONS: u32 = 10;
+
+/// The maximum degree of parallelism accepted for the key derivation.
+pub const MAX_KDF_PARALLELISM: u32 = 16;
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// Argon2id parameters for deriving a key from a password.
+///
+/// The defaults follow the OWASP recommendation of 19 MiB memory, 2 iterations and
+/// a parallelism of 1.
+///
+/// Parameters above [`MAX_KDF_MEMORY_KIB`], [`MAX_KDF_ITERATIONS`] or [`MAX_KDF_PARALLELISM`]
+/// are rejected, so untrusted envelopes can't make unwrapping arbitrarily expensive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordKdfParams {
+    /// Memory size in KiB.
+    pub memory_kib: u32,
+    /// Number of iterations.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+/// An access key encrypted with a key derived from a password.
+///
+/// The envelope holds everything apart from the password that's needed to unwrap
+/// the access key: the key derivation parameters and salt, and the nonce and ciphertext
+/// of the XChaCha20-Poly1305 encryption. The parameters and salt are authenticated as well.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::Result;
+/// use chrono::Utc;
+/// use rand_chacha::ChaCha12Rng;
+/// use rand_core::SeedableRng;
+/// use wnfs::{
+///     private::{
+///         forest::{hamt::HamtForest, traits::PrivateForest},
+///         PasswordKdfParams, PasswordWrappedKey, PrivateDirectory,
+///     },
+///     common::MemoryBlockStore,
+/// };
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let store = &MemoryBlockStore::default();
+///     let rng = &mut ChaCha12Rng::from_entropy();
+///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+///     let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+///     let access_key = dir.as_node().store(forest, store, rng).await?;
+///
+///     let params = PasswordKdfParams::default();
+///     let wrapped = PasswordWrappedKey::wrap(&access_key, b"correct horse", params, rng)?;
+///     let cid = wrapped.store(store).await?;
+///
+///     let loaded = PasswordWrappedKey::load(&cid, store).await?;
+///     assert_eq!(loaded.unwrap(b"correct horse")?, access_key);
+///     assert!(loaded.unwrap(b"wrong horse").is_err());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordWrappedKey {
+    params: PasswordKdfParams,
+    salt: [u8; SALT_SIZE],
+    nonce: [u8; 24],
+    ciphertext: Vec<u8>,
+}
+
+/// The authenticated part of the envelope.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PasswordWrappedKeyHeader {
+    r#type: String,
+    version: u64,
+    algorithm: String,
+    params: PasswordKdfParams,
+    #[serde(with = "serde_bytes")]
+    salt: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PasswordWrappedKeySerializable {
+    header: PasswordWrappedKeyHeader,
+    #[serde(with = "serde_bytes")]
+    nonce: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    ciphertext: Vec<u8>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl Default for PasswordKdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl PasswordKdfParams {
+    /// Checks that the parameters don't exceed the accepted maximums.
+    pub fn check_bounds(&self) -> Result<(), PasswordError> {
+        let exceeded = |field: &str, max: u32| {
+            PasswordError::InvalidKdfParams(format!("{field} exceeds the maximum of {max}"))
+        };
+
+        if self.memory_kib > MAX_KDF_MEMORY_KIB {
+            return Err(exceeded("memory size in KiB", MAX_KDF_MEMORY_KIB));
+        }
+
+        if self.iterations > MAX_KDF_ITERATIONS {
+            return Err(exceeded("iterations", MAX_KDF_ITERATIONS));
+        }
+
+        if self.parallelism > MAX_KDF_PARALLELISM {
+            return Err(exceeded("parallelism", MAX_KDF_PARALLELISM));
+        }
+
+        Ok(())
+    }
+
+    /// Derives a 256-bit key from the password and salt with Argon2id.
+    fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        self.check_bounds()?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| PasswordError::InvalidKdfParams(e.to_string()))?;
+
+        let mut key = Zeroizing::new([0; 32]);
+        Argon2::new(Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password_into(password, salt, key.as_mut())
+            .map_err(|e| PasswordError::InvalidKdfParams(e.to_string()))?;
+
+        Ok(key)
+    }
+}
+
+impl PasswordWrappedKey {
+    /// Encrypts an access key with a key derived from given password.
+    pub fn wrap(
+        access_key: &AccessKey,
+        password: &[u8],
+        params: PasswordKdfParams,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self> {
+        let salt = utils::get_random_bytes(rng);
+        let nonce = utils::get_random_bytes(rng);
+        let key = params.derive_key(password, &salt)?;
+        let header = Self::header(&params, &salt);
+
+        let ciphertext = XChaCha20Poly1305::new(&(*key).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &Zeroizing::new(access_key.to_bytes()?),
+                    aad: &serde_ipld_dagcbor::to_vec(&header)?,
+                },
+            )
+            .map_err(|e| CryptError::UnableToEncrypt(anyhow!(e)))?;
+
+        Ok(Self {
+            params,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypts the access key with given password.
+    ///
+    /// Fails with `PasswordError::DecryptionFailed` if the password is wrong.
+    pub fn unwrap(&self, password: &[u8]) -> Result<AccessKey> {
+        let key = self.params.derive_key(password, &self.salt)?;
+        let header = Self::header(&self.params, &self.salt);
+
+        let bytes = XChaCha20Poly1305::new(&(*key).into())
+            .decrypt(
+                XNonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &serde_ipld_dagcbor::to_vec(&header)?,
+                },
+            )
+            .map_err(|_| PasswordError::DecryptionFailed)?;
+
+        AccessKey::parse(Zeroizing::new(bytes))
+    }
+
+    /// Gets the key derivation parameters the access key was wrapped with.
+    pub fn get_params(&self) -> &PasswordKdfParams {
+        &self.params
+    }
+
+    /// Serializes the envelope to dag-cbor.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_ipld_dagcbor::to_vec(
+            &PasswordWrappedKeySerializable {
+                header: Self::header(&self.params, &self.salt),
+                nonce: self.nonce.to_vec(),
+                ciphertext: self.ciphertext.clone(),
+            },
+        )?)
+    }
+
+    /// Deserializes an envelope serialized with `to_bytes`.
+    ///
+    /// Fails if the key derivation parameters exceed the accepted maximums.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let serializable: PasswordWrappedKeySerializable = serde_ipld_dagcbor::from_slice(bytes)?;
+        let header = serializable.header;
+
+        if header.r#type != PASSWORD_WRAPPED_KEY_TYPE {
+            bail!(PasswordError::UnsupportedEnvelope(format!(
+                "unknown type {}",
+                header.r#type
+            )));
+        }
+
+        if header.version != PASSWORD_WRAPPED_KEY_VERSION {
+            bail!(PasswordError::UnsupportedEnvelope(format!(
+                "unknown version {}",
+                header.version
+            )));
+        }
+
+        if header.algorithm != "argon2id" {
+            bail!(PasswordError::UnsupportedEnvelope(format!(
+                "unknown key derivation algorithm {}",
+                header.algorithm
+            )));
+        }
+
+        header.params.check_bounds()?;
+
+        let invalid = |field: &str| PasswordError::UnsupportedEnvelope(format!("invalid {field}"));
+
+        Ok(Self {
+            params: header.params,
+            salt: header.salt.try_into().map_err(|_| invalid("salt"))?,
+            nonce: serializable
+                .nonce
+                .try_into()
+                .map_err(|_| invalid("nonce"))?,
+            ciphertext: serializable.ciphertext,
+        })
+    }
+
+    /// Stores the envelope as a standalone dag-cbor block.
+    pub async fn store(&self, store: &impl BlockStore) -> Result<Cid> {
+        Ok(store.put_block(self.to_bytes()?, CODEC_DAG_CBOR).await?)
+    }
+
+    /// Loads an envelope stored with `store`.
+    pub async fn load(cid: &Cid, store: &impl BlockStore) -> Result<Self> {
+        Self::from_bytes(&store.get_block(cid).await?)
+    }
+
+    /// Writes the envelope as a file in a public directory, e.g. the exchange partition.
+    pub async fn write(
+        &self,
+        dir: &mut Arc<PublicDirectory>,
+        path_segments: &[String],
+        time: DateTime<Utc>,
+        store: &impl BlockStore,
+    ) -> Result<()> {
+        dir.write(path_segments, self.to_bytes()?, time, store)
+            .await
+    }
+
+    /// Reads an envelope written with `write`.
+    pub async fn read(
+        dir: &PublicDirectory,
+        path_segments: &[String],
+        store: &impl BlockStore,
+    ) -> Result<Self> {
+        Self::from_bytes(&dir.read(path_segments, store).await?)
+    }
+
+    fn header(params: &PasswordKdfParams, salt: &[u8]) -> PasswordWrappedKeyHeader {
+        PasswordWrappedKeyHeader {
+            r#type: PASSWORD_WRAPPED_KEY_TYPE.into(),
+            version: PASSWORD_WRAPPED_KEY_VERSION,
+            algorithm: "argon2id".into(),
+            params: *params,
+            salt: salt.to_vec(),
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::private::{
+        forest::{hamt::HamtForest, traits::PrivateForest},
+        PrivateDirectory,
+    };
+    use rand_chacha::ChaCha12Rng;
+    use rand_core::SeedableRng;
+    use testresult::TestResult;
+    use wnfs_common::MemoryBlockStore;
+
+    const TEST_PARAMS: PasswordKdfParams = PasswordKdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[async_std::test]
+    async fn wrapped_access_key_roundtrips_through_the_exchange_partition() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let access_key = dir.as_node().store(forest, store, rng).await?;
+
+        let wrapped = PasswordWrappedKey::wrap(&access_key, b"passphrase", TEST_PARAMS, rng)?;
+        let exchange_root = &mut PublicDirectory::new_rc(Utc::now());
+        let path = ["recovery".into(), "root.key".into()];
+        wrapped
+            .write(exchange_root, &path, Utc::now(), store)
+            .await?;
+
+        let read = PasswordWrappedKey::read(exchange_root, &path, store).await?;
+        assert_eq!(read, wrapped);
+        assert_eq!(read.get_params(), &TEST_PARAMS);
+        assert_eq!(read.unwrap(b"passphrase")?, access_key);
+
+        let error = read.unwrap(b"Passphrase").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PasswordError>(),
+            Some(PasswordError::DecryptionFailed)
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn tampered_params_are_rejected() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let access_key = dir.as_node().store(forest, store, rng).await?;
+
+        let mut wrapped = PasswordWrappedKey::wrap(&access_key, b"passphrase", TEST_PARAMS, rng)?;
+        wrapped.params.iterations = 2;
+        assert!(wrapped.unwrap(b"passphrase").is_err());
+
+        let mut serializable: PasswordWrappedKeySerializable =
+            serde_ipld_dagcbor::from_slice(&wrapped.to_bytes()?)?;
+        serializable.header.version = PASSWORD_WRAPPED_KEY_VERSION + 1;
+        let bytes = serde_ipld_dagcbor::to_vec(&serializable)?;
+        assert!(PasswordWrappedKey::from_bytes(&bytes).is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn excessive_params_are_rejected() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let access_key = dir.as_node().store(forest, store, rng).await?;
+
+        let wrapped = PasswordWrappedKey::wrap(&access_key, b"passphrase", TEST_PARAMS, rng)?;
+        let excessive = [
+            PasswordKdfParams {
+                memory_kib: MAX_KDF_MEMORY_KIB + 1,
+                ..TEST_PARAMS
+            },
+            PasswordKdfParams {
+                iterations: MAX_KDF_ITERATIONS + 1,
+                ..TEST_PARAMS
+            },
+            PasswordKdfParams {
+                parallelism: MAX_KDF_PARALLELISM + 1,
+                ..TEST_PARAMS
+            },
+        ];
+
+        for params in excessive {
+            let mut serializable: PasswordWrappedKeySerializable =
+                serde_ipld_dagcbor::from_slice(&wrapped.to_bytes()?)?;
+            serializable.header.params = params;
+            let bytes = serde_ipld_dagcbor::to_vec(&serializable)?;
+
+            let error = PasswordWrappedKey::from_bytes(&bytes).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<PasswordError>(),
+                Some(PasswordError::InvalidKdfParams(_))
+            ));
+            assert!(PasswordWrappedKey::wrap(&access_key, b"passphrase", params, rng).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     error::FsError,
-    private::{PrivateRefSerializable, TemporalKey, KEY_BYTE_SIZE},
+    private::{KeyProvider, PrivateRefSerializable, TemporalKey, KEY_BYTE_SIZE},
 };
 use anyhow::Result;
 use libipld_core::cid::Cid;
@@ -15,12 +15,15 @@ use wnfs_common::HashOutput;
 /// PrivateRef holds the information to fetch a specific node from the private forest and decrypt it.
 ///
 /// It also includes required key material to decrypt/encrypt any future revisions of the node it points to.
+///
+/// The key material is a [`TemporalKey`] by default, but it can be any [`KeyProvider`],
+/// e.g. for loading a node whose temporal key is kept outside of memory.
 #[derive(Clone, PartialEq, Eq)]
-pub struct PrivateRef {
+pub struct PrivateRef<K = TemporalKey> {
     /// Blake3 hash of the revision name. Used as the label for identifying revisions of PrivateNodes in the PrivateForest.
     pub label: HashOutput,
     /// Skip-ratchet-derived key. Gives read access to the revision pointed to and any newer revisions.
-    pub temporal_key: TemporalKey,
+    pub temporal_key: K,
     /// CID that identifies the exact value in the multivalue.
     pub content_cid: Cid,
 }
@@ -72,13 +75,21 @@ impl PrivateRef {
         })
     }
 
-    pub(crate) fn from_serializable(
+    pub(crate) async fn from_serializable(
         private_ref: PrivateRefSerializable,
-        parent_temporal_key: &TemporalKey,
+        parent_temporal_key: &impl KeyProvider,
     ) -> Result<Self> {
-        let temporal_key_decrypted =
-            parent_temporal_key.key_wrap_decrypt(&private_ref.temporal_key)?;
+        let temporal_key_decrypted = parent_temporal_key
+            .key_unwrap(&private_ref.temporal_key)
+            .await?;
 
+        Self::from_decrypted_serializable(private_ref, temporal_key_decrypted)
+    }
+
+    fn from_decrypted_serializable(
+        private_ref: PrivateRefSerializable,
+        temporal_key_decrypted: Vec<u8>,
+    ) -> Result<Self> {
         let temporal_key_raw: [u8; KEY_BYTE_SIZE] =
             temporal_key_decrypted.try_into().map_err(|e: Vec<u8>| {
                 FsError::InvalidDeserialization(format!(
@@ -117,11 +128,27 @@ impl PrivateRef {
         D: serde::Deserializer<'de>,
     {
         let private_ref = PrivateRefSerializable::deserialize(deserializer)?;
-        PrivateRef::from_serializable(private_ref, temporal_key).map_err(DeError::custom)
+        let temporal_key_decrypted = temporal_key
+            .key_wrap_decrypt(&private_ref.temporal_key)
+            .map_err(DeError::custom)?;
+        PrivateRef::from_decrypted_serializable(private_ref, temporal_key_decrypted)
+            .map_err(DeError::custom)
+    }
+}
+
+impl<K: KeyProvider> PrivateRef<K> {
+    /// Creates a PrivateRef that decrypts the node it points to with a key provider
+    /// instead of an in-memory temporal key.
+    pub fn with_key_provider(label: HashOutput, key_provider: K, content_cid: Cid) -> Self {
+        Self {
+            label,
+            temporal_key: key_provider,
+            content_cid,
+        }
     }
 }
 
-impl Debug for PrivateRef {
+impl<K: Debug> Debug for PrivateRef<K> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut rev_name_hash_str = String::from("0x");
         for byte in self.label {
@@ -130,7 +157,7 @@ impl Debug for PrivateRef {
 
         f.debug_struct("PrivateRef")
             .field("label", &rev_name_hash_str)
-            .field("temporal_key", &hex::encode(self.temporal_key.0))
+            .field("temporal_key", &self.temporal_key)
             .field("content_cid", &format!("{}", self.content_cid))
             .finish()
     }
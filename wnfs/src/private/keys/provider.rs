@@ -0,0 +1,176 @@
+use crate::private::{SnapshotKey, TemporalKey};
+use anyhow::Result;
+use futures::Future;
+use wnfs_common::utils::{CondSend, CondSync};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The `KeyProvider` trait defines the operations needed to decrypt a private node with its
+/// temporal key, without requiring the key material to be in memory.
+///
+/// This allows keeping e.g. the temporal key of a root directory in an OS keychain or a KMS,
+/// and loading the directory from a [`PrivateRef`] holding a key provider, see
+/// [`PrivateNode::from_private_ref`](crate::private::PrivateNode::from_private_ref).
+/// [`TemporalKey`] is the in-memory implementation and the default key of a private ref.
+///
+/// Once a node is loaded, its header is in memory, which contains the ratchet the temporal key is
+/// derived from. So only the key at rest is protected, not the key of a loaded node.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::Result;
+/// use chrono::Utc;
+/// use rand_chacha::ChaCha12Rng;
+/// use rand_core::SeedableRng;
+/// use wnfs::{
+///     private::{
+///         forest::{hamt::HamtForest, traits::PrivateForest},
+///         KeyProvider, PrivateDirectory, PrivateNode, PrivateRef, SnapshotKey, TemporalKey,
+///     },
+///     common::MemoryBlockStore,
+/// };
+///
+/// /// Stands in for a key held by an OS keychain.
+/// struct Keychain(TemporalKey);
+///
+/// impl KeyProvider for Keychain {
+///     async fn snapshot_key(&self) -> Result<SnapshotKey> {
+///         self.0.snapshot_key().await
+///     }
+///
+///     async fn key_unwrap(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+///         self.0.key_unwrap(ciphertext).await
+///     }
+/// }
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let store = &MemoryBlockStore::default();
+///     let rng = &mut ChaCha12Rng::from_entropy();
+///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+///     let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+///
+///     let node = dir.as_node();
+///     let access_key = node.store(forest, store, rng).await?;
+///     let keychain = Keychain(access_key.get_temporal_key()?.clone());
+///
+///     let private_ref = PrivateRef::with_key_provider(
+///         *access_key.get_label(),
+///         keychain,
+///         *access_key.get_content_cid(),
+///     );
+///     let loaded = PrivateNode::from_private_ref(&private_ref, forest, store, None).await?;
+///
+///     assert_eq!(loaded, node);
+///
+///     Ok(())
+/// }
+/// ```
+pub trait KeyProvider: CondSync {
+    /// Gets the snapshot key derived from the temporal key, to decrypt the node's content block.
+    fn snapshot_key(&self) -> impl Future<Output = Result<SnapshotKey>> + CondSend;
+
+    /// Decrypts a ciphertext encrypted with AES key wrap with padding (AES-KWP) under the
+    /// temporal key. This is used for the node's header and the private refs of its children.
+    fn key_unwrap(&self, ciphertext: &[u8]) -> impl Future<Output = Result<Vec<u8>>> + CondSend;
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl KeyProvider for TemporalKey {
+    async fn snapshot_key(&self) -> Result<SnapshotKey> {
+        Ok(self.derive_snapshot_key())
+    }
+
+    async fn key_unwrap(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        self.key_wrap_decrypt(ciphertext)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::private::{
+        forest::{hamt::HamtForest, traits::PrivateForest},
+        PrivateDirectory, PrivateNode, PrivateRef,
+    };
+    use chrono::Utc;
+    use rand_chacha::ChaCha12Rng;
+    use rand_core::SeedableRng;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use testresult::TestResult;
+    use wnfs_common::MemoryBlockStore;
+
+    /// Counts how often the key is used.
+    struct CountingProvider(TemporalKey, AtomicUsize);
+
+    impl KeyProvider for CountingProvider {
+        async fn snapshot_key(&self) -> Result<SnapshotKey> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            self.0.snapshot_key().await
+        }
+
+        async fn key_unwrap(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            self.0.key_unwrap(ciphertext).await
+        }
+    }
+
+    #[async_std::test]
+    async fn root_directory_can_be_loaded_with_a_key_provider() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        root_dir
+            .write(
+                &["docs".into(), "notes.txt".into()],
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await?;
+        let access_key = root_dir.as_node().store(forest, store, rng).await?;
+
+        let private_ref = PrivateRef::with_key_provider(
+            *access_key.get_label(),
+            CountingProvider(access_key.get_temporal_key()?.clone(), AtomicUsize::new(0)),
+            *access_key.get_content_cid(),
+        );
+        let loaded = PrivateNode::from_private_ref(&private_ref, forest, store, None)
+            .await?
+            .as_dir()?;
+
+        // The content key, the header and the private ref of the single child
+        assert_eq!(private_ref.temporal_key.1.load(Ordering::SeqCst), 3);
+        let content = loaded
+            .read(&["docs".into(), "notes.txt".into()], true, forest, store)
+            .await?;
+        assert_eq!(content, b"Hello");
+
+        let wrong_ref = PrivateRef::with_key_provider(
+            *access_key.get_label(),
+            TemporalKey([0; 32]),
+            *access_key.get_content_cid(),
+        );
+        assert!(
+            PrivateNode::from_private_ref(&wrong_ref, forest, store, None)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+}
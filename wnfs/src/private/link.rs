@@ -1,5 +1,6 @@
 use super::{
-    forest::traits::PrivateForest, PrivateDirectory, PrivateFile, PrivateNode, PrivateRef,
+    forest::traits::PrivateForest, DeferredStore, PrivateDirectory, PrivateFile, PrivateNode,
+    PrivateRef,
 };
 use crate::utils::OnceCellDebug;
 use anyhow::{anyhow, Result};
@@ -62,6 +63,28 @@ impl PrivateLink {
         }
     }
 
+    /// Like `resolve_ref`, but defers any insertions into the forest.
+    ///
+    /// See `PrivateNode::store_concurrent`.
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    pub(crate) async fn resolve_ref_deferred(
+        &self,
+        concurrency: usize,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<(PrivateRef, DeferredStore)> {
+        match self {
+            Self::Encrypted { private_ref, .. } => {
+                Ok((private_ref.clone(), DeferredStore::default()))
+            }
+            Self::Decrypted { node } => {
+                Ok(node.store_deferred(concurrency, forest, store, rng).await?)
+            }
+        }
+    }
+
     pub(crate) async fn resolve_node(
         &self,
         forest: &impl PrivateForest,
@@ -8,10 +8,13 @@ mod keys;
 mod link;
 mod node;
 mod previous;
+pub mod recovery;
 pub mod share;
+mod snapshot;
 
 pub use directory::*;
 pub use file::*;
 pub use keys::*;
 pub use node::*;
 pub use previous::*;
+pub use snapshot::*;
@@ -1,7 +1,7 @@
 use super::{PrivateNode, PrivateNodeHeaderSerializable, TemporalKey, REVISION_SEGMENT_DSI};
 use crate::{
     error::FsError,
-    private::{forest::traits::PrivateForest, RevisionRef},
+    private::{forest::traits::PrivateForest, KeyProvider, RevisionRef},
 };
 use anyhow::{anyhow, bail, Result};
 use futures::TryStreamExt;
@@ -10,7 +10,6 @@ use rand_core::CryptoRngCore;
 use skip_ratchet::Ratchet;
 use std::{collections::BTreeMap, fmt::Debug};
 use wnfs_common::{BlockStore, CODEC_RAW};
-use wnfs_hamt::Hasher;
 use wnfs_nameaccumulator::{Name, NameSegment};
 
 //--------------------------------------------------------------------------------------------------
@@ -88,7 +87,7 @@ impl PrivateNodeHeader {
     /// Derives the revision ref of the current header.
     pub(crate) fn derive_revision_ref(&self, forest: &impl PrivateForest) -> RevisionRef {
         let temporal_key = self.derive_temporal_key();
-        let label = blake3::Hasher::hash(&forest.get_accumulated_name(&self.get_revision_name()));
+        let label = forest.get_label(&self.get_revision_name());
 
         RevisionRef {
             label,
@@ -192,14 +191,15 @@ impl PrivateNodeHeader {
     /// to be decrypted with given key.
     pub(crate) async fn load(
         cid: &Cid,
-        temporal_key: &TemporalKey,
+        temporal_key: &impl KeyProvider,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
         parent_name: Option<Name>,
     ) -> Result<Self> {
         let ciphertext = store.get_block(cid).await?;
-        let cbor_bytes = temporal_key.key_wrap_decrypt(&ciphertext)?;
+        let cbor_bytes = temporal_key.key_unwrap(&ciphertext).await?;
         let decoded: PrivateNodeHeaderSerializable = serde_ipld_dagcbor::from_slice(&cbor_bytes)?;
+        decoded.name.validate(forest.get_accumulator_setup())?;
         let serialized_name = decoded.name.clone();
         let mut header = Self::from_serializable(decoded);
         if let Some(parent_name) = parent_name {
@@ -225,8 +225,7 @@ impl PrivateNodeHeader {
     ) -> Result<Vec<(Cid, PrivateNode)>> {
         let mountpoint = self.name.parent();
 
-        let name_hash =
-            blake3::Hasher::hash(&forest.get_accumulated_name(&self.get_revision_name()));
+        let name_hash = forest.get_label(&self.get_revision_name());
 
         forest
             .get_multivalue_by_hash(&name_hash, &self.derive_temporal_key(), store, mountpoint)
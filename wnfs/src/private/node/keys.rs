@@ -10,7 +10,9 @@ use rand_core::CryptoRngCore;
 use serde::{Deserialize, Serialize};
 use skip_ratchet::Ratchet;
 use std::fmt::Debug;
+use subtle::ConstantTimeEq;
 use wnfs_common::utils;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 //--------------------------------------------------------------------------------------------------
 // Constants
@@ -55,11 +57,15 @@ pub(crate) const SNAPSHOT_KEY_DSI: &str = "wnfs/1.0/snapshot key derivation from
 //--------------------------------------------------------------------------------------------------
 
 /// The key used to encrypt the content of a node.
-#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+///
+/// The key material is wiped from memory when the key is dropped.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SnapshotKey(#[serde(with = "serde_byte_array")] pub(crate) [u8; KEY_BYTE_SIZE]);
 
 /// The key used to encrypt the header section of a node.
-#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+///
+/// The key material is wiped from memory when the key is dropped.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TemporalKey(#[serde(with = "serde_byte_array")] pub(crate) [u8; KEY_BYTE_SIZE]);
 
 //--------------------------------------------------------------------------------------------------
@@ -217,17 +223,65 @@ impl SnapshotKey {
 
 impl Debug for SnapshotKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("SnapshotKey")
-            .field(&hex::encode(&self.0[..8]))
-            .finish()
+        f.debug_tuple("SnapshotKey").field(&"..").finish()
     }
 }
 
 impl Debug for TemporalKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("TemporalKey")
-            .field(&hex::encode(&self.0[..8]))
-            .finish()
+        f.debug_tuple("TemporalKey").field(&"..").finish()
+    }
+}
+
+impl PartialEq for SnapshotKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+
+impl Eq for SnapshotKey {}
+
+impl PartialEq for TemporalKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+}
+
+impl Eq for TemporalKey {}
+
+impl Drop for SnapshotKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SnapshotKey {}
+
+impl Drop for TemporalKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for TemporalKey {}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_material_is_redacted_in_debug_output() {
+        let temporal_key = TemporalKey([0xab; KEY_BYTE_SIZE]);
+        let snapshot_key = temporal_key.derive_snapshot_key();
+
+        assert_eq!(format!("{temporal_key:?}"), r#"TemporalKey("..")"#);
+        assert_eq!(format!("{snapshot_key:?}"), r#"SnapshotKey("..")"#);
+        assert_eq!(temporal_key, TemporalKey([0xab; KEY_BYTE_SIZE]));
+        assert_ne!(temporal_key, TemporalKey([0xac; KEY_BYTE_SIZE]));
     }
 }
 
@@ -1,9 +1,10 @@
-use super::{PrivateNodeHeader, TemporalKey};
+use super::PrivateNodeHeader;
 use crate::{
     error::FsError,
     private::{
         encrypted::Encrypted, forest::traits::PrivateForest, link::PrivateLink, AccessKey,
-        PrivateDirectory, PrivateFile, PrivateNodeContentSerializable, PrivateRef,
+        FileContent, KeyProvider, PrivateDirectory, PrivateFile, PrivateNodeContentSerializable,
+        PrivateRef,
     },
     traits::Id,
 };
@@ -21,7 +22,7 @@ use std::{
 };
 use wnfs_common::{
     utils::{Arc, CondSend},
-    BlockStore,
+    BlockStore, HashOutput,
 };
 use wnfs_nameaccumulator::Name;
 
@@ -55,6 +56,21 @@ pub enum PrivateNode {
     Dir(Arc<PrivateDirectory>),
 }
 
+/// An insertion into the private forest that was held back while storing
+/// nodes, so it can be applied together with others later.
+pub(crate) type DeferredInsertion = (Name, [Cid; 2]);
+
+/// Everything that is held back while storing nodes with deferred insertions.
+///
+/// Besides the forest insertions, this keeps the content CIDs of the stored
+/// directories. Those are only cached on the directories once the insertions
+/// were committed, so a failed commit can simply be retried.
+#[derive(Default)]
+pub(crate) struct DeferredStore {
+    pub(crate) insertions: Vec<DeferredInsertion>,
+    pub(crate) stored_dirs: Vec<(Arc<PrivateDirectory>, Cid)>,
+}
+
 //--------------------------------------------------------------------------------------------------
 // Implementations
 //--------------------------------------------------------------------------------------------------
@@ -141,6 +157,93 @@ impl PrivateNode {
         Ok(())
     }
 
+    /// Adds the labels of the external content blocks of all files in this
+    /// private sub tree to `labels`.
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    pub(crate) async fn collect_content_labels(
+        &self,
+        labels: &mut BTreeSet<HashOutput>,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<()> {
+        match self {
+            Self::File(file) => {
+                if let FileContent::External(content) = &file.content.content {
+                    let block_names = content.get_block_names();
+                    labels.extend(block_names.iter().map(|name| forest.get_label(name)));
+                }
+            }
+            Self::Dir(dir) => {
+                for private_link in dir.content.entries.values() {
+                    private_link
+                        .resolve_node(forest, store, Some(dir.header.name.clone()))
+                        .await?
+                        .collect_content_labels(labels, forest, store)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rotates the keys of all nodes in this private sub tree.
+    ///
+    /// Unlike `update_ancestry`, this also re-encrypts external file content under
+    /// fresh keys and drops the links to previous revisions, so no previously known
+    /// key gives access to the new revisions.
+    ///
+    /// The names of the labels the old revisions and content blocks are stored at
+    /// are added to `old_names`.
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    pub(crate) async fn rotate_keys(
+        &mut self,
+        parent_name: &Name,
+        old_names: &mut Vec<Name>,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        match self {
+            Self::File(file_rc) => {
+                let file = Arc::make_mut(file_rc);
+                old_names.push(file.header.get_revision_name());
+
+                file.prepare_key_rotation(parent_name, rng).await?;
+                file.content.previous.clear();
+
+                if let FileContent::External(content) = &file.content.content {
+                    old_names.extend(content.get_block_names());
+                    let content = content
+                        .rotate_key(file.header.get_name(), forest, store, rng)
+                        .await?;
+                    file.content.content = FileContent::External(content);
+                }
+            }
+            Self::Dir(dir_rc) => {
+                let dir = Arc::make_mut(dir_rc);
+                old_names.push(dir.header.get_revision_name());
+
+                let old_name = dir.header.name.clone();
+                dir.prepare_key_rotation(parent_name, rng);
+                dir.content.previous.clear();
+
+                let new_name = dir.header.name.clone();
+                for private_link in dir.content.entries.values_mut() {
+                    let mut node = private_link
+                        .resolve_node(forest, store, Some(old_name.clone()))
+                        .await?
+                        .clone();
+                    node.rotate_keys(&new_name, old_names, forest, store, rng)
+                        .await?;
+                    *private_link = PrivateLink::from(node);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Gets the header of the node.
     ///
     /// # Examples
@@ -514,8 +617,11 @@ impl PrivateNode {
 
     /// Tries to deserialize and decrypt a PrivateNode at provided PrivateRef
     /// from the PrivateForest.
-    pub(crate) async fn from_private_ref(
-        private_ref: &PrivateRef,
+    ///
+    /// The node is decrypted with the private ref's key provider, which is its
+    /// temporal key by default, see [`KeyProvider`].
+    pub async fn from_private_ref(
+        private_ref: &PrivateRef<impl KeyProvider>,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
         parent_name: Option<Name>,
@@ -533,37 +639,38 @@ impl PrivateNode {
 
     pub(crate) async fn from_cid(
         cid: Cid,
-        temporal_key: &TemporalKey,
+        temporal_key: &impl KeyProvider,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
         parent_name: Option<Name>,
     ) -> Result<PrivateNode> {
         let encrypted_bytes = store.get_block(&cid).await?;
-        let snapshot_key = temporal_key.derive_snapshot_key();
+        let snapshot_key = temporal_key.snapshot_key().await?;
         let bytes = snapshot_key.decrypt(&encrypted_bytes)?;
         let node: PrivateNodeContentSerializable = serde_ipld_dagcbor::from_slice(&bytes)?;
+        // Boxed, so that the futures of recursive path lookups stay small.
         Ok(match node {
             PrivateNodeContentSerializable::File(file) => {
-                let file = PrivateFile::from_serializable(
+                let file = Box::pin(PrivateFile::from_serializable(
                     file,
                     temporal_key,
                     cid,
                     forest,
                     store,
                     parent_name,
-                )
+                ))
                 .await?;
                 PrivateNode::File(Arc::new(file))
             }
             PrivateNodeContentSerializable::Dir(dir) => {
-                let dir = PrivateDirectory::from_serializable(
+                let dir = Box::pin(PrivateDirectory::from_serializable(
                     dir,
                     temporal_key,
                     cid,
                     forest,
                     store,
                     parent_name,
-                )
+                ))
                 .await?;
                 PrivateNode::Dir(Arc::new(dir))
             }
@@ -589,6 +696,41 @@ impl PrivateNode {
         }
     }
 
+    /// Stores this node without inserting anything into the forest.
+    ///
+    /// The forest insertions that are necessary for making the stored
+    /// nodes available are returned instead.
+    pub(crate) async fn store_deferred(
+        &self,
+        concurrency: usize,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<(PrivateRef, DeferredStore)> {
+        match self {
+            Self::File(file) => {
+                let (private_ref, insertions) = file.store_deferred(forest, store, rng).await?;
+                let deferred = DeferredStore {
+                    insertions,
+                    ..Default::default()
+                };
+
+                Ok((private_ref, deferred))
+            }
+            Self::Dir(dir) => {
+                let (private_ref, mut deferred) =
+                    dir.store_deferred(concurrency, forest, store, rng).await?;
+                if dir.content.persisted_as.get().is_none() {
+                    deferred
+                        .stored_dirs
+                        .push((Arc::clone(dir), private_ref.content_cid));
+                }
+
+                Ok((private_ref, deferred))
+            }
+        }
+    }
+
     /// Loads a node from the forest using provided access key.
     ///
     /// In case you're loading this node as a sub-node of another node, you need
@@ -653,6 +795,102 @@ impl PrivateNode {
         let private_ref = &self.store_and_get_private_ref(forest, store, rng).await?;
         Ok(AccessKey::Temporal(private_ref.into()))
     }
+
+    /// Like `store`, but returns a snapshot access key.
+    ///
+    /// A snapshot access key only gives read access to the revision that was stored,
+    /// see [`SnapshotNode`](crate::private::SnapshotNode).
+    pub async fn store_snapshot(
+        &self,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<AccessKey> {
+        let private_ref = &self.store_and_get_private_ref(forest, store, rng).await?;
+        Ok(AccessKey::Snapshot(private_ref.into()))
+    }
+
+    /// Like `store`, but encrypts and stores independent subtrees concurrently.
+    ///
+    /// At most `concurrency` children of each directory are stored at the same time.
+    /// Each child gets its own random number generator derived from `rng`, so given
+    /// a seeded `rng`, the result is reproducible.
+    ///
+    /// All insertions into the private forest are batched and applied at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::{
+    ///         PrivateDirectory, PrivateNode,
+    ///         forest::{hamt::HamtForest, traits::PrivateForest},
+    ///     },
+    ///     common::MemoryBlockStore,
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///     let dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+    ///
+    ///     for name in ["a.txt", "b.txt", "c.txt"] {
+    ///         dir.write(&[name.into()], true, Utc::now(), b"Hello".to_vec(), forest, store, rng)
+    ///             .await?;
+    ///     }
+    ///
+    ///     let access_key = dir.as_node().store_concurrent(8, forest, store, rng).await?;
+    ///     let node = PrivateNode::load(&access_key, forest, store, None).await?;
+    ///
+    ///     assert_eq!(node.as_dir()?.ls(&[], true, forest, store).await?.len(), 3);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn store_concurrent(
+        &self,
+        concurrency: usize,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<AccessKey> {
+        let (private_ref, deferred) = self.store_deferred(concurrency, forest, store, rng).await?;
+        deferred.commit(forest, store).await?;
+
+        Ok(AccessKey::Temporal((&private_ref).into()))
+    }
+}
+
+impl DeferredStore {
+    /// Applies the insertions to the forest and only then caches the content
+    /// CIDs of the stored directories.
+    pub(crate) async fn commit(
+        self,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<()> {
+        forest.put_encrypted_many(self.insertions, store).await?;
+
+        for (dir, content_cid) in self.stored_dirs {
+            dir.content
+                .persisted_as
+                .get_or_init(async { content_cid })
+                .await;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.insertions.extend(other.insertions);
+        self.stored_dirs.extend(other.stored_dirs);
+    }
 }
 
 impl Id for PrivateNode {
@@ -0,0 +1,581 @@
+//! Recovery of private roots with the help of guardians.
+//!
+//! A root access key is split into one recovery share per guardian with Shamir secret sharing over GF(256),
+//! so that any `threshold` of the shares are enough to rebuild it, while fewer shares reveal nothing about it.
+//! Each share is encrypted to a guardian's exchange key and stored in the private forest under a recovery share label,
+//! which is derived like the label of regular shares, but in a separate domain. To recover, guardians decrypt their shares
+//! and hand them to the owner, who combines them back into the access key.
+
+use crate::{
+    error::{FsError, RecoveryError},
+    is_readable_wnfs_version,
+    private::{
+        forest::traits::PrivateForest, AccessKey, EncodedExchangeKey, ExchangeKey, PrivateKey,
+        SnapshotKey,
+    },
+    WNFS_VERSION,
+};
+use anyhow::{anyhow, bail, Result};
+use rand_core::CryptoRngCore;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use wnfs_common::{utils::CondSend, BlockStore, CODEC_DAG_CBOR};
+use wnfs_nameaccumulator::{Name, NameSegment};
+use zeroize::{Zeroize, Zeroizing};
+
+//--------------------------------------------------------------------------------------------------
+// Constants
+//--------------------------------------------------------------------------------------------------
+
+/// The type tag of serialized recovery shares.
+const RECOVERY_SHARE_TYPE: &str = "wnfs/recovery/share";
+
+/// The domain separation info of recovery share label segments, which keeps
+/// recovery share labels apart from the labels of regular shares.
+const RECOVERY_SHARE_DSI: &str = "wnfs/recovery/share/label";
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// One of the shares a root access key is split into.
+///
+/// A single share doesn't reveal anything about the access key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveryShare {
+    threshold: u8,
+    index: u8,
+    value: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecoveryShareSerializable {
+    r#type: String,
+    version: Version,
+    threshold: u8,
+    index: u8,
+    #[serde(with = "serde_bytes")]
+    value: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedRecoveryShare {
+    #[serde(with = "serde_bytes")]
+    encrypted_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    encrypted_share: Vec<u8>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl RecoveryShare {
+    /// Splits an access key into `shares` recovery shares, any `threshold` of which
+    /// can be combined to get the access key back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use rand_chacha::ChaCha12Rng;
+    /// use rand_core::SeedableRng;
+    /// use wnfs::{
+    ///     private::{
+    ///         forest::{hamt::HamtForest, traits::PrivateForest},
+    ///         recovery::RecoveryShare,
+    ///         PrivateDirectory,
+    ///     },
+    ///     common::MemoryBlockStore,
+    /// };
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::default();
+    ///     let rng = &mut ChaCha12Rng::from_entropy();
+    ///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+    ///     let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+    ///     let access_key = dir.as_node().store(forest, store, rng).await?;
+    ///
+    ///     let shares = RecoveryShare::split(&access_key, 2, 3, rng)?;
+    ///
+    ///     assert_eq!(RecoveryShare::combine(&shares[1..])?, access_key);
+    ///     assert!(RecoveryShare::combine(&shares[..1]).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn split(
+        access_key: &AccessKey,
+        threshold: u8,
+        shares: u8,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Vec<Self>> {
+        if threshold == 0 || threshold > shares {
+            bail!(RecoveryError::InvalidThreshold { threshold, shares });
+        }
+
+        let secret = Zeroizing::new(access_key.to_bytes()?);
+        let mut recovery_shares = (1..=shares)
+            .map(|index| Self {
+                threshold,
+                index,
+                value: Vec::with_capacity(secret.len()),
+            })
+            .collect::<Vec<_>>();
+
+        let mut coefficients = vec![0; threshold as usize];
+        for &byte in secret.iter() {
+            coefficients[0] = byte;
+            rng.fill_bytes(&mut coefficients[1..]);
+
+            for share in recovery_shares.iter_mut() {
+                let y = coefficients
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, &c| gf_mul(acc, share.index) ^ c);
+                share.value.push(y);
+            }
+        }
+
+        coefficients.zeroize();
+
+        Ok(recovery_shares)
+    }
+
+    /// Rebuilds the access key from at least `threshold` distinct recovery shares.
+    pub fn combine(shares: &[Self]) -> Result<AccessKey> {
+        let Some(first) = shares.first() else {
+            bail!(RecoveryError::NoShares);
+        };
+
+        let mut distinct: Vec<&Self> = Vec::new();
+        for share in shares {
+            if share.threshold != first.threshold
+                || share.value.len() != first.value.len()
+                || share.index == 0
+            {
+                bail!(RecoveryError::InconsistentShares);
+            }
+
+            match distinct.iter().find(|s| s.index == share.index) {
+                Some(other) if other.value != share.value => {
+                    bail!(RecoveryError::InconsistentShares)
+                }
+                Some(_) => {}
+                None => distinct.push(share),
+            }
+        }
+
+        if distinct.len() < first.threshold as usize {
+            bail!(RecoveryError::NotEnoughShares {
+                threshold: first.threshold,
+                found: distinct.len(),
+            });
+        }
+
+        // Lagrange interpolation at x = 0.
+        let distinct = &distinct[..first.threshold as usize];
+        let basis = distinct
+            .iter()
+            .map(|share| {
+                distinct
+                    .iter()
+                    .filter(|other| other.index != share.index)
+                    .fold(1, |acc, other| {
+                        gf_mul(acc, gf_div(other.index, other.index ^ share.index))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let secret = Zeroizing::new(
+            (0..first.value.len())
+                .map(|i| {
+                    distinct
+                        .iter()
+                        .zip(&basis)
+                        .fold(0, |acc, (share, &b)| acc ^ gf_mul(share.value[i], b))
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        AccessKey::parse(secret).map_err(|_| anyhow!(RecoveryError::InconsistentShares))
+    }
+
+    /// Gets the number of shares needed to rebuild the access key.
+    pub fn get_threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Gets the index of this share, starting at 1.
+    pub fn get_index(&self) -> u8 {
+        self.index
+    }
+
+    /// Serializes the share, e.g. for handing it to the owner.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_ipld_dagcbor::to_vec(&RecoveryShareSerializable {
+            r#type: RECOVERY_SHARE_TYPE.into(),
+            version: WNFS_VERSION,
+            threshold: self.threshold,
+            index: self.index,
+            value: self.value.clone(),
+        })?)
+    }
+
+    /// Deserializes a share serialized with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let share: RecoveryShareSerializable = serde_ipld_dagcbor::from_slice(bytes)?;
+
+        if !is_readable_wnfs_version(&share.version) {
+            bail!(FsError::UnexpectedVersion(share.version));
+        }
+
+        if share.r#type != RECOVERY_SHARE_TYPE {
+            bail!(RecoveryError::InconsistentShares);
+        }
+
+        Ok(Self {
+            threshold: share.threshold,
+            index: share.index,
+            value: share.value,
+        })
+    }
+
+    /// Encrypts the share with a fresh key from `rng`, which is encrypted with the guardian's
+    /// exchange key.
+    pub async fn encrypt(
+        &self,
+        exchange_key: &impl ExchangeKey,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<Vec<u8>> {
+        let key = SnapshotKey::new(rng);
+        let encrypted_share = key.encrypt(&Zeroizing::new(self.to_bytes()?), rng)?;
+        let encrypted = EncryptedRecoveryShare {
+            encrypted_key: exchange_key.encrypt(key.as_bytes()).await?,
+            encrypted_share,
+        };
+
+        Ok(serde_ipld_dagcbor::to_vec(&encrypted)?)
+    }
+
+    /// Decrypts a share with the guardian's private key.
+    pub async fn decrypt(bytes: &[u8], guardian_key: &impl PrivateKey) -> Result<Self> {
+        let encrypted: EncryptedRecoveryShare = serde_ipld_dagcbor::from_slice(bytes)?;
+        let key = Zeroizing::new(guardian_key.decrypt(&encrypted.encrypted_key).await?);
+        let key = SnapshotKey(
+            key.as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("Invalid recovery share key"))?,
+        );
+
+        Self::from_bytes(&Zeroizing::new(key.decrypt(&encrypted.encrypted_share)?))
+    }
+}
+
+impl Debug for RecoveryShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecoveryShare")
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("value", &"..")
+            .finish()
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Functions
+//--------------------------------------------------------------------------------------------------
+
+/// Splits an access key into one recovery share per guardian, any `threshold` of which
+/// can rebuild it, and stores each share encrypted to its guardian's exchange key.
+///
+/// The shares are labeled with [`create_recovery_share_name`] at given share count. Like for
+/// `sharer::share`, the share count should not have been used for these guardians before.
+/// Recovery share labels don't collide with the labels of regular shares, so recovery shares
+/// don't show up in a guardian's share inbox.
+#[allow(clippy::too_many_arguments)]
+pub async fn distribute<K: ExchangeKey>(
+    access_key: &AccessKey,
+    threshold: u8,
+    share_count: u64,
+    sharer_root_did: &str,
+    guardian_exchange_keys: &[EncodedExchangeKey],
+    forest: &mut impl PrivateForest,
+    store: &impl BlockStore,
+    rng: &mut (impl CryptoRngCore + CondSend),
+) -> Result<()> {
+    let shares = u8::try_from(guardian_exchange_keys.len()).map_err(|_| {
+        RecoveryError::InvalidThreshold {
+            threshold,
+            shares: u8::MAX,
+        }
+    })?;
+
+    let recovery_shares = RecoveryShare::split(access_key, threshold, shares, rng)?;
+    for (share, public_key) in recovery_shares.iter().zip(guardian_exchange_keys) {
+        let exchange_key = K::from_encoded(public_key).await?;
+        let encrypted_share = share.encrypt(&exchange_key, rng).await?;
+        let share_label =
+            create_recovery_share_name(share_count, sharer_root_did, public_key.as_bytes(), forest);
+
+        let share_cid = store.put_block(encrypted_share, CODEC_DAG_CBOR).await?;
+
+        forest
+            .put_encrypted(&share_label, Some(share_cid), store)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Finds and decrypts the recovery share stored for a guardian at given share count.
+pub async fn receive(
+    share_count: u64,
+    sharer_root_did: &str,
+    guardian_exchange_key: &EncodedExchangeKey,
+    guardian_key: &impl PrivateKey,
+    forest: &impl PrivateForest,
+    store: &impl BlockStore,
+) -> Result<RecoveryShare> {
+    let share_label = create_recovery_share_name(
+        share_count,
+        sharer_root_did,
+        guardian_exchange_key.as_bytes(),
+        forest,
+    );
+
+    let Some(cids) = forest
+        .get_encrypted_by_hash(&forest.get_label(&share_label), store)
+        .await?
+    else {
+        bail!(RecoveryError::RecoveryShareNotFound);
+    };
+
+    for cid in cids {
+        let bytes = store.get_block(cid).await?;
+        if let Ok(share) = RecoveryShare::decrypt(&bytes, guardian_key).await {
+            return Ok(share);
+        }
+    }
+
+    bail!(RecoveryError::RecoveryShareNotFound)
+}
+
+/// Rebuilds the access key from the recovery shares of at least `threshold` guardians.
+pub fn recover(shares: &[RecoveryShare]) -> Result<AccessKey> {
+    RecoveryShare::combine(shares)
+}
+
+/// Generates the name for the recovery share of given guardian,
+/// at given count and from given sharer.
+pub fn create_recovery_share_name(
+    share_count: u64,
+    sharer_root_did: &str,
+    guardian_exchange_key: &[u8],
+    forest: &impl PrivateForest,
+) -> Name {
+    forest.empty_name().with_segments_added([
+        NameSegment::new_hashed(RECOVERY_SHARE_DSI, sharer_root_did.as_bytes()),
+        NameSegment::new_hashed(RECOVERY_SHARE_DSI, guardian_exchange_key),
+        NameSegment::new_hashed(RECOVERY_SHARE_DSI, share_count.to_le_bytes()),
+    ])
+}
+
+/// Multiplies two elements of GF(256) with the AES reduction polynomial, without branching on them.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = a >> 7;
+        a = (a << 1) ^ (0x1b & carry.wrapping_neg());
+        b >>= 1;
+    }
+
+    product
+}
+
+/// Divides two elements of GF(256), `b` must not be zero.
+fn gf_div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b, as the multiplicative group has order 255.
+    let mut inverse = 1;
+    let mut power = b;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            inverse = gf_mul(inverse, power);
+        }
+        power = gf_mul(power, power);
+        exponent >>= 1;
+    }
+
+    gf_mul(a, inverse)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::private::{
+        forest::hamt::HamtForest, share::recipient, AnyExchangeKey, PrivateDirectory, PrivateNode,
+        X25519PrivateKey,
+    };
+    use chrono::Utc;
+    use rand_chacha::ChaCha12Rng;
+    use rand_core::SeedableRng;
+    use testresult::TestResult;
+    use wnfs_common::MemoryBlockStore;
+
+    #[test]
+    fn gf_arithmetic_is_consistent() {
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(gf_div(1, a), a), 1);
+            assert_eq!(gf_div(gf_mul(a, 0x53), 0x53), a);
+        }
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+    }
+
+    #[async_std::test]
+    async fn any_threshold_shares_recover_the_access_key() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let dir = PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let access_key = dir.as_node().store(forest, store, rng).await?;
+
+        let shares = RecoveryShare::split(&access_key, 3, 5, rng)?;
+        assert!(shares.iter().all(|share| share.get_threshold() == 3));
+
+        for subset in [[0, 1, 2], [0, 2, 4], [4, 3, 1], [1, 2, 3]] {
+            let subset = subset.map(|i| shares[i].clone());
+            assert_eq!(recover(&subset)?, access_key);
+        }
+
+        let duplicated = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        let error = recover(&duplicated).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<RecoveryError>(),
+            Some(RecoveryError::NotEnoughShares {
+                threshold: 3,
+                found: 2
+            })
+        ));
+
+        let error = recover(&[]).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<RecoveryError>(),
+            Some(RecoveryError::NoShares)
+        ));
+
+        let bytes = shares[2].to_bytes()?;
+        assert_eq!(RecoveryShare::from_bytes(&bytes)?, shares[2]);
+
+        let debug = format!("{:?}", shares[2]);
+        assert!(debug.contains("threshold: 3"));
+        assert!(!debug.contains(&format!("{:?}", shares[2].value)));
+
+        assert!(RecoveryShare::split(&access_key, 0, 3, rng).is_err());
+        assert!(RecoveryShare::split(&access_key, 4, 3, rng).is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn guardians_can_recover_a_distributed_access_key() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let sharer_root_did = "did:key:z6MkqZjY";
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        root_dir
+            .write(
+                &["notes.txt".into()],
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await?;
+        let access_key = root_dir.as_node().store(forest, store, rng).await?;
+
+        let guardian_keys = (0..3).map(|_| X25519PrivateKey::new()).collect::<Vec<_>>();
+        let guardian_exchange_keys = guardian_keys
+            .iter()
+            .map(|key| key.get_public_key().to_encoded())
+            .collect::<Vec<_>>();
+
+        distribute::<AnyExchangeKey>(
+            &access_key,
+            2,
+            0,
+            sharer_root_did,
+            &guardian_exchange_keys,
+            forest,
+            store,
+            rng,
+        )
+        .await?;
+
+        let mut shares = Vec::new();
+        for i in [2, 0] {
+            let share = receive(
+                0,
+                sharer_root_did,
+                &guardian_exchange_keys[i],
+                &guardian_keys[i],
+                forest,
+                store,
+            )
+            .await?;
+            shares.push(share);
+        }
+
+        // A guardian can't decrypt someone else's share.
+        let label = create_recovery_share_name(
+            0,
+            sharer_root_did,
+            guardian_exchange_keys[1].as_bytes(),
+            forest,
+        );
+        assert!(forest.has(&label, store).await?);
+        assert!(receive(
+            0,
+            sharer_root_did,
+            &guardian_exchange_keys[1],
+            &guardian_keys[0],
+            forest,
+            store
+        )
+        .await
+        .is_err());
+
+        // Recovery shares don't show up as regular shares.
+        let inbox = recipient::list_inbox(
+            &[sharer_root_did],
+            &[(guardian_exchange_keys[0].clone(), guardian_keys[0].clone())],
+            forest,
+            store,
+        )
+        .await?;
+        assert!(inbox.is_empty());
+
+        let recovered = recover(&shares)?;
+        assert_eq!(recovered, access_key);
+
+        let dir = PrivateNode::load(&recovered, forest, store, None)
+            .await?
+            .as_dir()?;
+        let content = dir.read(&["notes.txt".into()], true, forest, store).await?;
+        assert_eq!(content, b"Hello");
+
+        Ok(())
+    }
+}
@@ -1,34 +1,410 @@
 //! The WNFS Shared Private Data Extension Specification is a protocol that allows users to exchange private data
 //! asynchronously when the other party may be offline using store-and-forward networks.
-//! The protocol uses asymmetric encryption, with RSA public keys, and is versioned to support multiple versions on the same file system.
+//! The protocol uses asymmetric encryption, with RSA or X25519 public keys, and is versioned to support multiple versions on the same file system.
 //! Public keys are widely distributed in a "exchange keys partition" and are grouped by device for easy selection by the sender.
-//! Asymmetrically encrypted access keys containing pointers to the private data are stored in the "Private Forest" and are labeled with a name filter that includes the sender's and recipient's information,
+//! Each device publishes a typed exchange key, or a bare RSA modulus for devices set up before key types existed.
+//! Share pointers, which hold an access key to the private data together with the sharer's DID, a suggested name and timestamps,
+//! are encrypted for the recipient and stored in the "Private Forest". They are labeled with a name filter that includes the sender's and recipient's information,
 //! as well as a counter.
 
+use crate::{
+    error::{FsError, ShareError},
+    is_readable_wnfs_version,
+    private::{
+        forest::traits::PrivateForest, AccessKey, EncodedExchangeKey, ExchangeKey, PrivateKey,
+        PrivateNode, SnapshotKey, SnapshotNode,
+    },
+    public::PublicDirectory,
+    WNFS_VERSION,
+};
+use anyhow::{bail, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use rand_core::CryptoRngCore;
+use recipient::{ShareKind, SharedNode};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use wnfs_common::{utils::CondSend, BlockStore, NodeType};
+use zeroize::Zeroizing;
+
 //--------------------------------------------------------------------------------------------------
 // Constants
 //--------------------------------------------------------------------------------------------------
 
-const EXCHANGE_KEY_NAME: &str = "v1.exchange_key";
+/// The file name of a bare RSA public key modulus in a device's exchange key directory.
+pub const EXCHANGE_KEY_NAME: &str = "v1.exchange_key";
+
+/// The file name of an exchange key prefixed with its key type in a device's exchange key directory.
+///
+/// See [`EncodedExchangeKey`](crate::private::EncodedExchangeKey).
+pub const TYPED_EXCHANGE_KEY_NAME: &str = "v2.exchange_key";
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// The payload that is encrypted for a recipient of a share.
+///
+/// Shares without an expiry are encoded as a bare [`AccessKey`], so they can be
+/// read by recipients that don't know about expiries.
+///
+/// Shares are written as [`SharePointer`]s now, which carry more information about
+/// the share. Payloads are still read from shares written before share pointers existed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharePayload {
+    pub access_key: AccessKey,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExpiringSharePayload {
+    access_key: AccessKey,
+    expires_at: i64,
+}
+
+/// A share pointer is what the recipient of a share receives.
+///
+/// It holds the access key to the shared node, together with some information
+/// about the share that the recipient can look at before loading the node.
+///
+/// Share pointers are encrypted with a fresh key, which is encrypted with the recipient's
+/// exchange key. Shares from before share pointers existed only hold an encrypted
+/// [`SharePayload`], they are read as share pointers without sharer and timestamp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharePointer {
+    pub access_key: AccessKey,
+    /// The root DID the sharer claims to have.
+    pub sharer_root_did: Option<String>,
+    /// The name the sharer suggests for the shared node.
+    pub suggested_name: Option<String>,
+    pub shared_at: Option<DateTime<Utc>>,
+    /// The time after which the recipient should refuse to receive the share.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharePointerSerializable {
+    r#type: String,
+    version: Version,
+    access_key: AccessKey,
+    sharer_root_did: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    suggested_name: Option<String>,
+    shared_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedSharePointer {
+    #[serde(with = "serde_bytes")]
+    encrypted_key: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    encrypted_pointer: Vec<u8>,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl SharePayload {
+    /// Creates a payload for a share that doesn't expire.
+    pub fn new(access_key: AccessKey) -> Self {
+        Self {
+            access_key,
+            expires_at: None,
+        }
+    }
+
+    /// Creates a payload for a share that recipients refuse to receive after given time.
+    ///
+    /// The expiry is stored with a precision of seconds.
+    pub fn with_expiry(access_key: AccessKey, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            access_key,
+            expires_at: Some(expires_at),
+        }
+    }
+
+    /// Returns whether the share has expired at given time.
+    pub fn is_expired(&self, time: DateTime<Utc>) -> bool {
+        self.expires_at
+            .map(|expires_at| time >= expires_at)
+            .unwrap_or(false)
+    }
+
+    /// Encodes the payload as DAG-CBOR.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(match self.expires_at {
+            None => self.access_key.to_bytes()?,
+            Some(expires_at) => serde_ipld_dagcbor::to_vec(&ExpiringSharePayload {
+                access_key: self.access_key.clone(),
+                expires_at: expires_at.timestamp(),
+            })?,
+        })
+    }
+
+    /// Decodes a payload encoded with `to_bytes`.
+    pub fn parse(bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        if let Ok(payload) = serde_ipld_dagcbor::from_slice::<ExpiringSharePayload>(bytes) {
+            let expires_at = Utc
+                .timestamp_opt(payload.expires_at, 0)
+                .single()
+                .ok_or_else(|| anyhow::anyhow!("Invalid share expiry"))?;
+
+            return Ok(Self::with_expiry(payload.access_key, expires_at));
+        }
+
+        Ok(Self::new(AccessKey::parse(bytes)?))
+    }
+}
+
+impl SharePointer {
+    /// Creates a share pointer to given access key.
+    pub fn new(access_key: AccessKey, sharer_root_did: &str, shared_at: DateTime<Utc>) -> Self {
+        Self::from_payload(SharePayload::new(access_key), sharer_root_did, shared_at)
+    }
+
+    /// Creates a share pointer to the access key of given payload, which expires with it.
+    pub fn from_payload(
+        payload: SharePayload,
+        sharer_root_did: &str,
+        shared_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            sharer_root_did: Some(sharer_root_did.to_string()),
+            shared_at: Some(shared_at),
+            ..payload.into()
+        }
+    }
+
+    /// Sets the name the sharer suggests for the shared node.
+    pub fn with_suggested_name(mut self, name: impl Into<String>) -> Self {
+        self.suggested_name = Some(name.into());
+        self
+    }
+
+    /// Makes recipients refuse to receive the share after given time.
+    ///
+    /// The expiry is stored with a precision of seconds.
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Returns either `NodeType::TemporalSharePointer` or `NodeType::SnapshotSharePointer`.
+    pub fn get_node_type(&self) -> NodeType {
+        match self.get_kind() {
+            ShareKind::Temporal => NodeType::TemporalSharePointer,
+            ShareKind::Snapshot => NodeType::SnapshotSharePointer,
+        }
+    }
+
+    /// Returns whether the share gives access to future revisions or only to a snapshot.
+    pub fn get_kind(&self) -> ShareKind {
+        match self.access_key {
+            AccessKey::Temporal(_) => ShareKind::Temporal,
+            AccessKey::Snapshot(_) => ShareKind::Snapshot,
+        }
+    }
+
+    /// Returns whether the share has expired at given time.
+    pub fn is_expired(&self, time: DateTime<Utc>) -> bool {
+        self.expires_at
+            .map(|expires_at| time >= expires_at)
+            .unwrap_or(false)
+    }
+
+    /// Loads the shared node from the sharer's forest.
+    ///
+    /// Shared snapshot access keys are loaded as `SharedNode::Snapshot`, which
+    /// refuses to look up any other revisions.
+    pub async fn load(
+        &self,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<SharedNode> {
+        Ok(match &self.access_key {
+            AccessKey::Temporal(_) => {
+                let private_ref = self.access_key.derive_private_ref()?;
+                SharedNode::Node(
+                    PrivateNode::from_private_ref(&private_ref, forest, store, None).await?,
+                )
+            }
+            AccessKey::Snapshot(access_key) => {
+                SharedNode::Snapshot(Box::new(SnapshotNode::load(access_key, store).await?))
+            }
+        })
+    }
+
+    /// Encrypts the share pointer for the holder of given exchange key.
+    ///
+    /// The pointer is encrypted with a key generated from `rng`.
+    pub async fn encrypt(
+        &self,
+        exchange_key: &impl ExchangeKey,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<Vec<u8>> {
+        let (Some(sharer_root_did), Some(shared_at)) = (&self.sharer_root_did, self.shared_at)
+        else {
+            bail!(ShareError::NoSharerOrRecipients)
+        };
+
+        let pointer = SharePointerSerializable {
+            r#type: self.get_node_type().to_string(),
+            version: WNFS_VERSION,
+            access_key: self.access_key.clone(),
+            sharer_root_did: sharer_root_did.clone(),
+            suggested_name: self.suggested_name.clone(),
+            shared_at: shared_at.timestamp(),
+            expires_at: self.expires_at.map(|expires_at| expires_at.timestamp()),
+        };
+
+        let key = SnapshotKey::new(rng);
+        let encrypted_pointer = key.encrypt(&serde_ipld_dagcbor::to_vec(&pointer)?, rng)?;
+        let encrypted = EncryptedSharePointer {
+            encrypted_key: exchange_key.encrypt(key.as_bytes()).await?,
+            encrypted_pointer,
+        };
+
+        Ok(serde_ipld_dagcbor::to_vec(&encrypted)?)
+    }
+
+    /// Decrypts a share pointer with the recipient's private key.
+    pub async fn decrypt(bytes: &[u8], recipient_key: &impl PrivateKey) -> Result<Self> {
+        let Ok(encrypted) = serde_ipld_dagcbor::from_slice::<EncryptedSharePointer>(bytes) else {
+            // Shares from before share pointers existed.
+            let payload = SharePayload::parse(recipient_key.decrypt(bytes).await?)?;
+            return Ok(payload.into());
+        };
+
+        let key = Zeroizing::new(recipient_key.decrypt(&encrypted.encrypted_key).await?);
+        let key = SnapshotKey(
+            key.as_slice()
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid share pointer key"))?,
+        );
+        let bytes = key.decrypt(&encrypted.encrypted_pointer)?;
+        let pointer: SharePointerSerializable = serde_ipld_dagcbor::from_slice(&bytes)?;
+
+        if !is_readable_wnfs_version(&pointer.version) {
+            bail!(FsError::UnexpectedVersion(pointer.version));
+        }
+
+        let timestamp = |seconds: i64| {
+            Utc.timestamp_opt(seconds, 0)
+                .single()
+                .ok_or_else(|| anyhow::anyhow!("Invalid share pointer timestamp"))
+        };
+
+        let decrypted = Self {
+            access_key: pointer.access_key,
+            sharer_root_did: Some(pointer.sharer_root_did),
+            suggested_name: pointer.suggested_name,
+            shared_at: Some(timestamp(pointer.shared_at)?),
+            expires_at: pointer.expires_at.map(timestamp).transpose()?,
+        };
+
+        let node_type = decrypted.get_node_type();
+        if pointer.r#type != node_type.to_string() {
+            bail!(FsError::UnexpectedNodeType(node_type));
+        }
+
+        Ok(decrypted)
+    }
+}
+
+impl From<SharePayload> for SharePointer {
+    fn from(payload: SharePayload) -> Self {
+        Self {
+            access_key: payload.access_key,
+            sharer_root_did: None,
+            suggested_name: None,
+            shared_at: None,
+            expires_at: payload.expires_at,
+        }
+    }
+}
 
 //--------------------------------------------------------------------------------------------------
 // Functions
 //--------------------------------------------------------------------------------------------------
 
+/// Reads the exchange keys that given device published in an exchange partition.
+///
+/// A typed exchange key comes before a bare RSA modulus. Typed keys that can't be
+/// decoded are skipped.
+pub(crate) async fn read_device_exchange_keys(
+    exchange_root: &PublicDirectory,
+    device: &str,
+    store: &impl BlockStore,
+) -> Result<Vec<EncodedExchangeKey>> {
+    let device_path = [device.to_string()];
+    let names = exchange_root
+        .ls(&device_path, store)
+        .await?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+    let mut keys = Vec::new();
+    if names.iter().any(|name| name == TYPED_EXCHANGE_KEY_NAME) {
+        let path = [device.to_string(), TYPED_EXCHANGE_KEY_NAME.into()];
+        if let Ok(key) = EncodedExchangeKey::decode(exchange_root.read(&path, store).await?) {
+            keys.push(key);
+        }
+    }
+
+    if names.iter().any(|name| name == EXCHANGE_KEY_NAME) {
+        let path = [device.to_string(), EXCHANGE_KEY_NAME.into()];
+        let modulus = exchange_root.read(&path, store).await?;
+        keys.push(EncodedExchangeKey::from_legacy_modulus(modulus));
+    }
+
+    Ok(keys)
+}
+
 pub mod sharer {
-    use super::EXCHANGE_KEY_NAME;
+    use super::{read_device_exchange_keys, SharePayload, SharePointer};
     use crate::{
-        private::{forest::traits::PrivateForest, AccessKey, ExchangeKey, PublicKeyModulus},
+        error::ShareError,
+        private::{
+            forest::traits::PrivateForest, AccessKey, EncodedExchangeKey, ExchangeKey,
+            PrivateDirectory,
+        },
         public::PublicLink,
     };
     use anyhow::Result;
     use async_stream::try_stream;
+    use chrono::{DateTime, Utc};
     use futures::{Stream, TryStreamExt};
-    use wnfs_common::{BlockStore, CODEC_RAW};
+    use rand_core::CryptoRngCore;
+    use wnfs_common::{
+        utils::{Arc, CondSend},
+        BlockStore, CODEC_DAG_CBOR,
+    };
     use wnfs_nameaccumulator::{Name, NameSegment};
 
+    /// The status of a share from the sharer's point of view.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ShareStatus {
+        /// There is no share at the share label.
+        NotShared,
+        /// An access key is waiting at the share label.
+        /// There is no way to tell whether the recipient already received it.
+        Pending,
+        /// The share was withdrawn with `unshare`.
+        Revoked,
+    }
+
     /// Encrypts and shares a access key with multiple recipients using their
     /// exchange keys and stores the shares in the sharer's private forest.
+    ///
+    /// Only exchange keys of types supported by `K` are shared with. The share pointer
+    /// is timestamped with the current time, use `share_pointer` to add more information.
     #[allow(clippy::too_many_arguments)]
     pub async fn share<K: ExchangeKey>(
         access_key: &AccessKey,
@@ -37,33 +413,186 @@ pub mod sharer {
         recipient_exchange_root: PublicLink,
         forest: &mut impl PrivateForest,
         store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        share_payload::<K>(
+            &SharePayload::new(access_key.clone()),
+            share_count,
+            sharer_root_did,
+            recipient_exchange_root,
+            forest,
+            store,
+            rng,
+        )
+        .await
+    }
+
+    /// Like `share`, but recipients refuse to receive the share after given time.
+    ///
+    /// The expiry is only enforced by well-behaved recipients. Use `unshare` to make
+    /// sure the shared node's future revisions can't be read anymore.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn share_with_expiry<K: ExchangeKey>(
+        access_key: &AccessKey,
+        expires_at: DateTime<Utc>,
+        share_count: u64,
+        sharer_root_did: &str,
+        recipient_exchange_root: PublicLink,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        share_payload::<K>(
+            &SharePayload::with_expiry(access_key.clone(), expires_at),
+            share_count,
+            sharer_root_did,
+            recipient_exchange_root,
+            forest,
+            store,
+            rng,
+        )
+        .await
+    }
+
+    /// Encrypts and shares a payload with all of the recipient's devices.
+    ///
+    /// The payload is shared in a share pointer that is timestamped with the current time.
+    /// Share pointers are encrypted with keys generated from `rng`.
+    pub async fn share_payload<K: ExchangeKey>(
+        payload: &SharePayload,
+        share_count: u64,
+        sharer_root_did: &str,
+        recipient_exchange_root: PublicLink,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        let pointer = SharePointer::from_payload(payload.clone(), sharer_root_did, Utc::now());
+        share_pointer::<K>(
+            &pointer,
+            share_count,
+            recipient_exchange_root,
+            forest,
+            store,
+            rng,
+        )
+        .await
+    }
+
+    /// Encrypts and shares a share pointer with all of the recipient's devices.
+    ///
+    /// The share is labeled with the sharer root DID from the pointer.
+    pub async fn share_pointer<K: ExchangeKey>(
+        pointer: &SharePointer,
+        share_count: u64,
+        recipient_exchange_root: PublicLink,
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
     ) -> Result<()> {
-        let mut exchange_keys = fetch_exchange_keys(recipient_exchange_root, store).await;
-        let encoded_key = &serde_ipld_dagcbor::to_vec(access_key)?;
+        let Some(sharer_root_did) = &pointer.sharer_root_did else {
+            return Err(ShareError::NoSharerOrRecipients.into());
+        };
 
-        while let Some(public_key_modulus) = exchange_keys.try_next().await? {
-            let exchange_key = K::from_modulus(public_key_modulus.as_ref()).await?;
-            let encrypted_key = exchange_key.encrypt(encoded_key).await?;
+        let mut exchange_keys = fetch_exchange_keys::<K>(recipient_exchange_root, store).await;
+
+        while let Some(public_key) = exchange_keys.try_next().await? {
+            let exchange_key = K::from_encoded(&public_key).await?;
+            let encrypted_pointer = pointer.encrypt(&exchange_key, rng).await?;
             let share_label =
-                create_share_name(share_count, sharer_root_did, &public_key_modulus, forest);
+                create_share_name(share_count, sharer_root_did, public_key.as_bytes(), forest);
 
-            let access_key_cid = store.put_block(encrypted_key, CODEC_RAW).await?;
+            let pointer_cid = store.put_block(encrypted_pointer, CODEC_DAG_CBOR).await?;
 
             forest
-                .put_encrypted(&share_label, Some(access_key_cid), store)
+                .put_encrypted(&share_label, Some(pointer_cid), store)
                 .await?;
         }
 
         Ok(())
     }
 
+    /// Withdraws a share and revokes access to the shared node at given path.
+    ///
+    /// The encrypted access keys are removed from the share labels of all of the recipient's
+    /// devices with exchange keys supported by `K`. The labels themselves stay in the forest
+    /// without any access keys, so later share counters can still be found, and `share_status`
+    /// can tell that the share was revoked. Merging in a copy of the forest from before the
+    /// share was withdrawn brings the access keys back.
+    ///
+    /// As recipients may have received the share already, the keys of the shared node's
+    /// subtree are rotated with [`PrivateDirectory::revoke`], so future revisions can't be
+    /// read with the shared access key. This affects everyone else the node was shared with
+    /// as well. `root_dir` needs to be stored afterwards.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn unshare<K: ExchangeKey>(
+        share_count: u64,
+        sharer_root_did: &str,
+        recipient_exchange_root: PublicLink,
+        root_dir: &mut Arc<PrivateDirectory>,
+        path_segments: &[String],
+        forest: &mut impl PrivateForest,
+        store: &impl BlockStore,
+        rng: &mut (impl CryptoRngCore + CondSend),
+    ) -> Result<()> {
+        let exchange_keys = fetch_exchange_keys::<K>(recipient_exchange_root, store)
+            .await
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for public_key in exchange_keys {
+            let share_label =
+                create_share_name(share_count, sharer_root_did, public_key.as_bytes(), forest);
+
+            if forest
+                .remove_encrypted(&share_label, store)
+                .await?
+                .is_some()
+            {
+                forest.put_encrypted(&share_label, None, store).await?;
+            }
+        }
+
+        root_dir
+            .revoke(path_segments, true, false, forest, store, rng)
+            .await
+    }
+
+    /// Gets the status of the share at given counter from given sharer to the recipient's
+    /// exchange key.
+    pub async fn share_status(
+        share_count: u64,
+        sharer_root_did: &str,
+        recipient_exchange_key: &[u8],
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<ShareStatus> {
+        let share_label =
+            create_share_name(share_count, sharer_root_did, recipient_exchange_key, forest);
+
+        Ok(
+            match forest
+                .get_encrypted_by_hash(&forest.get_label(&share_label), store)
+                .await?
+            {
+                None => ShareStatus::NotShared,
+                Some(cids) if cids.is_empty() => ShareStatus::Revoked,
+                Some(_) => ShareStatus::Pending,
+            },
+        )
+    }
+
     /// Fetches the exchange keys of recipients using their exchange root, resolve the root_dir,
     /// search for the exchange key, and read the exchange key's cid in the recipient's store and
     /// yield the exchange key's value.
-    pub async fn fetch_exchange_keys(
+    ///
+    /// Yields at most one key per device, the first one of a type supported by `K`.
+    /// Typed exchange keys are preferred over bare RSA moduli, and keys of unknown
+    /// types are skipped.
+    pub async fn fetch_exchange_keys<K: ExchangeKey>(
         recipient_exchange_root: PublicLink,
         store: &impl BlockStore,
-    ) -> impl Stream<Item = Result<PublicKeyModulus>> + '_ {
+    ) -> impl Stream<Item = Result<EncodedExchangeKey>> + '_ {
         Box::pin(try_stream! {
             let root_dir = recipient_exchange_root
                 .resolve_value(store)
@@ -72,12 +601,9 @@ pub mod sharer {
 
             let devices = root_dir.ls(&[], store).await?;
             for (device, _) in devices {
-                let value = root_dir.ls(&[device.clone()], store).await?;
-                for (name, _) in value {
-                    if name == EXCHANGE_KEY_NAME {
-                        yield root_dir.read(&[device, name], store).await?;
-                        break
-                    }
+                let keys = read_device_exchange_keys(&root_dir, &device, store).await?;
+                if let Some(key) = keys.into_iter().find(|key| K::supports(key.key_type())) {
+                    yield key;
                 }
             }
         })
@@ -100,16 +626,61 @@ pub mod sharer {
 }
 
 pub mod recipient {
-    use super::sharer;
+    use super::{sharer, SharePointer};
     use crate::{
-        error::ShareError,
-        private::{forest::traits::PrivateForest, AccessKey, PrivateKey, PrivateNode},
+        error::{AccessKeyError, ShareError},
+        private::{
+            forest::traits::PrivateForest, AccessKey, EncodedExchangeKey, PrivateKey, PrivateNode,
+            PrivateNodeContentSerializable, SnapshotNode,
+        },
     };
-    use anyhow::Result;
-    use wnfs_common::BlockStore;
-    use wnfs_hamt::Hasher;
+    use anyhow::{bail, Result};
+    use chrono::{DateTime, Utc};
+    use libipld_core::cid::Cid;
+    use wnfs_common::{BlockStore, NodeType};
     use wnfs_nameaccumulator::Name;
 
+    /// Whether a share grants access to the shared node's future revisions or only
+    /// to the revision it was shared at.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ShareKind {
+        Temporal,
+        Snapshot,
+    }
+
+    /// A node received from a share.
+    #[derive(Debug, Clone)]
+    pub enum SharedNode {
+        /// A node shared with a temporal access key, which also gives access
+        /// to the node's future revisions.
+        Node(PrivateNode),
+        /// A single revision shared with a snapshot access key.
+        Snapshot(Box<SnapshotNode>),
+    }
+
+    /// A share found in a recipient's inbox.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ShareEntry {
+        /// The root DID of the sharer whose share label the share was found at.
+        pub sharer_root_did: String,
+        /// The share counter the share was found at.
+        pub counter: u64,
+        /// The recipient's exchange key the share was encrypted for.
+        pub exchange_key: EncodedExchangeKey,
+        /// The CID of the encrypted share pointer.
+        pub cid: Cid,
+        /// Either `NodeType::PrivateFile` or `NodeType::PrivateDirectory`.
+        pub node_type: NodeType,
+        pub kind: ShareKind,
+        pub access_key: AccessKey,
+        /// The time after which the recipient should refuse to receive the share.
+        pub expires_at: Option<DateTime<Utc>>,
+        /// The name the sharer suggests for the shared node.
+        pub suggested_name: Option<String>,
+        /// When the share was made, according to the sharer.
+        pub shared_at: Option<DateTime<Utc>>,
+    }
+
     /// Seeks to the latest share counter that is populated.
     pub async fn find_latest_share_counter(
         share_count_start: u64,
@@ -143,58 +714,262 @@ pub mod recipient {
         Ok(Some(share_count_start + limit - 1))
     }
 
+    /// Like `find_latest_share_counter`, but without a limit.
+    ///
+    /// Probes counters at exponentially growing distances from `share_count_start` until
+    /// it finds an unpopulated one, then binary searches for the last populated counter
+    /// in between. This needs a logarithmic number of lookups, but assumes share counters
+    /// are populated without gaps.
+    pub async fn search_latest_share_counter(
+        share_count_start: u64,
+        recipient_exchange_key: &[u8],
+        sharer_root_did: &str,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Option<u64>> {
+        let has_share = |share_count: u64| {
+            let share_label = sharer::create_share_name(
+                share_count,
+                sharer_root_did,
+                recipient_exchange_key,
+                forest,
+            );
+            async move { forest.has(&share_label, store).await }
+        };
+
+        if !has_share(share_count_start).await? {
+            return Ok(None);
+        }
+
+        // Invariant: `low` is populated, `high` is not.
+        let mut low = share_count_start;
+        let mut step = 1u64;
+        let mut high = loop {
+            let Some(candidate) = low.checked_add(step) else {
+                break u64::MAX;
+            };
+
+            if !has_share(candidate).await? {
+                break candidate;
+            }
+
+            low = candidate;
+            step = step.saturating_mul(2);
+        };
+
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if has_share(mid).await? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Ok(Some(low))
+    }
+
+    impl SharedNode {
+        /// Returns true if only a single revision was shared.
+        pub fn is_snapshot(&self) -> bool {
+            matches!(self, Self::Snapshot(_))
+        }
+
+        /// Gets the shared private node.
+        ///
+        /// Fails with `AccessKeyError::SnapshotOnly` if only a snapshot was shared.
+        pub fn into_node(self) -> Result<PrivateNode> {
+            match self {
+                Self::Node(node) => Ok(node),
+                Self::Snapshot(_) => bail!(AccessKeyError::SnapshotOnly),
+            }
+        }
+
+        /// Gets the latest revision of the shared node.
+        ///
+        /// Fails with `AccessKeyError::SnapshotOnly` if only a snapshot was shared.
+        pub async fn search_latest(
+            &self,
+            forest: &impl PrivateForest,
+            store: &impl BlockStore,
+        ) -> Result<PrivateNode> {
+            match self {
+                Self::Node(node) => node.search_latest(forest, store).await,
+                Self::Snapshot(snapshot) => snapshot.search_latest(),
+            }
+        }
+    }
+
+    /// Lists all shares that the given sharers made with any of the recipient's exchange keys.
+    ///
+    /// Each exchange key needs to be given as published, together with the private key
+    /// to decrypt shares with. The latest share counter is found with
+    /// `search_latest_share_counter`.
+    ///
+    /// A share label can hold multiple encrypted access keys, e.g. after merging forests
+    /// with concurrent shares. An entry is returned for each of them that can be decrypted,
+    /// the others are skipped. Expired shares are listed as well.
+    pub async fn list_inbox<K: PrivateKey>(
+        sharer_root_dids: &[impl AsRef<str>],
+        recipient_keys: &[(EncodedExchangeKey, K)],
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Vec<ShareEntry>> {
+        let mut entries = Vec::new();
+        for sharer_root_did in sharer_root_dids {
+            let sharer_root_did = sharer_root_did.as_ref();
+            for (exchange_key, private_key) in recipient_keys {
+                let Some(latest) = search_latest_share_counter(
+                    0,
+                    exchange_key.as_bytes(),
+                    sharer_root_did,
+                    forest,
+                    store,
+                )
+                .await?
+                else {
+                    continue;
+                };
+
+                for counter in 0..=latest {
+                    let share_label = sharer::create_share_name(
+                        counter,
+                        sharer_root_did,
+                        exchange_key.as_bytes(),
+                        forest,
+                    );
+
+                    for (cid, pointer) in
+                        decrypt_shares(&share_label, private_key, forest, store).await?
+                    {
+                        let Ok(node_type) = load_node_type(&pointer.access_key, store).await else {
+                            continue;
+                        };
+
+                        entries.push(ShareEntry {
+                            sharer_root_did: sharer_root_did.to_string(),
+                            counter,
+                            exchange_key: exchange_key.clone(),
+                            cid,
+                            node_type,
+                            kind: pointer.get_kind(),
+                            access_key: pointer.access_key,
+                            expires_at: pointer.expires_at,
+                            suggested_name: pointer.suggested_name,
+                            shared_at: pointer.shared_at,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
     /// Lets a recipient receive a share from a sharer using the sharer's forest and store.
-    /// The recipient's private forest and store are used to store the share.
+    ///
+    /// If there are multiple encrypted access keys at the share label, the first one
+    /// that can be decrypted and hasn't expired at given time is used.
+    ///
+    /// Shares of snapshot access keys are returned as `SharedNode::Snapshot`, which
+    /// refuses to look up any other revisions.
     pub async fn receive_share(
         share_label: &Name,
         recipient_key: &impl PrivateKey,
+        time: DateTime<Utc>,
         forest: &impl PrivateForest,
         store: &impl BlockStore,
-    ) -> Result<PrivateNode> {
-        // Get cid to encrypted payload from sharer's forest using share_label
-        let access_key_cid = forest
-            .get_encrypted_by_hash(
-                &blake3::Hasher::hash(&forest.get_accumulated_name(share_label)),
-                store,
-            )
+    ) -> Result<SharedNode> {
+        receive_share_pointer(share_label, recipient_key, time, forest, store)
             .await?
-            .ok_or(ShareError::AccessKeyNotFound)?
-            .first()
-            .ok_or(ShareError::AccessKeyNotFound)?;
-
-        // Get encrypted access key from store using cid
-        let encrypted_access_key = store.get_block(access_key_cid).await?.to_vec();
+            .load(forest, store)
+            .await
+    }
 
-        // Decrypt access key using recipient's private key and decode it.
-        let access_key: AccessKey =
-            serde_ipld_dagcbor::from_slice(&recipient_key.decrypt(&encrypted_access_key).await?)?;
+    /// Like `receive_share`, but returns the share pointer without loading the shared node.
+    ///
+    /// Use [`SharePointer::load`] to load the shared node.
+    pub async fn receive_share_pointer(
+        share_label: &Name,
+        recipient_key: &impl PrivateKey,
+        time: DateTime<Utc>,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<SharePointer> {
+        let pointers = decrypt_shares(share_label, recipient_key, forest, store).await?;
+        if let Some((_, pointer)) = pointers.iter().find(|(_, p)| !p.is_expired(time)) {
+            return Ok(pointer.clone());
+        }
 
-        // Use decrypted key to get cid to encrypted node in sharer's forest.
-        PrivateNode::from_private_ref(&access_key.derive_private_ref()?, forest, store, None).await
+        match pointers.iter().filter_map(|(_, p)| p.expires_at).max() {
+            Some(expires_at) => bail!(ShareError::ShareExpired(expires_at)),
+            None => bail!(ShareError::AccessKeyNotFound),
+        }
     }
-}
 
-//--------------------------------------------------------------------------------------------------
-// Tests
-//--------------------------------------------------------------------------------------------------
+    /// Decrypts all share pointers stored at given share label that can be decrypted
+    /// with the recipient's key.
+    async fn decrypt_shares(
+        share_label: &Name,
+        recipient_key: &impl PrivateKey,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Vec<(Cid, SharePointer)>> {
+        let Some(cids) = forest
+            .get_encrypted_by_hash(&forest.get_label(share_label), store)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut pointers = Vec::new();
+        for cid in cids {
+            let bytes = store.get_block(cid).await?;
+            if let Ok(pointer) = SharePointer::decrypt(&bytes, recipient_key).await {
+                pointers.push((*cid, pointer));
+            }
+        }
+
+        Ok(pointers)
+    }
+
+    /// Finds out whether an access key points to a file or a directory.
+    async fn load_node_type(access_key: &AccessKey, store: &impl BlockStore) -> Result<NodeType> {
+        let encrypted_bytes = store.get_block(access_key.get_content_cid()).await?;
+        let bytes = access_key.get_snapshot_key().decrypt(&encrypted_bytes)?;
+        let node: PrivateNodeContentSerializable = serde_ipld_dagcbor::from_slice(&bytes)?;
+
+        Ok(match node {
+            PrivateNodeContentSerializable::File(_) => NodeType::PrivateFile,
+            PrivateNodeContentSerializable::Dir(_) => NodeType::PrivateDirectory,
+        })
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
     use super::{
-        recipient::{self, find_latest_share_counter},
-        sharer, EXCHANGE_KEY_NAME,
+        recipient::{self, find_latest_share_counter, search_latest_share_counter, ShareKind},
+        sharer::{self, ShareStatus},
+        SharePayload, SharePointer, EXCHANGE_KEY_NAME, TYPED_EXCHANGE_KEY_NAME,
     };
+    use crate::error::{AccessKeyError, ShareError};
     use crate::{
         private::{
             forest::{hamt::HamtForest, traits::PrivateForest},
-            AccessKey, PrivateDirectory, RsaPublicKey,
+            AccessKey, AnyExchangeKey, ExchangeKey, PrivateDirectory, PrivateFile, RsaPrivateKey,
+            RsaPublicKey, SnapshotAccessKey, X25519PrivateKey, X25519PublicKey,
         },
-        public::PublicLink,
+        public::{PublicDirectory, PublicLink},
     };
-    use chrono::Utc;
+    use chrono::{Duration, TimeZone, Utc};
     use rand_chacha::ChaCha12Rng;
     use rand_core::SeedableRng;
-    use wnfs_common::{utils::Arc, MemoryBlockStore};
+    use wnfs_common::{utils::Arc, MemoryBlockStore, NodeType};
 
     mod helper {
         use crate::{
@@ -290,6 +1065,7 @@ mod tests {
             PublicLink::with_rc_dir(recipient_exchange_root),
             forest,
             store,
+            rng,
         )
         .await
         .unwrap();
@@ -306,12 +1082,99 @@ mod tests {
         );
 
         // Grab node using share label.
-        let node = recipient::receive_share(&share_label, &recipient_key, forest, store)
+        let node =
+            recipient::receive_share(&share_label, &recipient_key, Utc::now(), forest, store)
+                .await
+                .unwrap();
+
+        // Assert node is the same as the original.
+        assert_eq!(node.into_node().unwrap().as_dir().unwrap(), sharer_dir);
+    }
+
+    #[async_std::test]
+    async fn shares_only_with_supported_exchange_key_types() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let sharer_root_did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let sharer_dir = helper::create_sharer_dir(forest, store, rng).await.unwrap();
+        let access_key = sharer_dir
+            .as_node()
+            .store(forest, store, rng)
             .await
             .unwrap();
 
-        // Assert node is the same as the original.
-        assert_eq!(node.as_dir().unwrap(), sharer_dir);
+        // One device with a legacy RSA key, one with an X25519 key, one with an unknown key type.
+        let rsa_key = RsaPrivateKey::new().unwrap();
+        let rsa_modulus = rsa_key.get_public_key().get_public_key_modulus().unwrap();
+        let x25519_key = X25519PrivateKey::new();
+        let x25519_public = x25519_key.get_public_key().to_encoded();
+
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        for (device, name, key) in [
+            ("device1", EXCHANGE_KEY_NAME, rsa_modulus.clone()),
+            (
+                "device2",
+                TYPED_EXCHANGE_KEY_NAME,
+                x25519_public.as_bytes().to_vec(),
+            ),
+            (
+                "device3",
+                TYPED_EXCHANGE_KEY_NAME,
+                vec![0xff, 0xff, 0x03, 1, 2, 3],
+            ),
+        ] {
+            exchange_root
+                .write(&[device.into(), name.into()], key, Utc::now(), store)
+                .await
+                .unwrap();
+        }
+
+        sharer::share::<X25519PublicKey>(
+            &access_key,
+            0,
+            sharer_root_did,
+            PublicLink::with_rc_dir(Arc::clone(&exchange_root)),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        let rsa_label = sharer::create_share_name(0, sharer_root_did, &rsa_modulus, forest);
+        let x25519_label =
+            sharer::create_share_name(0, sharer_root_did, x25519_public.as_bytes(), forest);
+        assert!(!forest.has(&rsa_label, store).await.unwrap());
+        let node = recipient::receive_share(&x25519_label, &x25519_key, Utc::now(), forest, store)
+            .await
+            .unwrap();
+        assert_eq!(node.into_node().unwrap().as_dir().unwrap(), sharer_dir);
+
+        sharer::share::<AnyExchangeKey>(
+            &access_key,
+            1,
+            sharer_root_did,
+            PublicLink::with_rc_dir(exchange_root),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        let rsa_label = sharer::create_share_name(1, sharer_root_did, &rsa_modulus, forest);
+        let x25519_label =
+            sharer::create_share_name(1, sharer_root_did, x25519_public.as_bytes(), forest);
+        let node = recipient::receive_share(&rsa_label, &rsa_key, Utc::now(), forest, store)
+            .await
+            .unwrap();
+        assert_eq!(node.into_node().unwrap().as_dir().unwrap(), sharer_dir);
+        let node = recipient::receive_share(&x25519_label, &x25519_key, Utc::now(), forest, store)
+            .await
+            .unwrap();
+        assert_eq!(node.into_node().unwrap().as_dir().unwrap(), sharer_dir);
     }
 
     #[async_std::test]
@@ -334,6 +1197,17 @@ mod tests {
         let deserialized: AccessKey = serde_ipld_dagcbor::from_slice(&serialized).unwrap();
 
         assert_eq!(access_key, deserialized);
+
+        let expires_at = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let payload = SharePayload::with_expiry(access_key.clone(), expires_at);
+        let serialized = payload.to_bytes().unwrap();
+
+        assert!(serialized.len() <= 190);
+        assert_eq!(SharePayload::parse(&serialized).unwrap(), payload);
+        assert_eq!(
+            SharePayload::parse(access_key.to_bytes().unwrap()).unwrap(),
+            SharePayload::new(access_key)
+        );
     }
 
     #[async_std::test]
@@ -388,6 +1262,7 @@ mod tests {
                 PublicLink::with_rc_dir(Arc::clone(&recipient_exchange_root)),
                 forest,
                 store,
+                rng,
             )
             .await
             .unwrap();
@@ -407,4 +1282,538 @@ mod tests {
         // We expect the count to be the latest share
         assert_eq!(max_share_count, Some(expected_max_share_count));
     }
+
+    #[async_std::test]
+    async fn search_latest_share_counter_matches_linear_search() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let sharer_root_did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let recipient_key = X25519PrivateKey::new();
+        let exchange_key = recipient_key.get_public_key().to_encoded();
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        exchange_root
+            .write(
+                &["device1".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+                exchange_key.as_bytes().to_vec(),
+                Utc::now(),
+                store,
+            )
+            .await
+            .unwrap();
+
+        let latest =
+            search_latest_share_counter(0, exchange_key.as_bytes(), sharer_root_did, forest, store)
+                .await
+                .unwrap();
+        assert_eq!(latest, None);
+
+        let dir =
+            PrivateDirectory::new_and_store(&forest.empty_name(), Utc::now(), forest, store, rng)
+                .await
+                .unwrap();
+        let access_key = dir.as_node().store(forest, store, rng).await.unwrap();
+
+        for i in 0..37 {
+            sharer::share::<X25519PublicKey>(
+                &access_key,
+                i,
+                sharer_root_did,
+                PublicLink::with_rc_dir(Arc::clone(&exchange_root)),
+                forest,
+                store,
+                rng,
+            )
+            .await
+            .unwrap();
+
+            let latest = search_latest_share_counter(
+                0,
+                exchange_key.as_bytes(),
+                sharer_root_did,
+                forest,
+                store,
+            )
+            .await
+            .unwrap();
+            let expected = find_latest_share_counter(
+                0,
+                100,
+                exchange_key.as_bytes(),
+                sharer_root_did,
+                forest,
+                store,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(latest, Some(i));
+            assert_eq!(latest, expected);
+        }
+    }
+
+    #[async_std::test]
+    async fn list_inbox_finds_shares_from_all_known_sharers() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let alice = "did:key:alice";
+        let bob = "did:key:bob";
+        let recipient_key = X25519PrivateKey::new();
+        let exchange_key = recipient_key.get_public_key().to_encoded();
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        exchange_root
+            .write(
+                &["device1".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+                exchange_key.as_bytes().to_vec(),
+                Utc::now(),
+                store,
+            )
+            .await
+            .unwrap();
+        let exchange_root = PublicLink::with_rc_dir(exchange_root);
+
+        let dir = helper::create_sharer_dir(forest, store, rng).await.unwrap();
+        let dir_key = dir.as_node().store(forest, store, rng).await.unwrap();
+        let file = PrivateFile::with_content_rc(
+            &forest.empty_name(),
+            Utc::now(),
+            b"Hello".to_vec(),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+        let file_ref = file
+            .as_node()
+            .store_and_get_private_ref(forest, store, rng)
+            .await
+            .unwrap();
+        let file_key = AccessKey::Snapshot(SnapshotAccessKey::from(&file_ref));
+
+        // Alice shares the directory twice at the same counter, e.g. from two devices.
+        for (sharer, counter, access_key) in [
+            (alice, 0, &dir_key),
+            (alice, 1, &file_key),
+            (alice, 1, &dir_key),
+            (bob, 0, &file_key),
+        ] {
+            sharer::share::<X25519PublicKey>(
+                access_key,
+                counter,
+                sharer,
+                exchange_root.clone(),
+                forest,
+                store,
+                rng,
+            )
+            .await
+            .unwrap();
+        }
+
+        let entries = recipient::list_inbox(
+            &[alice, bob, "did:key:carol"],
+            &[(exchange_key, recipient_key)],
+            forest,
+            store,
+        )
+        .await
+        .unwrap();
+
+        let mut summary = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.sharer_root_did.as_str(),
+                    entry.counter,
+                    entry.node_type,
+                    entry.kind,
+                )
+            })
+            .collect::<Vec<_>>();
+        summary.sort_by_key(|(sharer, counter, node_type, _)| {
+            (*sharer, *counter, node_type.to_string())
+        });
+
+        assert_eq!(
+            summary,
+            vec![
+                (alice, 0, NodeType::PrivateDirectory, ShareKind::Temporal),
+                (alice, 1, NodeType::PrivateDirectory, ShareKind::Temporal),
+                (alice, 1, NodeType::PrivateFile, ShareKind::Snapshot),
+                (bob, 0, NodeType::PrivateFile, ShareKind::Snapshot),
+            ]
+        );
+    }
+
+    #[async_std::test]
+    async fn receive_share_refuses_expired_shares() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let sharer_root_did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let sharer_dir = helper::create_sharer_dir(forest, store, rng).await.unwrap();
+        let access_key = sharer_dir
+            .as_node()
+            .store(forest, store, rng)
+            .await
+            .unwrap();
+
+        let recipient_key = X25519PrivateKey::new();
+        let exchange_key = recipient_key.get_public_key().to_encoded();
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        exchange_root
+            .write(
+                &["device1".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+                exchange_key.as_bytes().to_vec(),
+                Utc::now(),
+                store,
+            )
+            .await
+            .unwrap();
+
+        let expires_at = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        sharer::share_with_expiry::<X25519PublicKey>(
+            &access_key,
+            expires_at,
+            0,
+            sharer_root_did,
+            PublicLink::with_rc_dir(exchange_root),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        let share_label =
+            sharer::create_share_name(0, sharer_root_did, exchange_key.as_bytes(), forest);
+
+        let before = expires_at - Duration::seconds(1);
+        let node = recipient::receive_share(&share_label, &recipient_key, before, forest, store)
+            .await
+            .unwrap();
+        assert_eq!(node.into_node().unwrap().as_dir().unwrap(), sharer_dir);
+
+        let error =
+            recipient::receive_share(&share_label, &recipient_key, expires_at, forest, store)
+                .await
+                .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ShareError>(),
+            Some(ShareError::ShareExpired(time)) if *time == expires_at
+        ));
+
+        let entries = recipient::list_inbox(
+            &[sharer_root_did],
+            &[(exchange_key, recipient_key)],
+            forest,
+            store,
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].expires_at, Some(expires_at));
+    }
+
+    #[async_std::test]
+    async fn unshare_withdraws_share_and_revokes_access() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let sharer_root_did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let path = ["shared".into(), "notes.txt".into()];
+        root_dir
+            .write(
+                &path,
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await
+            .unwrap();
+        root_dir.as_node().store(forest, store, rng).await.unwrap();
+
+        let shared = root_dir
+            .get_node(&path[..1], true, forest, store)
+            .await
+            .unwrap()
+            .unwrap();
+        let access_key = shared.store(forest, store, rng).await.unwrap();
+
+        let recipient_key = X25519PrivateKey::new();
+        let exchange_key = recipient_key.get_public_key().to_encoded();
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        exchange_root
+            .write(
+                &["device1".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+                exchange_key.as_bytes().to_vec(),
+                Utc::now(),
+                store,
+            )
+            .await
+            .unwrap();
+        let exchange_root = PublicLink::with_rc_dir(exchange_root);
+
+        for counter in 0..2 {
+            sharer::share::<X25519PublicKey>(
+                &access_key,
+                counter,
+                sharer_root_did,
+                exchange_root.clone(),
+                forest,
+                store,
+                rng,
+            )
+            .await
+            .unwrap();
+        }
+
+        let status = |counter| {
+            sharer::share_status(
+                counter,
+                sharer_root_did,
+                exchange_key.as_bytes(),
+                forest,
+                store,
+            )
+        };
+        assert_eq!(status(0).await.unwrap(), ShareStatus::Pending);
+        assert_eq!(status(2).await.unwrap(), ShareStatus::NotShared);
+
+        // Recipient received the share before it was withdrawn.
+        let share_label =
+            sharer::create_share_name(0, sharer_root_did, exchange_key.as_bytes(), forest);
+        let received =
+            recipient::receive_share(&share_label, &recipient_key, Utc::now(), forest, store)
+                .await
+                .unwrap();
+
+        sharer::unshare::<X25519PublicKey>(
+            0,
+            sharer_root_did,
+            exchange_root,
+            root_dir,
+            &path[..1],
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+        root_dir
+            .write(&path, true, Utc::now(), b"Bye".to_vec(), forest, store, rng)
+            .await
+            .unwrap();
+        root_dir.as_node().store(forest, store, rng).await.unwrap();
+
+        let status = |counter| {
+            sharer::share_status(
+                counter,
+                sharer_root_did,
+                exchange_key.as_bytes(),
+                forest,
+                store,
+            )
+        };
+        assert_eq!(status(0).await.unwrap(), ShareStatus::Revoked);
+        assert_eq!(status(1).await.unwrap(), ShareStatus::Pending);
+
+        let error =
+            recipient::receive_share(&share_label, &recipient_key, Utc::now(), forest, store)
+                .await
+                .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ShareError>(),
+            Some(ShareError::AccessKeyNotFound)
+        ));
+
+        // Later shares can still be found behind the withdrawn one.
+        let latest =
+            search_latest_share_counter(0, exchange_key.as_bytes(), sharer_root_did, forest, store)
+                .await
+                .unwrap();
+        assert_eq!(latest, Some(1));
+
+        // The previously received node doesn't lead to the new revisions.
+        let latest_received = received.search_latest(forest, store).await.unwrap();
+        let content = latest_received
+            .as_dir()
+            .unwrap()
+            .read(&path[1..], false, forest, store)
+            .await
+            .unwrap();
+        assert_eq!(content, b"Hello");
+        assert_eq!(
+            root_dir.read(&path, true, forest, store).await.unwrap(),
+            b"Bye"
+        );
+    }
+
+    #[async_std::test]
+    async fn snapshot_shares_only_give_access_to_one_revision() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+
+        let sharer_root_did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+        let mut sharer_dir = helper::create_sharer_dir(forest, store, rng).await.unwrap();
+        let access_key = sharer_dir
+            .as_node()
+            .store_snapshot(forest, store, rng)
+            .await
+            .unwrap();
+
+        let recipient_key = X25519PrivateKey::new();
+        let exchange_key = recipient_key.get_public_key().to_encoded();
+        let mut exchange_root = PublicDirectory::new_rc(Utc::now());
+        exchange_root
+            .write(
+                &["device1".into(), TYPED_EXCHANGE_KEY_NAME.into()],
+                exchange_key.as_bytes().to_vec(),
+                Utc::now(),
+                store,
+            )
+            .await
+            .unwrap();
+
+        sharer::share::<X25519PublicKey>(
+            &access_key,
+            0,
+            sharer_root_did,
+            PublicLink::with_rc_dir(exchange_root),
+            forest,
+            store,
+            rng,
+        )
+        .await
+        .unwrap();
+
+        sharer_dir
+            .write(
+                &["text.txt".into()],
+                true,
+                Utc::now(),
+                b"Changed".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await
+            .unwrap();
+        sharer_dir
+            .as_node()
+            .store(forest, store, rng)
+            .await
+            .unwrap();
+
+        let share_label =
+            sharer::create_share_name(0, sharer_root_did, exchange_key.as_bytes(), forest);
+        let pointer = recipient::receive_share_pointer(
+            &share_label,
+            &recipient_key,
+            Utc::now(),
+            forest,
+            store,
+        )
+        .await
+        .unwrap();
+        assert_eq!(pointer.get_node_type(), NodeType::SnapshotSharePointer);
+        assert_eq!(pointer.sharer_root_did.as_deref(), Some(sharer_root_did));
+
+        let shared = pointer.load(forest, store).await.unwrap();
+        assert!(shared.is_snapshot());
+        let error = shared.search_latest(forest, store).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AccessKeyError>(),
+            Some(AccessKeyError::SnapshotOnly)
+        ));
+
+        let recipient::SharedNode::Snapshot(snapshot) = shared else {
+            panic!("Expected a snapshot")
+        };
+        let content = snapshot
+            .read(&["text.txt".into()], forest, store)
+            .await
+            .unwrap();
+        assert_eq!(content, b"Hello World!");
+    }
+
+    #[async_std::test]
+    async fn share_pointers_can_be_encrypted_and_decrypted() {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::new();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let dir =
+            PrivateDirectory::new_and_store(&forest.empty_name(), Utc::now(), forest, store, rng)
+                .await
+                .unwrap();
+        let access_key = dir.as_node().store(forest, store, rng).await.unwrap();
+
+        let shared_at = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let pointer = SharePointer::new(
+            access_key.clone(),
+            "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            shared_at,
+        )
+        .with_suggested_name("Photos from our trip to the mountains")
+        .with_expiry(shared_at + Duration::days(7));
+
+        // RSA-OAEP can only encrypt 190 bytes, the pointer itself is larger.
+        let rsa_key = RsaPrivateKey::new().unwrap();
+        let encrypted = pointer
+            .encrypt(&rsa_key.get_public_key(), rng)
+            .await
+            .unwrap();
+        assert_eq!(
+            SharePointer::decrypt(&encrypted, &rsa_key).await.unwrap(),
+            pointer
+        );
+
+        let x25519_key = X25519PrivateKey::new();
+        let encrypted = pointer
+            .encrypt(&x25519_key.get_public_key(), rng)
+            .await
+            .unwrap();
+        assert_eq!(
+            SharePointer::decrypt(&encrypted, &x25519_key)
+                .await
+                .unwrap(),
+            pointer
+        );
+        assert!(SharePointer::decrypt(&encrypted, &X25519PrivateKey::new())
+            .await
+            .is_err());
+
+        // Shares from before share pointers existed only hold an access key.
+        let legacy = rsa_key
+            .get_public_key()
+            .encrypt(&access_key.to_bytes().unwrap())
+            .await
+            .unwrap();
+        let legacy_pointer = SharePointer::decrypt(&legacy, &rsa_key).await.unwrap();
+        assert_eq!(legacy_pointer.access_key, access_key);
+        assert_eq!(legacy_pointer.sharer_root_did, None);
+        assert_eq!(legacy_pointer.shared_at, None);
+
+        // Expiring shares from before share pointers existed hold a payload.
+        let payload = SharePayload::with_expiry(access_key.clone(), shared_at);
+        let legacy = rsa_key
+            .get_public_key()
+            .encrypt(&payload.to_bytes().unwrap())
+            .await
+            .unwrap();
+        let legacy_pointer = SharePointer::decrypt(&legacy, &rsa_key).await.unwrap();
+        assert_eq!(legacy_pointer, SharePointer::from(payload));
+        assert!(legacy_pointer.is_expired(shared_at));
+    }
 }
@@ -0,0 +1,284 @@
+use super::{
+    forest::traits::PrivateForest, FileContent, PrivateNode, PrivateNodeContentSerializable,
+    SnapshotAccessKey,
+};
+use crate::{
+    error::{AccessKeyError, FsError},
+    is_readable_wnfs_version,
+};
+use anyhow::{bail, Result};
+use async_recursion::async_recursion;
+use wnfs_common::{BlockStore, Metadata, NodeType};
+
+//--------------------------------------------------------------------------------------------------
+// Type Definitions
+//--------------------------------------------------------------------------------------------------
+
+/// A single, read-only revision of a private file or directory.
+///
+/// This is what a [`SnapshotAccessKey`] gives access to. Unlike a [`PrivateNode`], it
+/// can't be used to look up newer or older revisions, as the key to the node's header
+/// and its links to previous revisions is missing. Children of a snapshot directory
+/// are snapshots themselves.
+///
+/// # Examples
+///
+/// ```
+/// use anyhow::Result;
+/// use chrono::Utc;
+/// use rand_chacha::ChaCha12Rng;
+/// use rand_core::SeedableRng;
+/// use wnfs::{
+///     private::{
+///         AccessKey, PrivateDirectory, SnapshotNode,
+///         forest::{hamt::HamtForest, traits::PrivateForest},
+///     },
+///     common::MemoryBlockStore,
+/// };
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let store = &MemoryBlockStore::default();
+///     let rng = &mut ChaCha12Rng::from_entropy();
+///     let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+///     let dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+///
+///     dir.write(&["hello.txt".into()], true, Utc::now(), b"Hello".to_vec(), forest, store, rng)
+///         .await?;
+///
+///     let AccessKey::Snapshot(access_key) = dir.as_node().store_snapshot(forest, store, rng).await? else {
+///         unreachable!()
+///     };
+///
+///     let snapshot = SnapshotNode::load(&access_key, store).await?;
+///
+///     assert_eq!(snapshot.read(&["hello.txt".into()], forest, store).await?, b"Hello");
+///     assert!(snapshot.search_latest().is_err());
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SnapshotNode {
+    access_key: SnapshotAccessKey,
+    content: PrivateNodeContentSerializable,
+}
+
+//--------------------------------------------------------------------------------------------------
+// Implementations
+//--------------------------------------------------------------------------------------------------
+
+impl SnapshotNode {
+    /// Loads the revision of a node that given snapshot access key points to.
+    pub async fn load(access_key: &SnapshotAccessKey, store: &impl BlockStore) -> Result<Self> {
+        let encrypted_bytes = store.get_block(&access_key.content_cid).await?;
+        let bytes = access_key.snapshot_key.decrypt(&encrypted_bytes)?;
+        let content: PrivateNodeContentSerializable = serde_ipld_dagcbor::from_slice(&bytes)?;
+
+        let version = match &content {
+            PrivateNodeContentSerializable::File(file) => &file.version,
+            PrivateNodeContentSerializable::Dir(dir) => &dir.version,
+        };
+
+        if !is_readable_wnfs_version(version) {
+            bail!(FsError::UnexpectedVersion(version.clone()));
+        }
+
+        Ok(Self {
+            access_key: access_key.clone(),
+            content,
+        })
+    }
+
+    /// Gets the access key this snapshot was loaded with.
+    pub fn get_access_key(&self) -> &SnapshotAccessKey {
+        &self.access_key
+    }
+
+    /// Returns either `NodeType::PrivateFile` or `NodeType::PrivateDirectory`.
+    pub fn get_node_type(&self) -> NodeType {
+        match &self.content {
+            PrivateNodeContentSerializable::File(_) => NodeType::PrivateFile,
+            PrivateNodeContentSerializable::Dir(_) => NodeType::PrivateDirectory,
+        }
+    }
+
+    /// Returns true if this is a snapshot of a file.
+    pub fn is_file(&self) -> bool {
+        matches!(self.content, PrivateNodeContentSerializable::File(_))
+    }
+
+    /// Returns true if this is a snapshot of a directory.
+    pub fn is_dir(&self) -> bool {
+        matches!(self.content, PrivateNodeContentSerializable::Dir(_))
+    }
+
+    /// Gets the metadata of the node at this revision.
+    pub fn get_metadata(&self) -> &Metadata {
+        match &self.content {
+            PrivateNodeContentSerializable::File(file) => &file.metadata,
+            PrivateNodeContentSerializable::Dir(dir) => &dir.metadata,
+        }
+    }
+
+    /// Lists the names of the entries of a directory snapshot.
+    pub fn ls(&self) -> Result<Vec<String>> {
+        match &self.content {
+            PrivateNodeContentSerializable::Dir(dir) => Ok(dir.entries.keys().cloned().collect()),
+            PrivateNodeContentSerializable::File(_) => bail!(FsError::NotADirectory),
+        }
+    }
+
+    /// Loads the snapshot of the directory entry with given name, at the revision
+    /// the directory snapshot refers to.
+    pub async fn lookup_node(
+        &self,
+        name: &str,
+        store: &impl BlockStore,
+    ) -> Result<Option<SnapshotNode>> {
+        let PrivateNodeContentSerializable::Dir(dir) = &self.content else {
+            bail!(FsError::NotADirectory)
+        };
+
+        let Some(private_ref) = dir.entries.get(name) else {
+            return Ok(None);
+        };
+
+        let access_key = SnapshotAccessKey {
+            label: private_ref.label,
+            content_cid: private_ref.content_cid,
+            snapshot_key: private_ref.snapshot_key.clone(),
+        };
+
+        Ok(Some(Self::load(&access_key, store).await?))
+    }
+
+    /// Follows a path and loads the snapshot of the node at the end of the path.
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    pub async fn get_node(
+        &self,
+        path_segments: &[String],
+        store: &impl BlockStore,
+    ) -> Result<Option<SnapshotNode>> {
+        let Some((head, tail)) = path_segments.split_first() else {
+            return Ok(Some(self.clone()));
+        };
+
+        match self.lookup_node(head, store).await? {
+            Some(node) => node.get_node(tail, store).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the entire content of a file snapshot.
+    pub async fn get_content(
+        &self,
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Vec<u8>> {
+        let PrivateNodeContentSerializable::File(file) = &self.content else {
+            bail!(FsError::NotAFile)
+        };
+
+        match &file.content {
+            FileContent::Inline { data } => Ok(data.clone()),
+            FileContent::External(content) => content.get_content(forest, store).await,
+        }
+    }
+
+    /// Reads the content of the file snapshot at given path.
+    pub async fn read(
+        &self,
+        path_segments: &[String],
+        forest: &impl PrivateForest,
+        store: &impl BlockStore,
+    ) -> Result<Vec<u8>> {
+        match self.get_node(path_segments, store).await? {
+            Some(node) => node.get_content(forest, store).await,
+            None => bail!(FsError::NotFound),
+        }
+    }
+
+    /// Snapshots only give access to a single revision, so this always fails.
+    ///
+    /// It exists so that code written against [`PrivateNode`] fails with a clear
+    /// error when it's handed a snapshot.
+    pub fn search_latest(&self) -> Result<PrivateNode> {
+        bail!(AccessKeyError::SnapshotOnly)
+    }
+
+    /// Snapshots only give access to a single revision, so this always fails.
+    ///
+    /// See [`SnapshotNode::search_latest`].
+    pub fn get_previous(&self) -> Result<SnapshotNode> {
+        bail!(AccessKeyError::SnapshotOnly)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Tests
+//--------------------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::private::{forest::hamt::HamtForest, AccessKey, PrivateDirectory};
+    use chrono::Utc;
+    use rand_chacha::ChaCha12Rng;
+    use rand_core::SeedableRng;
+    use testresult::TestResult;
+    use wnfs_common::MemoryBlockStore;
+
+    #[async_std::test]
+    async fn snapshot_stays_at_the_shared_revision() -> TestResult {
+        let rng = &mut ChaCha12Rng::seed_from_u64(0);
+        let store = &MemoryBlockStore::default();
+        let forest = &mut HamtForest::new_rsa_2048_rc(rng);
+        let root_dir = &mut PrivateDirectory::new_rc(&forest.empty_name(), Utc::now(), rng);
+        let path = ["docs".into(), "notes.txt".into()];
+
+        root_dir
+            .write(
+                &path,
+                true,
+                Utc::now(),
+                b"Hello".to_vec(),
+                forest,
+                store,
+                rng,
+            )
+            .await?;
+        let temporal_key = root_dir.as_node().store(forest, store, rng).await?;
+        let AccessKey::Snapshot(access_key) = temporal_key.to_snapshot() else {
+            panic!("Expected a snapshot access key")
+        };
+
+        root_dir
+            .write(&path, true, Utc::now(), b"Bye".to_vec(), forest, store, rng)
+            .await?;
+        root_dir.as_node().store(forest, store, rng).await?;
+
+        let snapshot = SnapshotNode::load(&access_key, store).await?;
+        assert!(snapshot.is_dir());
+        assert_eq!(snapshot.ls()?, vec!["docs".to_string()]);
+        assert_eq!(snapshot.read(&path, forest, store).await?, b"Hello");
+
+        let docs = snapshot.get_node(&path[..1], store).await?.unwrap();
+        assert_eq!(docs.get_node_type(), NodeType::PrivateDirectory);
+        assert!(docs.get_content(forest, store).await.is_err());
+        assert!(snapshot
+            .get_node(&["missing".into()], store)
+            .await?
+            .is_none());
+
+        let error = snapshot.search_latest().unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AccessKeyError>(),
+            Some(AccessKeyError::SnapshotOnly)
+        ));
+        assert!(snapshot.get_previous().is_err());
+
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@
 
 use super::{
     PublicDirectorySerializable, PublicFile, PublicLink, PublicNode, PublicNodeSerializable,
+    UNIXFS_SYMLINK_METADATA_KEY,
 };
 use crate::{
     error::FsError,
@@ -14,15 +15,17 @@ use anyhow::{bail, ensure, Result};
 use async_once_cell::OnceCell;
 use async_recursion::async_recursion;
 use chrono::{DateTime, Utc};
-use libipld_core::cid::Cid;
+use futures::{stream, StreamExt, TryStreamExt};
+use libipld_core::{cid::Cid, ipld::Ipld};
 use std::{
     cmp::Ordering,
     collections::{btree_map::Entry, BTreeMap, BTreeSet},
 };
 use wnfs_common::{
     utils::{boxed_fut, error, Arc},
-    BlockStore, Metadata, NodeType, Storable,
+    BlockStore, Link, Metadata, NodeType, Storable,
 };
+use wnfs_unixfs_file::directory::{cumulative_size, store_symlink, DirectoryBuilder, UnixFsEntry};
 
 //--------------------------------------------------------------------------------------------------
 // Type Definitions
@@ -970,6 +973,224 @@ impl PublicDirectory {
 
         Ok(())
     }
+
+    /// Stores this directory and all of its unstored descendants in the
+    /// block store, storing up to `concurrency` sibling subtrees at a time.
+    ///
+    /// The resulting CID is the same as the one returned by `store`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wnfs::{
+    ///     public::PublicDirectory,
+    ///     common::{MemoryBlockStore, Storable},
+    /// };
+    /// use chrono::Utc;
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> anyhow::Result<()> {
+    ///     let time = Utc::now();
+    ///     let store = &MemoryBlockStore::new();
+    ///     let dir = &mut PublicDirectory::new_rc(time);
+    ///
+    ///     for name in ["a", "b", "c"] {
+    ///         dir.write(&[name.into(), "file.txt".into()], b"Hello".to_vec(), time, store)
+    ///             .await?;
+    ///     }
+    ///
+    ///     let cid = dir.store_concurrent(4, store).await?;
+    ///     let loaded = PublicDirectory::load(&cid, store).await?;
+    ///
+    ///     assert_eq!(loaded.ls(&[], store).await?.len(), 3);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    pub async fn store_concurrent(
+        &self,
+        concurrency: usize,
+        store: &impl BlockStore,
+    ) -> Result<Cid> {
+        if let Some(cid) = self.persisted_as.get() {
+            return Ok(*cid);
+        }
+
+        let children = self
+            .userland
+            .values()
+            .map(|link| async move {
+                match link.get_value() {
+                    Some(PublicNode::Dir(dir)) if link.get_cid().is_none() => {
+                        dir.store_concurrent(concurrency, store).await
+                    }
+                    _ => link.resolve_cid(store).await,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        stream::iter(children)
+            .buffer_unordered(concurrency.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        self.store(store).await
+    }
+
+    /// Exports this directory as a plain UnixFS directory tree, returning the root CID.
+    ///
+    /// The resulting dag-pb DAG can be browsed with regular IPFS tooling and HTTP gateways.
+    /// File contents aren't copied, the exported directories link to the existing content CIDs.
+    /// Large directories are exported as HAMT-sharded directories.
+    ///
+    /// WNFS-specific information like metadata and previous links is not exported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use wnfs::{public::PublicDirectory, common::MemoryBlockStore};
+    /// use wnfs_unixfs_file::directory::resolve_path;
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let dir = &mut PublicDirectory::new_rc(Utc::now());
+    ///     let path = ["pictures".into(), "cats".into(), "tabby.png".into()];
+    ///     dir.write(&path, b"Hello, World!".to_vec(), Utc::now(), store).await?;
+    ///
+    ///     let root = dir.export_unixfs(store).await?;
+    ///
+    ///     assert!(resolve_path(&root, &path, store).await?.is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn export_unixfs(&self, store: &impl BlockStore) -> Result<Cid> {
+        let (cid, _) = self.export_unixfs_with_tsize(store).await?;
+        Ok(cid)
+    }
+
+    /// Exports this directory as a UnixFS directory tree, returning the root CID
+    /// and the cumulative size of the exported DAG, for linking to it.
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    async fn export_unixfs_with_tsize(&self, store: &impl BlockStore) -> Result<(Cid, u64)> {
+        let mut builder = DirectoryBuilder::new();
+        for (name, link) in self.userland.iter() {
+            let (cid, tsize) = match link.resolve_value(store).await? {
+                PublicNode::Dir(dir) => dir.export_unixfs_with_tsize(store).await?,
+                PublicNode::File(file) => {
+                    let cid = match file.metadata.get(UNIXFS_SYMLINK_METADATA_KEY) {
+                        Some(Ipld::String(target)) => store_symlink(target, store).await?,
+                        _ => file.userland.resolve_cid(store).await?,
+                    };
+                    (cid, cumulative_size(&cid, store).await?)
+                }
+            };
+
+            builder = builder.add_link(name, cid, Some(tsize));
+        }
+
+        builder.build()?.store_with_tsize(store).await
+    }
+
+    /// Imports the UnixFS DAG at given CID as a new node at given path.
+    ///
+    /// The DAG may be a file, a directory, a HAMT-sharded directory or a symlink.
+    /// Files keep their existing content CIDs. Symlinks are imported as empty files
+    /// with their target stored in the `UNIXFS_SYMLINK_METADATA_KEY` metadata entry.
+    ///
+    /// Intermediate directories are created if they don't exist.
+    /// Fails if there already is a node at given path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use chrono::Utc;
+    /// use wnfs::{public::PublicDirectory, common::MemoryBlockStore};
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<()> {
+    ///     let store = &MemoryBlockStore::new();
+    ///     let dir = &mut PublicDirectory::new_rc(Utc::now());
+    ///     let path = ["pictures".into(), "cats".into(), "tabby.png".into()];
+    ///     dir.write(&path, b"Hello, World!".to_vec(), Utc::now(), store).await?;
+    ///     let root = dir.export_unixfs(store).await?;
+    ///
+    ///     let imported = &mut PublicDirectory::new_rc(Utc::now());
+    ///     imported
+    ///         .import_unixfs(&["backup".into()], &root, Utc::now(), store)
+    ///         .await?;
+    ///
+    ///     let content = imported
+    ///         .read(&["backup".into(), "pictures".into(), "cats".into(), "tabby.png".into()], store)
+    ///         .await?;
+    ///
+    ///     assert_eq!(content, b"Hello, World!");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn import_unixfs(
+        self: &mut Arc<Self>,
+        path_segments: &[String],
+        cid: &Cid,
+        time: DateTime<Utc>,
+        store: &impl BlockStore,
+    ) -> Result<()> {
+        let (path, name) = utils::split_last(path_segments)?;
+        let node = Self::node_from_unixfs(cid, time, store).await?;
+        let dir = self.get_or_create_leaf_dir_mut(path, time, store).await?;
+
+        match dir.userland.entry(name.clone()) {
+            Entry::Occupied(occupied) => match occupied.get().resolve_value(store).await? {
+                PublicNode::Dir(_) => bail!(FsError::DirectoryAlreadyExists),
+                PublicNode::File(_) => bail!(FsError::FileAlreadyExists),
+            },
+            Entry::Vacant(vacant) => {
+                vacant.insert(PublicLink::new(node));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_recursion)]
+    #[cfg_attr(target_arch = "wasm32", async_recursion(?Send))]
+    async fn node_from_unixfs(
+        cid: &Cid,
+        time: DateTime<Utc>,
+        store: &impl BlockStore,
+    ) -> Result<PublicNode> {
+        Ok(match UnixFsEntry::load(cid, store).await? {
+            UnixFsEntry::File(_) => {
+                let mut file = PublicFile::new(time);
+                file.userland = Link::from_cid(*cid);
+                PublicNode::File(Arc::new(file))
+            }
+            UnixFsEntry::Symlink(target) => {
+                let mut file = PublicFile::new(time);
+                file.metadata
+                    .put(UNIXFS_SYMLINK_METADATA_KEY, Ipld::String(target));
+                PublicNode::File(Arc::new(file))
+            }
+            UnixFsEntry::Directory(unixfs_dir) => {
+                let mut dir = Self::new(time);
+                for link in unixfs_dir.entries(store).await? {
+                    let name = link.name.ok_or(FsError::InvalidPath)?;
+                    let node = Self::node_from_unixfs(&link.cid, time, store).await?;
+                    dir.userland.insert(name, PublicLink::new(node));
+                }
+
+                PublicNode::Dir(Arc::new(dir))
+            }
+        })
+    }
 }
 
 impl std::fmt::Debug for PublicDirectory {
@@ -1084,7 +1305,12 @@ mod tests {
     use super::*;
     use libipld_core::ipld::Ipld;
     use testresult::TestResult;
-    use wnfs_common::{decode, libipld::cbor::DagCborCodec, MemoryBlockStore};
+    use wnfs_common::{
+        decode,
+        libipld::{cbor::DagCborCodec, multihash::Code},
+        HashingBlockStore, MemoryBlockStore, VerifyingBlockStore,
+    };
+    use wnfs_unixfs_file::directory::{resolve_path, UnixFsDirectory};
 
     #[async_std::test]
     async fn look_up_can_fetch_file_added_to_directory() -> TestResult {
@@ -1108,6 +1334,183 @@ mod tests {
         Ok(())
     }
 
+    #[async_std::test]
+    async fn store_concurrent_matches_store() -> TestResult {
+        let time = Utc::now();
+        let store = &MemoryBlockStore::new();
+        let paths = [
+            vec!["text.txt".into()],
+            vec!["music".into(), "jazz".into(), "a.mp3".into()],
+            vec!["music".into(), "rock".into(), "b.mp3".into()],
+            vec!["videos".into(), "movies".into(), "anime".into()],
+        ];
+
+        let sequential = &mut PublicDirectory::new_rc(time);
+        let concurrent = &mut PublicDirectory::new_rc(time);
+        for path in paths.iter() {
+            sequential
+                .write(path, b"Hello".to_vec(), time, store)
+                .await?;
+            concurrent
+                .write(path, b"Hello".to_vec(), time, store)
+                .await?;
+        }
+
+        let cid = concurrent.store_concurrent(2, store).await?;
+
+        assert_eq!(cid, sequential.store(store).await?);
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn mixed_hash_directories_can_be_loaded() -> TestResult {
+        let time = Utc::now();
+        let blake3_store = &MemoryBlockStore::new();
+        let sha2_store = &HashingBlockStore::new(blake3_store.clone(), Code::Sha2_256);
+
+        let root_dir = &mut PublicDirectory::new_rc(time);
+        root_dir
+            .write(&["a.txt".into()], b"A".to_vec(), time, blake3_store)
+            .await?;
+        let cid = root_dir.store(blake3_store).await?;
+
+        let root_dir = &mut Arc::new(PublicDirectory::load(&cid, sha2_store).await?);
+        root_dir
+            .write(&["b.txt".into()], b"B".to_vec(), time, sha2_store)
+            .await?;
+        let cid = root_dir.store(sha2_store).await?;
+        assert_eq!(cid.hash().code(), u64::from(Code::Sha2_256));
+
+        let store = &VerifyingBlockStore::new(blake3_store.clone());
+        let root_dir = PublicDirectory::load(&cid, store).await?;
+        assert_eq!(root_dir.read(&["a.txt".into()], store).await?, b"A");
+        assert_eq!(root_dir.read(&["b.txt".into()], store).await?, b"B");
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn unixfs_export_can_be_imported() -> TestResult {
+        let time = Utc::now();
+        let store = &MemoryBlockStore::new();
+        let root_dir = &mut PublicDirectory::new_rc(time);
+        let paths = [
+            vec!["text.txt".into()],
+            vec!["music".into(), "jazz".into(), "a.mp3".into()],
+            vec!["videos".into(), "movies".into(), "anime.mp4".into()],
+        ];
+        for path in paths.iter() {
+            root_dir
+                .write(path, path.join("/").into_bytes(), time, store)
+                .await?;
+        }
+        root_dir.mkdir(&["empty".into()], time, store).await?;
+
+        let exported = root_dir.export_unixfs(store).await?;
+        // The link to "music" covers the blocks of "music", "jazz" and all of "a.mp3"
+        let mut expected_tsize = 0;
+        for depth in 1..paths[1].len() {
+            let cid = resolve_path(&exported, &paths[1][..depth], store).await?;
+            expected_tsize += store.get_block(&cid.unwrap()).await?.len() as u64;
+        }
+        let file_cid = resolve_path(&exported, &paths[1], store).await?.unwrap();
+        expected_tsize += cumulative_size(&file_cid, store).await?;
+        let music_link = UnixFsDirectory::load(&exported, store)
+            .await?
+            .get("music", store)
+            .await?
+            .unwrap();
+        assert_eq!(music_link.tsize, Some(expected_tsize));
+
+        let file = root_dir
+            .get_node(&paths[1], store)
+            .await?
+            .unwrap()
+            .as_file()?;
+        assert_eq!(
+            resolve_path(&exported, &paths[1], store).await?,
+            Some(file.userland.resolve_cid(store).await?)
+        );
+
+        let imported = &mut PublicDirectory::new_rc(time);
+        imported
+            .import_unixfs(&["import".into()], &exported, time, store)
+            .await?;
+
+        for path in paths.iter() {
+            let imported_path = [&["import".to_string()], path.as_slice()].concat();
+            let content = imported.read(&imported_path, store).await?;
+            assert_eq!(content, path.join("/").into_bytes());
+        }
+
+        let entries = imported.ls(&["import".into()], store).await?;
+        assert_eq!(entries.len(), 4);
+
+        let result = imported
+            .import_unixfs(&["import".into()], &exported, time, store)
+            .await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn unixfs_export_keeps_mtime_of_new_files() -> TestResult {
+        use chrono::TimeZone;
+        use wnfs_unixfs_file::unixfs::UnixFsFile;
+
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let store = &MemoryBlockStore::new();
+        let root_dir = &mut PublicDirectory::new_rc(time);
+        let path = ["text.txt".into()];
+        root_dir
+            .write(&path, b"Hello".to_vec(), time, store)
+            .await?;
+
+        let exported = root_dir.export_unixfs(store).await?;
+        let file_cid = resolve_path(&exported, &path, store).await?.unwrap();
+        let unixfs = UnixFsFile::load(&file_cid, store).await?;
+
+        assert_eq!(
+            unixfs.mtime().map(|mtime| mtime.seconds),
+            Some(time.timestamp())
+        );
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn unixfs_symlinks_and_sharded_directories_can_be_imported() -> TestResult {
+        let time = Utc::now();
+        let store = &MemoryBlockStore::new();
+
+        let symlink = store_symlink("../target", store).await?;
+        let mut builder = DirectoryBuilder::new().hamt_threshold(0);
+        for i in 0..100 {
+            builder = builder.add_link(format!("link-{i}"), symlink, None);
+        }
+        let sharded = builder.build()?.store(store).await?;
+
+        let dir = &mut PublicDirectory::new_rc(time);
+        dir.import_unixfs(&["sharded".into()], &sharded, time, store)
+            .await?;
+
+        assert_eq!(dir.ls(&["sharded".into()], store).await?.len(), 100);
+
+        let exported = dir.export_unixfs(store).await?;
+        let link = resolve_path(&exported, &["sharded", "link-42"], store)
+            .await?
+            .unwrap();
+
+        assert_eq!(
+            UnixFsEntry::load(&link, store).await?,
+            UnixFsEntry::Symlink("../target".into())
+        );
+
+        Ok(())
+    }
+
     #[async_std::test]
     async fn look_up_cannot_fetch_file_not_added_to_directory() {
         let root = PublicDirectory::new(Utc::now());
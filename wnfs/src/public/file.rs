@@ -16,7 +16,14 @@ use wnfs_common::{
     utils::{Arc, CondSend},
     BlockStore, Link, Metadata, NodeType, Storable,
 };
-use wnfs_unixfs_file::{builder::FileBuilder, unixfs::UnixFsFile};
+use wnfs_unixfs_file::{builder::FileBuilder, protobufs::UnixTime, unixfs::UnixFsFile};
+
+/// The metadata key that holds the target path of files that represent UnixFS symlinks.
+///
+/// WNFS has no symlink nodes, so `PublicDirectory::import_unixfs` imports
+/// symlinks as empty files with this key set, and `PublicDirectory::export_unixfs`
+/// turns them back into UnixFS symlinks.
+pub const UNIXFS_SYMLINK_METADATA_KEY: &str = "unixfsSymlink";
 
 /// A file in the WNFS public file system.
 ///
@@ -95,6 +102,7 @@ impl PublicFile {
     ) -> Result<Self> {
         let content_cid = FileBuilder::new()
             .content_bytes(content)
+            .mtime(UnixTime::new(time.timestamp(), 0))
             .build()?
             .store(store)
             .await?;
@@ -155,6 +163,7 @@ impl PublicFile {
     ) -> Result<Self> {
         let content_cid = FileBuilder::new()
             .content_reader(FuturesAsyncReadCompatExt::compat(content))
+            .mtime(UnixTime::new(time.timestamp(), 0))
             .build()?
             .store(store)
             .await?;
@@ -434,14 +443,19 @@ impl PublicFile {
 
     /// Writes a new content cid to the file.
     /// This will create a new revision of the file.
+    ///
+    /// The modification time is also recorded in the UnixFS root node of the
+    /// content, so it's kept when the content is exported to plain IPFS.
     pub async fn set_content(
         &mut self,
         content: Vec<u8>,
         time: DateTime<Utc>,
         store: &impl BlockStore,
     ) -> Result<()> {
+        // Metadata only has second precision
         let content_cid = FileBuilder::new()
             .content_bytes(content)
+            .mtime(UnixTime::new(time.timestamp(), 0))
             .build()?
             .store(store)
             .await?;
@@ -674,6 +688,30 @@ mod tests {
             vec![previous_cid]
         );
     }
+
+    #[async_std::test]
+    async fn set_content_propagates_mtime_to_unixfs() {
+        let time = Utc::now();
+        let store = &MemoryBlockStore::default();
+
+        let file = &mut PublicFile::new_rc(time);
+        file.prepare_next_revision()
+            .set_content(b"Hello, World!".to_vec(), time, store)
+            .await
+            .unwrap();
+
+        let content_cid = file.get_raw_content_cid(store).await;
+        let unixfs = UnixFsFile::load(&content_cid, store).await.unwrap();
+
+        assert_eq!(
+            unixfs.mtime().map(|mtime| mtime.seconds),
+            file.get_metadata().get_modified().map(|t| t.timestamp())
+        );
+        assert_eq!(
+            file.read_at(0, None, store).await.unwrap(),
+            b"Hello, World!"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -74,6 +74,22 @@ impl PublicLink {
         self.0.resolve_owned_value(store).await
     }
 
+    /// Gets the cid data stored in type.
+    ///
+    /// NOTE: This does not attempt to get it from the store if it does not exist.
+    #[inline]
+    pub fn get_cid(&self) -> Option<&Cid> {
+        self.0.get_cid()
+    }
+
+    /// Gets the value stored in type.
+    ///
+    /// NOTE: This does not attempt to get it from the store if it does not exist.
+    #[inline]
+    pub fn get_value(&self) -> Option<&PublicNode> {
+        self.0.get_value()
+    }
+
     /// Compares two links for equality. Attempts to get them from store if they are not already cached.
     #[inline]
     pub async fn deep_eq(&self, other: &Self, store: &impl BlockStore) -> Result<bool> {
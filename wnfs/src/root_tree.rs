@@ -2,7 +2,8 @@ use crate::{
     error::FsError,
     private::{
         forest::{hamt::HamtForest, traits::PrivateForest},
-        AccessKey, PrivateDirectory, PrivateNode,
+        share::{read_device_exchange_keys, TYPED_EXCHANGE_KEY_NAME},
+        AccessKey, AnyExchangeKey, EncodedExchangeKey, ExchangeKey, PrivateDirectory, PrivateNode,
     },
     public::PublicDirectory,
     WNFS_VERSION,
@@ -407,6 +408,64 @@ impl<B: BlockStore> RootTree<B> {
         Ok(())
     }
 
+    /// Publishes an exchange key for given device in the exchange partition.
+    ///
+    /// The key is validated and written as `<device>/v2.exchange_key` with its key type
+    /// prefix. This replaces a typed exchange key previously published for the device,
+    /// but keeps a bare RSA modulus in `<device>/v1.exchange_key`, so a device can be
+    /// shared with by sharers that only know about either.
+    pub async fn add_exchange_key(&mut self, device: &str, key: &EncodedExchangeKey) -> Result<()> {
+        self.add_exchange_key_with(device, key, Utc::now()).await
+    }
+
+    /// Like `add_exchange_key`, but with the modification time of the written file given.
+    pub async fn add_exchange_key_with(
+        &mut self,
+        device: &str,
+        key: &EncodedExchangeKey,
+        time: DateTime<Utc>,
+    ) -> Result<()> {
+        if device.is_empty() || device.contains('/') {
+            bail!(FsError::InvalidPath);
+        }
+
+        AnyExchangeKey::from_encoded(key).await?;
+        let key = EncodedExchangeKey::new(key.key_type(), key.key_bytes());
+
+        self.exchange_root
+            .write(
+                &[device.to_string(), TYPED_EXCHANGE_KEY_NAME.into()],
+                key.as_bytes().to_vec(),
+                time,
+                &self.store,
+            )
+            .await
+    }
+
+    /// Removes all exchange keys published for given device.
+    pub async fn remove_exchange_key(&mut self, device: &str) -> Result<()> {
+        self.exchange_root
+            .rm(&[device.to_string()], &self.store)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lists the exchange keys published for each device in the exchange partition.
+    ///
+    /// A device's typed `v2.exchange_key` key is listed before its bare RSA modulus in
+    /// `v1.exchange_key`. Typed keys that can't be decoded are skipped.
+    pub async fn list_exchange_keys(&self) -> Result<Vec<(String, EncodedExchangeKey)>> {
+        let mut keys = Vec::new();
+        for (device, _) in self.exchange_root.ls(&[], &self.store).await? {
+            for key in read_device_exchange_keys(&self.exchange_root, &device, &self.store).await? {
+                keys.push((device.clone(), key));
+            }
+        }
+
+        Ok(keys)
+    }
+
     pub async fn store(&mut self) -> Result<Cid> {
         self.store_with(&mut ChaCha12Rng::from_entropy()).await
     }
@@ -455,6 +514,7 @@ impl<B: BlockStore> RootTree<B> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::private::{share::EXCHANGE_KEY_NAME, ExchangeKeyType, RsaPrivateKey};
 
     #[async_std::test]
     async fn test_roots_read_write() {
@@ -516,6 +576,115 @@ mod tests {
 
         assert_eq!(content, b"hello world".to_vec());
     }
+
+    #[async_std::test]
+    async fn test_exchange_key_management() {
+        let store = MemoryBlockStore::default();
+        let mut root_tree = RootTree::empty(store);
+        let x25519_key = EncodedExchangeKey::new(ExchangeKeyType::X25519, &[7; 32]);
+        let rsa_key = RsaPrivateKey::new().unwrap().get_public_key().to_encoded();
+
+        root_tree
+            .add_exchange_key("laptop", &x25519_key)
+            .await
+            .unwrap();
+        root_tree.add_exchange_key("phone", &rsa_key).await.unwrap();
+        root_tree
+            .write(
+                &["exchange".into(), "tablet".into(), EXCHANGE_KEY_NAME.into()],
+                rsa_key.key_bytes().to_vec(),
+            )
+            .await
+            .unwrap();
+
+        let content = root_tree
+            .read(&[
+                "exchange".into(),
+                "laptop".into(),
+                TYPED_EXCHANGE_KEY_NAME.into(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(content, x25519_key.as_bytes());
+
+        let keys = root_tree.list_exchange_keys().await.unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                ("laptop".into(), x25519_key.clone()),
+                ("phone".into(), rsa_key.clone()),
+                (
+                    "tablet".into(),
+                    EncodedExchangeKey::from_legacy_modulus(rsa_key.key_bytes().to_vec())
+                ),
+            ]
+        );
+
+        // Invalid keys and device names are rejected
+        let short_key = EncodedExchangeKey::new(ExchangeKeyType::X25519, &[7; 16]);
+        assert!(root_tree
+            .add_exchange_key("laptop", &short_key)
+            .await
+            .is_err());
+        assert!(root_tree.add_exchange_key("", &x25519_key).await.is_err());
+
+        // Adding a key only replaces the device's previous typed key
+        root_tree
+            .add_exchange_key("tablet", &rsa_key)
+            .await
+            .unwrap();
+        root_tree
+            .add_exchange_key("tablet", &x25519_key)
+            .await
+            .unwrap();
+        let tablet_files = root_tree
+            .ls(&["exchange".into(), "tablet".into()])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tablet_files,
+            vec![
+                EXCHANGE_KEY_NAME.to_string(),
+                TYPED_EXCHANGE_KEY_NAME.to_string()
+            ]
+        );
+        let tablet_keys = root_tree
+            .list_exchange_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|(device, _)| device == "tablet")
+            .map(|(_, key)| key)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tablet_keys,
+            vec![
+                x25519_key.clone(),
+                EncodedExchangeKey::from_legacy_modulus(rsa_key.key_bytes().to_vec())
+            ]
+        );
+
+        root_tree.remove_exchange_key("laptop").await.unwrap();
+        let devices = root_tree
+            .list_exchange_keys()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(device, _)| device)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            devices,
+            vec![
+                "phone".to_string(),
+                "tablet".to_string(),
+                "tablet".to_string()
+            ]
+        );
+        assert!(root_tree.remove_exchange_key("laptop").await.is_err());
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,7 @@
 use crate::error::FsError;
 use anyhow::Result;
+use rand_chacha::ChaCha12Rng;
+use rand_core::{CryptoRngCore, SeedableRng};
 use std::fmt::Debug;
 use wnfs_common::utils::error;
 
@@ -22,6 +24,17 @@ pub(crate) fn split_last(path_segments: &[String]) -> Result<(&[String], &String
     }
 }
 
+/// Derives a new, independent random number generator from given one.
+///
+/// This is used to hand out a separate RNG to each task of a concurrent operation,
+/// so the result stays reproducible when given a seeded RNG, no matter in which
+/// order these tasks finish.
+pub(crate) fn split_rng(rng: &mut impl CryptoRngCore) -> ChaCha12Rng {
+    let mut seed = <ChaCha12Rng as SeedableRng>::Seed::default();
+    rng.fill_bytes(&mut seed);
+    ChaCha12Rng::from_seed(seed)
+}
+
 //--------------------------------------------------------------------------------------------------
 // Tests
 //--------------------------------------------------------------------------------------------------